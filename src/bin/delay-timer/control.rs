@@ -0,0 +1,267 @@
+//! control
+//! The control protocol between the subcommands and the running daemon over a unix socket.
+//!
+//! The client sends one line of command, e.g. `next 3 5`, then the daemon replies
+//! `ok` or `error` in the first line, followed by the output or the error message.
+use crate::daemon::Schedule;
+use crate::format_timestamp;
+
+use delay_timer::prelude::*;
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::Builder;
+use std::time::Duration;
+
+use log::error;
+
+/// The time to wait for the command of client, the idle connection is closed after it.
+const COMMAND_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The command sent to the running daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// List the tasks.
+    List,
+    /// Show the next `count` fire times of the task.
+    Next(u64, usize),
+    /// Run the task now.
+    RunNow(u64),
+    /// Reload the crontab or config file.
+    Reload,
+}
+
+impl Command {
+    fn parse(line: &str) -> Option<Command> {
+        let mut words = line.split_whitespace();
+        let command = match (words.next()?, words.next(), words.next()) {
+            ("list", None, None) => Command::List,
+            ("next", Some(task_id), Some(count)) => {
+                Command::Next(task_id.parse().ok()?, count.parse().ok()?)
+            }
+            ("run-now", Some(task_id), None) => Command::RunNow(task_id.parse().ok()?),
+            ("reload", None, None) => Command::Reload,
+            _ => return None,
+        };
+
+        match words.next() {
+            Some(_) => None,
+            None => Some(command),
+        }
+    }
+
+    fn execute(&self, delay_timer: &DelayTimer, schedule: &Schedule) -> AnyResult<String> {
+        match *self {
+            Command::List => {
+                let tasks: Vec<(TaskInfo, Option<u64>)> = delay_timer
+                    .get_task_infos(TaskSelector::default())
+                    .into_iter()
+                    .map(|task_info| {
+                        let next_fire_time = delay_timer
+                            .get_next_fire_times(task_info.task_id, 1)
+                            .and_then(|fire_times| fire_times.first().copied());
+                        (task_info, next_fire_time)
+                    })
+                    .collect();
+                Ok(format_tasks(&tasks))
+            }
+            Command::Next(task_id, count) => {
+                let fire_times = delay_timer
+                    .get_next_fire_times(task_id, count)
+                    .ok_or(TaskError::TaskNotFound(task_id))?;
+                Ok(fire_times
+                    .into_iter()
+                    .map(|fire_time| format_timestamp(fire_time) + "\n")
+                    .collect())
+            }
+            Command::RunNow(task_id) => {
+                delay_timer.advance_task_with_ack(task_id)?;
+                Ok(format!("Task {} is triggered.\n", task_id))
+            }
+            Command::Reload => Ok(schedule.reload(delay_timer)? + "\n"),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::List => write!(f, "list"),
+            Command::Next(task_id, count) => write!(f, "next {} {}", task_id, count),
+            Command::RunNow(task_id) => write!(f, "run-now {}", task_id),
+            Command::Reload => write!(f, "reload"),
+        }
+    }
+}
+
+// The table of tasks and their next fire times.
+fn format_tasks(tasks: &[(TaskInfo, Option<u64>)]) -> String {
+    let mut output = format!(
+        "{:<6} {:<7} {:<8} {:<26} {:<24} {}\n",
+        "ID", "STATE", "RUNNING", "NEXT", "EXPRESSION", "NAME"
+    );
+
+    for (task_info, next_fire_time) in tasks.iter() {
+        let state = if task_info.paused { "paused" } else { "active" };
+        let next_fire_time = next_fire_time
+            .map(format_timestamp)
+            .unwrap_or_else(|| String::from("-"));
+        let name = task_info
+            .name
+            .as_deref()
+            .or(task_info.description.as_deref())
+            .unwrap_or("-");
+
+        output.push_str(&format!(
+            "{:<6} {:<7} {:<8} {:<26} {:<24} {}\n",
+            task_info.task_id,
+            state,
+            task_info.running_instance_num,
+            next_fire_time,
+            task_info.cron_expression,
+            name
+        ));
+    }
+    output
+}
+
+// Serve the commands on the socket in a new thread, every connection is handled by its own thread,
+// so a client that doesn't send the command can't block the others.
+pub(crate) fn serve(
+    socket: &Path,
+    delay_timer: DelayTimer,
+    schedule: Arc<Schedule>,
+) -> AnyResult<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(anyhow!(
+                "Another daemon is listening on {}.",
+                socket.display()
+            ));
+        }
+        // The socket is left by a daemon that didn't exit cleanly.
+        fs::remove_file(socket)?;
+    }
+
+    let listener = UnixListener::bind(socket)?;
+    Builder::new()
+        .name("control_server".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        error!("Can't accept the control connection: {}", e);
+                        continue;
+                    }
+                };
+
+                let delay_timer = delay_timer.clone();
+                let schedule = schedule.clone();
+                let result = Builder::new()
+                    .name("control_connection".into())
+                    .spawn(move || {
+                        if let Err(e) = handle_connection(stream, &delay_timer, &schedule) {
+                            error!("Can't handle the control connection: {}", e);
+                        }
+                    });
+                if let Err(e) = result {
+                    error!("Can't handle the control connection: {}", e);
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+fn handle_connection(
+    stream: UnixStream,
+    delay_timer: &DelayTimer,
+    schedule: &Schedule,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(COMMAND_READ_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+
+    let reply = match Command::parse(&line) {
+        Some(command) => match command.execute(delay_timer, schedule) {
+            Ok(output) => format!("ok\n{}", output),
+            Err(e) => format!("error\n{:#}\n", e),
+        },
+        None => format!("error\nUnknown command `{}`.\n", line.trim()),
+    };
+
+    (&stream).write_all(reply.as_bytes())
+}
+
+/// Send the command to the running daemon, return its output.
+pub(crate) fn send(socket: &Path, command: &Command) -> AnyResult<String> {
+    let mut stream = UnixStream::connect(socket)
+        .map_err(|e| anyhow!("Can't connect to the daemon on {}: {}", socket.display(), e))?;
+    writeln!(stream, "{}", command)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+
+    let (status, output) = match reply.find('\n') {
+        Some(index) => (&reply[..index], &reply[index + 1..]),
+        None => (reply.as_str(), ""),
+    };
+    match status {
+        "ok" => Ok(output.to_string()),
+        _ => Err(anyhow!("{}", output.trim_end())),
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_command_parse() {
+        use super::Command;
+
+        for command in [
+            Command::List,
+            Command::Next(3, 10),
+            Command::RunNow(3),
+            Command::Reload,
+        ]
+        .iter()
+        {
+            assert_eq!(Command::parse(&format!("{}\n", command)), Some(*command));
+        }
+
+        assert_eq!(Command::parse("next 3"), None);
+        assert_eq!(Command::parse("run-now x"), None);
+        assert_eq!(Command::parse("list all"), None);
+        assert_eq!(Command::parse(""), None);
+    }
+
+    #[test]
+    fn test_format_tasks() {
+        use super::format_tasks;
+        use delay_timer::prelude::*;
+
+        let task_info = TaskInfo {
+            task_id: 1,
+            name: None,
+            labels: Default::default(),
+            cron_expression: String::from("0 0 * * * * *"),
+            description: Some(String::from("every hour")),
+            running_instance_num: 2,
+            paused: true,
+        };
+
+        let output = format_tasks(&[(task_info, None)]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID     STATE   RUNNING  NEXT"));
+        assert!(lines[1].starts_with("1      paused  2        -  "));
+        assert!(lines[1].ends_with("0 0 * * * * *            every hour"));
+    }
+}
@@ -0,0 +1,442 @@
+//! daemon
+//! The daemon runs the tasks of the crontab or config file until SIGTERM or SIGINT,
+//! and reloads the file on SIGHUP.
+use crate::{control, logger};
+
+use delay_timer::prelude::*;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use structopt::StructOpt;
+
+/// The file name of the daemon log in the data directory.
+const LOG_FILE_NAME: &str = "delay-timer.log";
+
+/// The file name of the execution history in the data directory.
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// The directory of the output logs of task instances in the data directory.
+const OUTPUT_LOG_DIR_NAME: &str = "output";
+
+/// The file name of the ids of crontab tasks in the data directory.
+const TASK_ID_FILE_NAME: &str = "crontab-task-ids.json";
+
+/// The id of the first task spawned by crontab.
+const FIRST_TASK_ID: u64 = 1;
+
+#[derive(Debug, StructOpt)]
+pub(crate) struct DaemonOpt {
+    /// The crontab file of tasks.
+    #[structopt(
+        long,
+        parse(from_os_str),
+        required_unless = "config",
+        conflicts_with = "config"
+    )]
+    crontab: Option<PathBuf>,
+
+    /// Read the crontab in the system format, which has the user field.
+    #[structopt(long, requires = "crontab")]
+    system: bool,
+
+    /// The TOML/YAML config file of tasks, it's also reloaded when it changes.
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// Seconds to wait for the running instances on SIGTERM, then they are cancelled.
+    #[structopt(long, default_value = "30")]
+    drain_timeout: u64,
+}
+
+/// The file that the tasks are loaded from.
+#[derive(Debug)]
+pub(crate) enum Schedule {
+    Crontab(CrontabLoader),
+    Config,
+}
+
+impl Schedule {
+    // Load the tasks when the daemon starts, the config file is loaded by `DelayTimerBuilder`.
+    fn start(&self, delay_timer: &DelayTimer) -> AnyResult<()> {
+        if let Schedule::Crontab(crontab_loader) = self {
+            let report = crontab_loader.load(delay_timer, true)?;
+            info!("The crontab is loaded, tasks: {:?}.", report.added);
+        }
+        Ok(())
+    }
+
+    // Apply the changes of file, return the summary of changes.
+    pub(crate) fn reload(&self, delay_timer: &DelayTimer) -> AnyResult<String> {
+        let report = match self {
+            Schedule::Crontab(crontab_loader) => crontab_loader.load(delay_timer, false)?,
+            Schedule::Config => delay_timer.reload_config()?,
+        };
+
+        Ok(format!(
+            "Added tasks: {:?}, updated tasks: {:?}, removed tasks: {:?}.",
+            report.added, report.updated, report.removed
+        ))
+    }
+}
+
+/// The stable ids of crontab tasks, they are kept in the data directory,
+/// so the id of a task doesn't change when other entries are inserted or removed, or the daemon restarts.
+///
+/// The task is identified by the command of its entry and the index of its expression,
+/// so the task keeps its id when the schedule changes. The ids of removed tasks aren't reused.
+#[derive(Debug)]
+struct TaskIds {
+    path: PathBuf,
+    ids: BTreeMap<String, u64>,
+}
+
+impl TaskIds {
+    fn load(path: PathBuf) -> AnyResult<TaskIds> {
+        let ids = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Invalid task ids {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(TaskIds { path, ids })
+    }
+
+    // Get the ids of the tasks of every entry, one for each expression (`@reboot` has one).
+    // The identical entries are told apart by their occurrence.
+    fn assign(&mut self, entries: &[CrontabEntry]) -> Vec<Vec<u64>> {
+        let mut occurrences = HashMap::new();
+        entries
+            .iter()
+            .map(|entry| {
+                let occurrence = occurrences.entry(Self::key(entry, 0, 0)).or_insert(0);
+                let task_num = if entry.reboot {
+                    1
+                } else {
+                    entry.cron_expressions.len()
+                };
+                let task_ids = (0..task_num)
+                    .map(|index| self.get_or_insert(Self::key(entry, *occurrence, index)))
+                    .collect();
+                *occurrence += 1;
+                task_ids
+            })
+            .collect()
+    }
+
+    fn get_or_insert(&mut self, key: String) -> u64 {
+        if let Some(task_id) = self.ids.get(&key) {
+            return *task_id;
+        }
+
+        let task_id = self.ids.values().max().map_or(FIRST_TASK_ID, |id| id + 1);
+        self.ids.insert(key, task_id);
+        task_id
+    }
+
+    fn key(entry: &CrontabEntry, occurrence: usize, index: usize) -> String {
+        serde_json::json!([
+            entry.user,
+            entry.command,
+            entry.stdin,
+            entry.environment,
+            occurrence,
+            index
+        ])
+        .to_string()
+    }
+
+    // The file is replaced by renaming, so it's never left half written.
+    fn save(&self) -> AnyResult<()> {
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(&self.ids)?)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// The loader of crontab, it compares the entries with the applied ones,
+/// because the commands of tasks can't be compared by `DelayTimer::reconcile`.
+#[derive(Debug)]
+pub(crate) struct CrontabLoader {
+    path: PathBuf,
+    format: CrontabFormat,
+    // The expression and entry of the tasks that have been applied.
+    applied_tasks: Mutex<BTreeMap<u64, (String, CrontabEntry)>>,
+    task_ids: Mutex<TaskIds>,
+}
+
+impl CrontabLoader {
+    fn new(
+        path: PathBuf,
+        format: CrontabFormat,
+        task_id_path: PathBuf,
+    ) -> AnyResult<CrontabLoader> {
+        Ok(CrontabLoader {
+            path,
+            format,
+            applied_tasks: Mutex::new(BTreeMap::new()),
+            task_ids: Mutex::new(TaskIds::load(task_id_path)?),
+        })
+    }
+
+    // Nothing is changed if the crontab is invalid.
+    // The `@reboot` entries only run when the daemon starts, so they are skipped by reloading.
+    fn load(&self, delay_timer: &DelayTimer, starting: bool) -> AnyResult<ConfigReloadReport> {
+        let crontab_errors = |errors: Vec<CrontabError>| {
+            let messages: Vec<String> = errors
+                .into_iter()
+                .map(|e| format!("{:#}", anyhow::Error::from(e)))
+                .collect();
+            anyhow!(
+                "Invalid crontab {}: {}",
+                self.path.display(),
+                messages.join(" ")
+            )
+        };
+
+        let crontab = Crontab::from_file(&self.path, self.format).map_err(crontab_errors)?;
+        let mut applied_tasks = self
+            .applied_tasks
+            .lock()
+            .map_err(|_| anyhow!("Crontab loader lock is poisoned."))?;
+        let mut task_ids = self
+            .task_ids
+            .lock()
+            .map_err(|_| anyhow!("Crontab loader lock is poisoned."))?;
+
+        let mut desired_tasks = BTreeMap::new();
+        let mut spawned_tasks = Vec::new();
+        let mut errors = Vec::new();
+        let all_task_ids = task_ids.assign(crontab.entries());
+        for (entry, entry_task_ids) in crontab.entries().iter().zip(all_task_ids) {
+            let expressions = if entry.reboot {
+                vec![String::from("@reboot")]
+            } else {
+                entry.cron_expressions.clone()
+            };
+
+            if entry.reboot && !starting {
+                if let Some(applied_task) = applied_tasks.get(&entry_task_ids[0]) {
+                    desired_tasks.insert(entry_task_ids[0], applied_task.clone());
+                }
+                continue;
+            }
+
+            let changed =
+                expressions
+                    .iter()
+                    .zip(entry_task_ids.iter())
+                    .any(|(expression, task_id)| {
+                        applied_tasks.get(task_id) != Some(&(expression.clone(), entry.clone()))
+                    });
+
+            for (expression, task_id) in expressions.iter().zip(entry_task_ids.iter()) {
+                desired_tasks.insert(*task_id, (expression.clone(), entry.clone()));
+            }
+
+            if changed {
+                // The tasks are spawned in the order of expressions, then they're given the stable ids.
+                match entry.spawn_tasks(FIRST_TASK_ID) {
+                    Ok(tasks) => spawned_tasks.extend(tasks.into_iter().zip(entry_task_ids).map(
+                        |(mut task, task_id)| {
+                            task.task_id = task_id;
+                            task
+                        },
+                    )),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(crontab_errors(errors));
+        }
+        task_ids.save()?;
+
+        let mut report = ConfigReloadReport::default();
+
+        let removed_task_ids: Vec<u64> = applied_tasks
+            .keys()
+            .filter(|task_id| !desired_tasks.contains_key(task_id))
+            .copied()
+            .collect();
+        for task_id in removed_task_ids {
+            match delay_timer.remove_task_with_ack(task_id) {
+                Ok(()) | Err(TaskError::TaskNotFound(_)) => {}
+                Err(e) => return Err(anyhow!("Task {} can't be removed: {}", task_id, e)),
+            }
+            applied_tasks.remove(&task_id);
+            report.removed.push(task_id);
+        }
+
+        for task in spawned_tasks {
+            let task_id = task.task_id;
+            if applied_tasks.get(&task_id) == desired_tasks.get(&task_id) {
+                continue;
+            }
+
+            let result = if applied_tasks.contains_key(&task_id) {
+                report.updated.push(task_id);
+                delay_timer.update_task_with_ack(task)
+            } else {
+                report.added.push(task_id);
+                delay_timer.add_task_with_ack(task).map(|_| ())
+            };
+            result.map_err(|e| anyhow!("Task {} can't be applied: {}", task_id, e))?;
+
+            if let Some(desired_task) = desired_tasks.get(&task_id) {
+                applied_tasks.insert(task_id, desired_task.clone());
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Run the daemon until SIGTERM or SIGINT.
+pub(crate) fn run(daemon_opt: DaemonOpt, data_dir: PathBuf, socket: PathBuf) -> AnyResult<()> {
+    fs::create_dir_all(&data_dir)?;
+    logger::init(&data_dir.join(LOG_FILE_NAME))?;
+
+    let mut delay_timer_builder = DelayTimerBuilder::default()
+        .history_store(FileHistoryStore::new(data_dir.join(HISTORY_FILE_NAME)))
+        .output_log(OutputLogConfig::new(data_dir.join(OUTPUT_LOG_DIR_NAME)));
+
+    let schedule = match (daemon_opt.crontab, daemon_opt.config) {
+        (Some(crontab), _) => {
+            let format = if daemon_opt.system {
+                CrontabFormat::System
+            } else {
+                CrontabFormat::User
+            };
+            Schedule::Crontab(CrontabLoader::new(
+                crontab,
+                format,
+                data_dir.join(TASK_ID_FILE_NAME),
+            )?)
+        }
+        (None, Some(config)) => {
+            // Fail fast, the invalid file is only reported by log when it's reloaded.
+            ScheduleConfig::from_file(&config)?;
+            delay_timer_builder = delay_timer_builder.config_file(config);
+            Schedule::Config
+        }
+        (None, None) => return Err(anyhow!("Either `--crontab` or `--config` is required.")),
+    };
+
+    let delay_timer = delay_timer_builder.build();
+    schedule.start(&delay_timer)?;
+
+    let schedule = Arc::new(schedule);
+    control::serve(&socket, delay_timer.clone(), schedule.clone())?;
+    info!("The daemon is started, pid: {}.", process::id());
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+    for signal in signals.forever() {
+        if signal == SIGHUP {
+            match schedule.reload(&delay_timer) {
+                Ok(summary) => info!("The schedule is reloaded. {}", summary),
+                Err(e) => error!("Can't reload the schedule: {:#}", e),
+            }
+            continue;
+        }
+
+        info!(
+            "Signal {} is received, draining the running instances.",
+            signal
+        );
+        drain(&delay_timer, Duration::from_secs(daemon_opt.drain_timeout));
+        break;
+    }
+
+    let _ = fs::remove_file(&socket);
+    info!("The daemon is stopped.");
+    log::logger().flush();
+    Ok(())
+}
+
+// Stop deriving new instances and wait for the running instances,
+// the instances that are still running after `timeout` are cancelled.
+fn drain(delay_timer: &DelayTimer, timeout: Duration) {
+    if let Err(e) = delay_timer.pause_tasks(TaskSelector::default()) {
+        error!("Can't pause the tasks: {}", e);
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let running_instance_num: u64 = delay_timer
+            .get_task_infos(TaskSelector::default())
+            .iter()
+            .map(|task_info| task_info.running_instance_num)
+            .sum();
+
+        if running_instance_num == 0 {
+            break;
+        }
+
+        if Instant::now() >= deadline {
+            warn!(
+                "Cancel {} running instances after the drain timeout.",
+                running_instance_num
+            );
+            if let Err(e) = delay_timer.cancel_instances(TaskSelector::default()) {
+                error!("Can't cancel the running instances: {}", e);
+            }
+            // Give the cancelled processes a moment to be killed.
+            sleep(Duration::from_millis(500));
+            break;
+        }
+
+        sleep(Duration::from_millis(100));
+    }
+
+    if let Err(e) = delay_timer.stop_delay_timer() {
+        error!("Can't stop the timer: {}", e);
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_task_ids() -> delay_timer::prelude::AnyResult<()> {
+        use super::TaskIds;
+        use delay_timer::prelude::*;
+
+        let path =
+            std::env::temp_dir().join(format!("delay_timer_task_ids_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let assign = |task_ids: &mut TaskIds, content: &str| -> Vec<Vec<u64>> {
+            let crontab = Crontab::parse(content, CrontabFormat::User).unwrap();
+            task_ids.assign(crontab.entries())
+        };
+
+        let mut task_ids = TaskIds::load(path.clone())?;
+        assert_eq!(
+            assign(&mut task_ids, "* * * * * a\n* * * * * b\n* * * * * b\n"),
+            [[1], [2], [3]]
+        );
+        task_ids.save()?;
+
+        // The ids are kept when an entry is inserted before them and the schedule is changed,
+        // or the ids are loaded again.
+        let mut task_ids = TaskIds::load(path.clone())?;
+        assert_eq!(
+            assign(&mut task_ids, "* * * * * c\n0 * * * * a\n* * * * * b\n"),
+            [[4], [1], [2]]
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//! logger
+//! The logger of daemon, the records are appended to the log file in the data directory.
+use delay_timer::prelude::*;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} {}",
+                Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%z"),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+// Append the logs to the file, the level is `info` unless `RUST_LOG` is one of the level names.
+pub(crate) fn init(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+
+    let logger = Box::new(FileLogger {
+        file: Mutex::new(file),
+    });
+    log::set_logger(Box::leak(logger))
+        .map_err(|e| io::Error::new(io::ErrorKind::AlreadyExists, e.to_string()))?;
+    log::set_max_level(level);
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! delay-timer
+//! It is a cron daemon built on `delay_timer`, the schedule is read from a crontab or config file,
+//! and the running daemon is controlled by the subcommands over a unix socket.
+//!
+//! ```sh
+//! delay-timer --data-dir /var/lib/delay-timer daemon --crontab /etc/delay-timer.crontab
+//! delay-timer --data-dir /var/lib/delay-timer list
+//! delay-timer --data-dir /var/lib/delay-timer next 3 --count 10
+//! delay-timer --data-dir /var/lib/delay-timer run-now 3
+//! ```
+//!
+//! # Required features
+//!
+//! This binary requires the `daemon` feature of the `delay_timer` crate to be enabled.
+mod control;
+mod daemon;
+mod logger;
+
+use delay_timer::prelude::*;
+
+use std::path::PathBuf;
+use std::process;
+
+use structopt::StructOpt;
+
+use control::Command;
+
+/// The default directory of logs, history and the control socket.
+const DEFAULT_DATA_DIR: &str = "/var/lib/delay-timer";
+
+/// The file name of the control socket in the data directory.
+const SOCKET_FILE_NAME: &str = "delay-timer.sock";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "delay-timer", about = "A cron daemon built on delay_timer.")]
+struct Opt {
+    /// The directory of logs, history and the control socket.
+    #[structopt(long, parse(from_os_str), default_value = DEFAULT_DATA_DIR)]
+    data_dir: PathBuf,
+
+    /// The control socket, it's `delay-timer.sock` in the data directory by default.
+    #[structopt(long, parse(from_os_str))]
+    socket: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    subcommand: Subcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum Subcommand {
+    /// Run the daemon in the foreground.
+    Daemon(daemon::DaemonOpt),
+    /// List the tasks of the running daemon.
+    List,
+    /// Show the next fire times of a task.
+    Next {
+        /// The id of task.
+        task_id: u64,
+        /// The number of fire times.
+        #[structopt(long, default_value = "5")]
+        count: usize,
+    },
+    /// Run a task now.
+    RunNow {
+        /// The id of task.
+        task_id: u64,
+    },
+    /// Reload the crontab or config file.
+    Reload,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    let socket = opt
+        .socket
+        .clone()
+        .unwrap_or_else(|| opt.data_dir.join(SOCKET_FILE_NAME));
+
+    let command = match opt.subcommand {
+        Subcommand::Daemon(daemon_opt) => {
+            if let Err(e) = daemon::run(daemon_opt, opt.data_dir, socket) {
+                eprintln!("delay-timer: {:#}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Subcommand::List => Command::List,
+        Subcommand::Next { task_id, count } => Command::Next(task_id, count),
+        Subcommand::RunNow { task_id } => Command::RunNow(task_id),
+        Subcommand::Reload => Command::Reload,
+    };
+
+    match control::send(&socket, &command) {
+        Ok(output) => print!("{}", output),
+        Err(e) => {
+            eprintln!("delay-timer: {:#}", e);
+            process::exit(1);
+        }
+    }
+}
+
+// Format the timestamp in the local time zone.
+fn format_timestamp(timestamp: u64) -> String {
+    Local
+        .timestamp_opt(timestamp as i64, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S %z").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
@@ -17,15 +17,20 @@ use super::timer::{
 };
 use crate::prelude::*;
 use crate::timer::runtime_trace::task_instance::task_instance_chain_pair;
+use crate::utils::history::{ExecutionHistory, HistoryStoreWriter};
+use crate::utils::metrics::Metrics;
+use crate::utils::overflow::ChannelConfig;
 
+use std::collections::HashSet;
 use std::fmt;
-use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::AcqRel};
 use std::sync::Arc;
 use std::thread::Builder;
 use std::time::SystemTime;
 
+use dashmap::mapref::entry::Entry;
 use futures::executor::block_on;
-use smol::channel::unbounded;
+use smol::channel::{bounded, TrySendError};
 use snowflake::SnowflakeIdGenerator;
 
 cfg_tokio_support!(
@@ -33,8 +38,32 @@ cfg_tokio_support!(
     use std::sync::atomic::{AtomicUsize, Ordering};
 );
 
+cfg_task_spec!(
+    use crate::utils::task_spec::HandlerRegistry;
+    use serde_json::Value;
+);
+
+cfg_job_store!(
+    use crate::utils::job_store::{JobStoreWriter, TaskRestorer};
+);
+
+cfg_config!(
+    use crate::utils::config::ConfigLoader;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::thread::sleep;
+    use std::time::Duration;
+    use std::sync::atomic::Ordering::Acquire;
+);
+
 cfg_status_report!(
     use crate::utils::status_report::StatusReporter;
+    use crate::utils::overflow::OverflowSender;
+);
+
+cfg_admin_api!(
+    use crate::utils::admin_api;
+    use std::net::{SocketAddr, TcpListener};
 );
 
 // Set it. Motivation to move forward.
@@ -66,21 +95,61 @@ pub(crate) type SharedTaskFlagMap = Arc<DashMap<u64, TaskMark>>;
 pub struct DelayTimerBuilder {
     shared_header: SharedHeader,
     timer_event_channel: Option<(AsyncSender<TimerEvent>, AsyncReceiver<TimerEvent>)>,
+    /// The capacity of the timer-event channel.
+    timer_event_channel_config: ChannelConfig,
+    /// The capacity of the recycle-unit channel.
+    recycle_unit_channel_config: ChannelConfig,
+    /// The capacity and overflow policy of the task-instances-chain channel.
+    task_instances_chain_config: ChannelConfig,
+    /// Whether or not to allocate task ids by DelayTimer.
+    enable_task_id_allocation: bool,
+    /// How to deal with the task whose id is already in use.
+    duplicate_task_policy: DuplicateTaskPolicy,
     /// Whether or not to enable the status-report
     #[cfg(feature = "status-report")]
     enable_status_report: bool,
     #[cfg(feature = "status-report")]
-    status_report_channel: Option<(AsyncSender<PublicEvent>, AsyncReceiver<PublicEvent>)>,
+    status_report_channel: Option<(OverflowSender<PublicEvent>, AsyncReceiver<PublicEvent>)>,
+    #[cfg(feature = "status-report")]
+    status_report_channel_config: ChannelConfig,
+    /// Rebuild the tasks from the records of job-store.
+    #[cfg(feature = "job-store")]
+    task_restorer: Option<TaskRestorer>,
+    /// The config file that defines the schedule.
+    #[cfg(feature = "config")]
+    config_file: Option<PathBuf>,
+    /// The interval of checking the config file for changes.
+    #[cfg(feature = "config")]
+    config_reload_interval: Option<Duration>,
+    /// The address that the admin api is bound to.
+    #[cfg(feature = "admin-api")]
+    admin_api_addr: Option<SocketAddr>,
 }
 
 /// DelayTimer is an abstraction layer that helps users solve execution cycle synchronous/asynchronous tasks.
 #[derive(Clone, Debug)]
 pub struct DelayTimer {
-    #[allow(dead_code)]
-    shared_header: SharedHeader,
+    pub(crate) shared_header: SharedHeader,
     timer_event_sender: TimerEventSender,
+    enable_task_id_allocation: bool,
+    duplicate_task_policy: DuplicateTaskPolicy,
+    task_instances_chain_config: ChannelConfig,
     #[cfg(feature = "status-report")]
     status_reporter: Option<StatusReporter>,
+    #[cfg(feature = "config")]
+    config_loader: Option<Arc<Mutex<ConfigLoader>>>,
+    #[cfg(feature = "admin-api")]
+    admin_api_addr: Option<SocketAddr>,
+}
+
+/// The policy for adding a task whose id is already in use.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateTaskPolicy {
+    /// Reject the new task with `TaskError::DuplicateTaskId`.
+    #[default]
+    Reject,
+    /// Replace the old task with the new task, running instances of the old task are not affected.
+    Replace,
 }
 
 /// SharedHeader Store the core context of the runtime.
@@ -100,6 +169,20 @@ pub struct SharedHeader {
     pub(crate) runtime_instance: RuntimeInstance,
     // Unique id generator.
     pub(crate) id_generator: SharedIdGenerator,
+    // The last task id allocated by DelayTimer.
+    pub(crate) task_id_seed: Arc<AtomicU64>,
+    // The registry of named handlers for `TaskSpec`.
+    #[cfg(feature = "task-spec")]
+    pub(crate) handler_registry: HandlerRegistry,
+    // The storage that persists tasks, it's written by a dedicated thread.
+    #[cfg(feature = "job-store")]
+    pub(crate) job_store_writer: Option<JobStoreWriter>,
+    // The execution history of tasks.
+    pub(crate) execution_history: ExecutionHistory,
+    // The metrics of tasks and internal workers.
+    pub(crate) metrics: Metrics,
+    // The config of the log files of process output.
+    pub(crate) output_log: Option<Arc<OutputLogConfig>>,
 }
 
 impl fmt::Debug for SharedHeader {
@@ -143,6 +226,7 @@ impl Default for SharedHeader {
         let shared_motivation = Arc::new(AtomicBool::new(true));
         let runtime_instance = RuntimeInstance::default();
         let id_generator = Arc::new(AsyncMutex::new(SnowflakeIdGenerator::new(1, 1)));
+        let task_id_seed = Arc::new(AtomicU64::new(0));
 
         SharedHeader {
             wheel_queue,
@@ -152,6 +236,14 @@ impl Default for SharedHeader {
             shared_motivation,
             runtime_instance,
             id_generator,
+            task_id_seed,
+            #[cfg(feature = "task-spec")]
+            handler_registry: HandlerRegistry::default(),
+            #[cfg(feature = "job-store")]
+            job_store_writer: None,
+            execution_history: ExecutionHistory::default(),
+            metrics: Metrics::default(),
+            output_log: None,
         }
     }
 }
@@ -167,7 +259,74 @@ impl DelayTimerBuilder {
     pub fn build(mut self) -> DelayTimer {
         self.lauch()
             .expect("delay-timer The base task failed to launch.");
-        self.init_delay_timer()
+        let delay_timer = self.init_delay_timer();
+
+        #[cfg(feature = "job-store")]
+        self.restore_tasks(&delay_timer);
+
+        #[cfg(feature = "config")]
+        self.load_config(&delay_timer);
+
+        #[cfg(feature = "admin-api")]
+        let delay_timer = self.serve_admin_api(delay_timer);
+
+        delay_timer
+    }
+
+    /// Let DelayTimer allocate an unique id for every added task,
+    /// the id set by `TaskBuilder` is ignored and the allocated id is returned by `add_task`.
+    pub fn enable_task_id_allocation(mut self) -> Self {
+        self.enable_task_id_allocation = true;
+        self
+    }
+
+    /// Set the policy for adding a task whose id is already in use, the default is `DuplicateTaskPolicy::Reject`.
+    pub fn duplicate_task_policy(mut self, duplicate_task_policy: DuplicateTaskPolicy) -> Self {
+        self.duplicate_task_policy = duplicate_task_policy;
+        self
+    }
+
+    /// Set the capacity of the internal timer-event channel, it's unbounded by default.
+    ///
+    /// When the channel is full, the non-blocking operations of `DelayTimer` fail with `TaskError::DisSend`,
+    /// and the async operations (e.g. `add_task_with_async_ack`) wait until it has space.
+    pub fn timer_event_channel_capacity(mut self, capacity: usize) -> Self {
+        self.timer_event_channel_config = ChannelConfig::new(capacity, OverflowPolicy::Block);
+        self
+    }
+
+    /// Set the capacity of the internal channel that sends the tasks with `maximum_running_time` for recycling,
+    /// it's unbounded by default.
+    pub fn recycle_unit_channel_capacity(mut self, capacity: usize) -> Self {
+        self.recycle_unit_channel_config = ChannelConfig::new(capacity, OverflowPolicy::Block);
+        self
+    }
+
+    /// Set the capacity and overflow policy of every `TaskInstancesChain`, it's unbounded by default.
+    ///
+    /// Note that with `OverflowPolicy::Block`, a chain that is not consumed blocks the whole timer.
+    pub fn task_instances_chain_capacity(
+        mut self,
+        capacity: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
+        self.task_instances_chain_config = ChannelConfig::new(capacity, overflow_policy);
+        self
+    }
+
+    /// Persist the execution records of tasks to the `history_store` by a dedicated thread,
+    /// and `DelayTimer::history` queries it instead of the records in memory.
+    pub fn history_store<S: HistoryStore + 'static>(mut self, history_store: S) -> Self {
+        self.shared_header.execution_history.store_writer =
+            Some(HistoryStoreWriter::new(Arc::new(history_store)));
+        self
+    }
+
+    /// Write the output of process tasks to the log files of instances,
+    /// the lines are streamed to the subscribers of `TaskInstance` regardless.
+    pub fn output_log(mut self, output_log_config: OutputLogConfig) -> Self {
+        self.shared_header.output_log = Some(Arc::new(output_log_config));
+        self
     }
 
     // Start the DelayTimer.
@@ -176,7 +335,8 @@ impl DelayTimerBuilder {
         event_handle_builder
             .timer_event_receiver(self.get_timer_event_receiver())
             .timer_event_sender(self.get_timer_event_sender())
-            .shared_header(self.shared_header.clone());
+            .shared_header(self.shared_header.clone())
+            .recycle_unit_channel_config(self.recycle_unit_channel_config);
 
         #[cfg(feature = "status-report")]
         if self.enable_status_report {
@@ -240,21 +400,33 @@ impl DelayTimerBuilder {
         DelayTimer {
             shared_header,
             timer_event_sender,
+            enable_task_id_allocation: self.enable_task_id_allocation,
+            duplicate_task_policy: self.duplicate_task_policy,
+            task_instances_chain_config: self.task_instances_chain_config,
             #[cfg(feature = "status-report")]
             status_reporter,
+            #[cfg(feature = "config")]
+            config_loader: self
+                .config_file
+                .clone()
+                .map(|config_file| Arc::new(Mutex::new(ConfigLoader::new(config_file)))),
+            #[cfg(feature = "admin-api")]
+            admin_api_addr: None,
         }
     }
 
     fn get_timer_event_sender(&mut self) -> AsyncSender<TimerEvent> {
+        let timer_event_channel_config = self.timer_event_channel_config;
         self.timer_event_channel
-            .get_or_insert_with(unbounded::<TimerEvent>)
+            .get_or_insert_with(|| timer_event_channel_config.channel::<TimerEvent>())
             .0
             .clone()
     }
 
     fn get_timer_event_receiver(&mut self) -> AsyncReceiver<TimerEvent> {
+        let timer_event_channel_config = self.timer_event_channel_config;
         self.timer_event_channel
-            .get_or_insert_with(unbounded::<TimerEvent>)
+            .get_or_insert_with(|| timer_event_channel_config.channel::<TimerEvent>())
             .1
             .clone()
     }
@@ -266,22 +438,30 @@ impl DelayTimer {
         DelayTimerBuilder::default().build()
     }
 
-    /// Add a task in timer_core by event-channel.
-    pub fn add_task(&self, task: Task) -> Result<(), TaskError> {
+    /// Add a task in timer_core by event-channel, return the id of task.
+    pub fn add_task(&self, mut task: Task) -> Result<u64, TaskError> {
+        let task_id = self.register_task_id(&mut task)?;
+
         self.seed_timer_event(TimerEvent::AddTask(Box::new(task)))
+            .map_err(|e| self.unregister_task_id(task_id, e))?;
+        Ok(task_id)
     }
 
     /// Add a task in timer_core by event-channel.
     /// But it will return a handle that can constantly take out new instances of the task.
-    pub fn insert_task(&self, task: Task) -> Result<TaskInstancesChain, TaskError> {
+    pub fn insert_task(&self, mut task: Task) -> Result<TaskInstancesChain, TaskError> {
+        let task_id = self.register_task_id(&mut task)?;
+
         let (mut task_instances_chain, task_instances_chain_maintainer) =
-            task_instance_chain_pair();
+            task_instance_chain_pair(self.task_instances_chain_config);
+        task_instances_chain.task_id = task_id;
         task_instances_chain.timer_event_sender = Some(self.timer_event_sender.clone());
 
         self.seed_timer_event(TimerEvent::InsertTask(
             Box::new(task),
             task_instances_chain_maintainer,
-        ))?;
+        ))
+        .map_err(|e| self.unregister_task_id(task_id, e))?;
         Ok(task_instances_chain)
     }
 
@@ -306,11 +486,227 @@ impl DelayTimer {
         self.seed_timer_event(TimerEvent::CancelTask(task_id, record_id))
     }
 
+    /// Add a task in timer_core by event-channel and block the thread to wait for the acknowledgement,
+    /// return the id of task.
+    pub fn add_task_with_ack(&self, task: Task) -> Result<u64, TaskError> {
+        block_on(self.add_task_with_async_ack(task))
+    }
+
+    /// Add a task in timer_core by event-channel and async-await the acknowledgement,
+    /// return the id of task.
+    pub async fn add_task_with_async_ack(&self, mut task: Task) -> Result<u64, TaskError> {
+        let task_id = self.register_task_id(&mut task)?;
+
+        self.seed_timer_event_with_ack(TimerEvent::AddTask(Box::new(task)))
+            .await
+            .map_err(|e| self.unregister_task_id(task_id, e))?;
+        Ok(task_id)
+    }
+
+    /// Insert a task in timer_core by event-channel and block the thread to wait for the acknowledgement.
+    pub fn insert_task_with_ack(&self, task: Task) -> Result<TaskInstancesChain, TaskError> {
+        block_on(self.insert_task_with_async_ack(task))
+    }
+
+    /// Insert a task in timer_core by event-channel and async-await the acknowledgement.
+    pub async fn insert_task_with_async_ack(
+        &self,
+        mut task: Task,
+    ) -> Result<TaskInstancesChain, TaskError> {
+        let task_id = self.register_task_id(&mut task)?;
+
+        let (mut task_instances_chain, task_instances_chain_maintainer) =
+            task_instance_chain_pair(self.task_instances_chain_config);
+        task_instances_chain.task_id = task_id;
+        task_instances_chain.timer_event_sender = Some(self.timer_event_sender.clone());
+
+        self.seed_timer_event_with_ack(TimerEvent::InsertTask(
+            Box::new(task),
+            task_instances_chain_maintainer,
+        ))
+        .await
+        .map_err(|e| self.unregister_task_id(task_id, e))?;
+        Ok(task_instances_chain)
+    }
+
+    /// Update a task in timer_core by event-channel and block the thread to wait for the acknowledgement.
+    pub fn update_task_with_ack(&self, task: Task) -> Result<(), TaskError> {
+        block_on(self.update_task_with_async_ack(task))
+    }
+
+    /// Update a task in timer_core by event-channel and async-await the acknowledgement.
+    pub async fn update_task_with_async_ack(&self, task: Task) -> Result<(), TaskError> {
+        self.seed_timer_event_with_ack(TimerEvent::UpdateTask(Box::new(task)))
+            .await
+    }
+
+    /// Remove a task in timer_core by event-channel and block the thread to wait for the acknowledgement.
+    pub fn remove_task_with_ack(&self, task_id: u64) -> Result<(), TaskError> {
+        block_on(self.remove_task_with_async_ack(task_id))
+    }
+
+    /// Remove a task in timer_core by event-channel and async-await the acknowledgement.
+    pub async fn remove_task_with_async_ack(&self, task_id: u64) -> Result<(), TaskError> {
+        self.seed_timer_event_with_ack(TimerEvent::RemoveTask(task_id))
+            .await
+    }
+
+    /// Advance a task in timer_core by event-channel and block the thread to wait for the acknowledgement.
+    pub fn advance_task_with_ack(&self, task_id: u64) -> Result<(), TaskError> {
+        block_on(self.advance_task_with_async_ack(task_id))
+    }
+
+    /// Advance a task in timer_core by event-channel and async-await the acknowledgement.
+    pub async fn advance_task_with_async_ack(&self, task_id: u64) -> Result<(), TaskError> {
+        self.seed_timer_event_with_ack(TimerEvent::AdvanceTask(task_id))
+            .await
+    }
+
+    /// Cancel a task running instance in timer_core by event-channel and block the thread to wait for the acknowledgement.
+    pub fn cancel_task_with_ack(&self, task_id: u64, record_id: i64) -> Result<(), TaskError> {
+        block_on(self.cancel_task_with_async_ack(task_id, record_id))
+    }
+
+    /// Cancel a task running instance in timer_core by event-channel and async-await the acknowledgement.
+    pub async fn cancel_task_with_async_ack(
+        &self,
+        task_id: u64,
+        record_id: i64,
+    ) -> Result<(), TaskError> {
+        self.seed_timer_event_with_ack(TimerEvent::CancelTask(task_id, record_id))
+            .await
+    }
+
+    /// Pause a task in timer_core by event-channel.
+    /// The paused task doesn't derive new running instances until it is resumed,
+    /// running instances are not affected.
+    pub fn pause_task(&self, task_id: u64) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::PauseTask(task_id))
+    }
+
+    /// Resume a paused task in timer_core by event-channel.
+    pub fn resume_task(&self, task_id: u64) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::ResumeTask(task_id))
+    }
+
+    /// Remove all tasks matched by the `task_selector` in timer_core by event-channel.
+    pub fn remove_tasks(&self, task_selector: TaskSelector) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::RemoveTasks(task_selector))
+    }
+
+    /// Pause all tasks matched by the `task_selector` in timer_core by event-channel.
+    pub fn pause_tasks(&self, task_selector: TaskSelector) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::PauseTasks(task_selector))
+    }
+
+    /// Resume all tasks matched by the `task_selector` in timer_core by event-channel.
+    pub fn resume_tasks(&self, task_selector: TaskSelector) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::ResumeTasks(task_selector))
+    }
+
+    /// Cancel all running instances of the tasks matched by the `task_selector` in timer_core by event-channel.
+    pub fn cancel_instances(&self, task_selector: TaskSelector) -> Result<(), TaskError> {
+        self.seed_timer_event(TimerEvent::CancelInstances(task_selector))
+    }
+
+    /// Converge the tasks in timer_core to the desired `tasks` in one batch,
+    /// and block the thread to wait for the `ReconcileReport`.
+    ///
+    /// The tasks are keyed by their own task ids: the missing tasks are added,
+    /// the changed tasks are updated, and the tasks that are not desired are removed.
+    /// No other event is handled until the whole batch is applied.
+    ///
+    /// The task bodies can't be compared, so a task whose only change is its body is left unchanged,
+    /// unless it's built from `TaskSpec` (see `ReconcileReport`). Remove the task first to replace its body.
+    ///
+    /// The whole batch is rejected with `TaskError::DuplicateTaskId` if a task id is desired twice.
+    pub fn reconcile(&self, tasks: Vec<Task>) -> Result<ReconcileReport, TaskError> {
+        block_on(self.reconcile_with_async(tasks))
+    }
+
+    /// Converge the tasks in timer_core to the desired `tasks` in one batch,
+    /// and async-await the `ReconcileReport`.
+    pub async fn reconcile_with_async(
+        &self,
+        tasks: Vec<Task>,
+    ) -> Result<ReconcileReport, TaskError> {
+        let mut desired_task_ids = HashSet::with_capacity(tasks.len());
+        if let Some(task) = tasks
+            .iter()
+            .find(|task| !desired_task_ids.insert(task.task_id))
+        {
+            return Err(TaskError::DuplicateTaskId(task.task_id));
+        }
+
+        // The allocated task ids never collide with the desired tasks.
+        if let Some(task_id) = tasks.iter().map(|task| task.task_id).max() {
+            self.shared_header.task_id_seed.fetch_max(task_id, AcqRel);
+        }
+
+        let (reply_sender, reply_receiver) = bounded(1);
+        self.seed_timer_event_async(TimerEvent::Reconcile(tasks, reply_sender))
+            .await?;
+
+        Ok(reply_receiver.recv().await?)
+    }
+
     /// Stop DelayTimer, running tasks are not affected.
     pub fn stop_delay_timer(&self) -> Result<(), TaskError> {
         self.seed_timer_event(TimerEvent::StopTimer)
     }
 
+    /// Get the information of a task that has been added, contains the description of its cron expression.
+    pub fn get_task_info(&self, task_id: u64) -> Option<TaskInfo> {
+        self.shared_header
+            .task_flag_map
+            .get(&task_id)
+            .filter(|task_mark| !task_mark.value().is_exhausted())
+            .map(|task_mark| task_mark.value().get_task_info())
+    }
+
+    /// Get the information of all tasks matched by the `task_selector`, they are ordered by task id.
+    pub fn get_task_infos(&self, task_selector: TaskSelector) -> Vec<TaskInfo> {
+        let mut task_infos: Vec<TaskInfo> = self
+            .shared_header
+            .task_flag_map
+            .iter()
+            .filter(|task_mark| task_mark.is_live() && task_selector.matches(task_mark))
+            .map(|task_mark| task_mark.value().get_task_info())
+            .collect();
+        task_infos.sort_unstable_by_key(|task_info| task_info.task_id);
+        task_infos
+    }
+
+    /// Get the next `count` fire times (timestamp in seconds) of a task that has been added,
+    /// they are derived from its cron expression and time zone.
+    pub fn get_next_fire_times(&self, task_id: u64, count: usize) -> Option<Vec<u64>> {
+        self.shared_header
+            .task_flag_map
+            .get(&task_id)
+            .filter(|task_mark| !task_mark.value().is_exhausted())
+            .map(|task_mark| task_mark.value().get_next_fire_times(count))
+    }
+
+    /// Get the execution records of the task matched by the `filter`, they are ordered from oldest to newest.
+    ///
+    /// The latest records of the task are kept in memory (see `TaskBuilder::set_history_capacity`),
+    /// and they are dropped when the task is removed, unless the `HistoryStore` is set.
+    pub fn history(
+        &self,
+        task_id: u64,
+        filter: HistoryFilter,
+    ) -> Result<Vec<ExecutionRecord>, HistoryStoreError> {
+        self.shared_header.execution_history.query(task_id, &filter)
+    }
+
+    /// Render the metrics of tasks and internal workers in the Prometheus text exposition format,
+    /// e.g. the number of started instances and the histogram of run duration of every task.
+    pub fn render_metrics(&self) -> String {
+        self.shared_header
+            .metrics
+            .render(&self.shared_header.task_flag_map)
+    }
+
     /// Set internal id-generator for `machine_id` and `node_id`.
     /// Add a new api in the future to support passing a custom id generator.
     /// The id-generator is mainly used for binding unique record ids to internal events, for user collection, and for tracking task dynamics.
@@ -321,10 +717,107 @@ impl DelayTimer {
         id_generator.node_id = node_id;
     }
 
+    // Reserve the `TaskMark` of the task before the event is sent,
+    // so the duplicate id can be found even if the previous task is still in the event-channel.
+    fn register_task_id(&self, task: &mut Task) -> Result<u64, TaskError> {
+        if self.enable_task_id_allocation {
+            loop {
+                let task_id = self.shared_header.task_id_seed.fetch_add(1, AcqRel) + 1;
+
+                if let Entry::Vacant(entry) = self.shared_header.task_flag_map.entry(task_id) {
+                    task.task_id = task_id;
+                    entry.insert(TaskMark::reserved(task));
+                    return Ok(task_id);
+                }
+            }
+        }
+
+        let task_id = task.task_id;
+        match self.shared_header.task_flag_map.entry(task_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(TaskMark::reserved(task));
+            }
+            // The id of the exhausted task is released, it's reused as the replacement.
+            Entry::Occupied(entry)
+                if self.duplicate_task_policy == DuplicateTaskPolicy::Reject
+                    && !entry.get().is_exhausted() =>
+            {
+                return Err(TaskError::DuplicateTaskId(task_id));
+            }
+            Entry::Occupied(_) => {}
+        }
+
+        Ok(task_id)
+    }
+
+    // Release the reserved `TaskMark` when the task can't be sent.
+    fn unregister_task_id(&self, task_id: u64, error: TaskError) -> TaskError {
+        self.shared_header
+            .task_flag_map
+            .remove_if(&task_id, |_, task_mark| task_mark.is_reserved());
+        error
+    }
+
+    // Add the task with its own id, even if the task ids are allocated by DelayTimer,
+    // it's used by the tasks restored from job-store and defined by config file.
+    #[cfg(any(feature = "job-store", feature = "config"))]
+    pub(crate) fn add_task_with_own_id(&self, task: Task) -> Result<u64, TaskError> {
+        let task_id = self.register_own_task_id(&task)?;
+
+        self.seed_timer_event(TimerEvent::AddTask(Box::new(task)))
+            .map_err(|e| self.unregister_task_id(task_id, e))?;
+        Ok(task_id)
+    }
+
+    // Same as `add_task_with_own_id`, and block the thread to wait for the acknowledgement.
+    #[cfg(feature = "config")]
+    pub(crate) fn add_task_with_own_id_ack(&self, task: Task) -> Result<u64, TaskError> {
+        let task_id = self.register_own_task_id(&task)?;
+
+        block_on(self.seed_timer_event_with_ack(TimerEvent::AddTask(Box::new(task))))
+            .map_err(|e| self.unregister_task_id(task_id, e))?;
+        Ok(task_id)
+    }
+
+    #[cfg(any(feature = "job-store", feature = "config"))]
+    fn register_own_task_id(&self, task: &Task) -> Result<u64, TaskError> {
+        let task_id = task.task_id;
+        self.shared_header.task_id_seed.fetch_max(task_id, AcqRel);
+
+        match self.shared_header.task_flag_map.entry(task_id) {
+            Entry::Vacant(entry) => {
+                entry.insert(TaskMark::reserved(task));
+            }
+            Entry::Occupied(entry) if !entry.get().is_exhausted() => {
+                return Err(TaskError::DuplicateTaskId(task_id))
+            }
+            Entry::Occupied(_) => {}
+        }
+
+        Ok(task_id)
+    }
+
     /// Send a event to event-handle.
     fn seed_timer_event(&self, event: TimerEvent) -> Result<(), TaskError> {
         Ok(self.timer_event_sender.try_send(event)?)
     }
+
+    /// Send a event to event-handle, wait if the channel is full.
+    async fn seed_timer_event_async(&self, event: TimerEvent) -> Result<(), TaskError> {
+        self.timer_event_sender
+            .send(event)
+            .await
+            .map_err(|e| TrySendError::Closed(e.into_inner()).into())
+    }
+
+    /// Send a event to event-handle, and wait for the result of handling it.
+    async fn seed_timer_event_with_ack(&self, event: TimerEvent) -> Result<(), TaskError> {
+        let (reply_sender, reply_receiver) = bounded(1);
+        self.seed_timer_event_async(TimerEvent::Acknowledged(Box::new(event), reply_sender))
+            .await?;
+
+        reply_receiver.recv().await?
+    }
 }
 
 cfg_tokio_support!(
@@ -407,6 +900,279 @@ cfg_tokio_support!(
 
 );
 
+cfg_task_spec!(
+/// # Required features
+///
+/// This function requires the `task-spec` feature of the `delay_timer`
+/// crate to be enabled.
+    impl DelayTimerBuilder {
+
+        /// Register a named handler, the task defined by `TaskSpec` runs the handler with its payload.
+        pub fn register_handler<S, F>(self, name: S, handler: F) -> Self
+        where
+            S: Into<String>,
+            F: Fn(TaskContext, &Value) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync,
+        {
+            self.shared_header.handler_registry.register(name.into(), handler);
+            self
+        }
+    }
+
+    impl DelayTimer {
+
+        /// Register a named handler, the handler with the same name is replaced.
+        /// Tasks that have been built are not affected.
+        pub fn register_handler<S, F>(&self, name: S, handler: F)
+        where
+            S: Into<String>,
+            F: Fn(TaskContext, &Value) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync,
+        {
+            self.shared_header.handler_registry.register(name.into(), handler);
+        }
+
+        /// Build the task from `TaskSpec` with the registered handler.
+        pub fn spawn_task(&self, task_spec: &TaskSpec) -> Result<Task, TaskError> {
+            self.shared_header.handler_registry.spawn_task(task_spec)
+        }
+
+        /// Build the task from `TaskSpec` and add it in timer_core by event-channel, return the id of task.
+        pub fn add_task_spec(&self, task_spec: &TaskSpec) -> Result<u64, TaskError> {
+            self.add_task(self.spawn_task(task_spec)?)
+        }
+
+        /// Build the tasks from `TaskSpec`s and converge the tasks in timer_core to them,
+        /// nothing is applied if any of them can't be built.
+        pub fn reconcile_specs(&self, task_specs: &[TaskSpec]) -> Result<ReconcileReport, TaskError> {
+            let tasks = task_specs
+                .iter()
+                .map(|task_spec| self.spawn_task(task_spec))
+                .collect::<Result<Vec<Task>, TaskError>>()?;
+
+            self.reconcile(tasks)
+        }
+    }
+);
+
+cfg_config!(
+/// # Required features
+///
+/// This function requires the `config` feature of the `delay_timer`
+/// crate to be enabled.
+    impl DelayTimerBuilder {
+
+        /// Load the schedule from the TOML/YAML config file when `build`,
+        /// and reload it when the file changes.
+        ///
+        /// On reload, the tasks that are added to, changed in and removed from the file are applied,
+        /// unchanged tasks and their running instances are left alone.
+        /// An invalid file is reported by log and nothing is changed.
+        pub fn config_file<P: Into<PathBuf>>(mut self, config_file: P) -> Self {
+            self.config_file = Some(config_file.into());
+            self
+        }
+
+        /// Set the interval of checking the config file for changes,
+        /// the default is `DEFAULT_CONFIG_RELOAD_INTERVAL`.
+        pub fn config_reload_interval(mut self, interval: Duration) -> Self {
+            self.config_reload_interval = Some(interval);
+            self
+        }
+
+        fn load_config(&self, delay_timer: &DelayTimer) {
+            if delay_timer.config_loader.is_none() {
+                return;
+            }
+
+            if let Err(e) = delay_timer.reload_config() {
+                error!("Can't load the config file: {}", e);
+            }
+
+            let interval = self
+                .config_reload_interval
+                .unwrap_or(DEFAULT_CONFIG_RELOAD_INTERVAL);
+            let delay_timer = delay_timer.clone();
+            Builder::new()
+                .name("config_watcher".into())
+                .spawn(move || {
+                    while delay_timer.shared_header.shared_motivation.load(Acquire) {
+                        sleep(interval);
+
+                        if !delay_timer.is_config_modified() {
+                            continue;
+                        }
+
+                        match delay_timer.reload_config() {
+                            Ok(report) => info!("The config file is reloaded: {:?}", report),
+                            Err(e) => error!("Can't reload the config file: {}", e),
+                        }
+                    }
+                })
+                .expect("config_watcher can't start.");
+        }
+    }
+
+    impl DelayTimer {
+
+        /// Reload the config file now, return the changes of tasks.
+        ///
+        /// The tasks in the file are compared with the running tasks,
+        /// so the task that is removed or has run out is added again.
+        pub fn reload_config(&self) -> Result<ConfigReloadReport, ConfigError> {
+            let config_loader = self
+                .config_loader
+                .as_ref()
+                .ok_or(ConfigError::MissingConfigFile)?;
+
+            config_loader
+                .lock()
+                .map_err(|_| ConfigError::Poisoned)?
+                .reload(self)
+        }
+
+        fn is_config_modified(&self) -> bool {
+            self.config_loader
+                .as_ref()
+                .and_then(|config_loader| config_loader.lock().ok())
+                .map(|config_loader| config_loader.is_modified())
+                .unwrap_or(false)
+        }
+    }
+);
+
+cfg_admin_api!(
+/// # Required features
+///
+/// This function requires the `admin-api` feature of the `delay_timer`
+/// crate to be enabled.
+    impl DelayTimerBuilder {
+
+        /// Serve the local HTTP/JSON admin api on `addr` when `build`, see `utils::admin_api` for the routes.
+        ///
+        /// The status-report is enabled, and its public events are streamed by the admin api,
+        /// so `DelayTimer::take_status_reporter` returns `None`.
+        /// The failure of binding `addr` is reported by log.
+        pub fn admin_api<A: Into<SocketAddr>>(mut self, addr: A) -> Self {
+            self.admin_api_addr = Some(addr.into());
+            self.enable_status_report = true;
+            self
+        }
+
+        fn serve_admin_api(&self, mut delay_timer: DelayTimer) -> DelayTimer {
+            let addr = match self.admin_api_addr {
+                Some(addr) => addr,
+                None => return delay_timer,
+            };
+
+            let status_reporter = delay_timer.status_reporter.take();
+            let served = TcpListener::bind(addr).and_then(|listener| {
+                delay_timer.admin_api_addr = Some(listener.local_addr()?);
+                admin_api::serve(delay_timer.clone(), listener, status_reporter)
+            });
+
+            if let Err(e) = served {
+                delay_timer.admin_api_addr = None;
+                error!("Can't serve the admin api on {}: {}", addr, e);
+            }
+            delay_timer
+        }
+    }
+
+    impl DelayTimer {
+
+        /// Get the address that the admin api is bound to,
+        /// it's `None` if the admin api isn't served.
+        pub fn admin_api_addr(&self) -> Option<SocketAddr> {
+            self.admin_api_addr
+        }
+    }
+);
+
+cfg_job_store!(
+/// # Required features
+///
+/// This function requires the `job-store` feature of the `delay_timer`
+/// crate to be enabled.
+    impl DelayTimerBuilder {
+
+        /// Persist tasks to the `job_store`, and restore the tasks in it when `build`.
+        ///
+        /// The task body can't be persisted, so the task built from `TaskSpec` is restored by its registered handler,
+        /// and the `restorer` rebuilds the others from their records (e.g. by `TaskRecord::restore`),
+        /// the record is kept but not restored if it returns `None`.
+        /// Restored tasks keep their ids,
+        /// and the task that missed its fire while the process was down runs once right after it's restored.
+        pub fn job_store<S, F>(mut self, job_store: S, restorer: F) -> Self
+        where
+            S: JobStore + 'static,
+            F: Fn(&TaskRecord) -> Option<Task> + 'static + Send + Sync,
+        {
+            self.shared_header.job_store_writer = Some(JobStoreWriter::new(Arc::new(job_store)));
+            self.task_restorer = Some(TaskRestorer(Arc::new(restorer)));
+            self
+        }
+
+        fn restore_tasks(&self, delay_timer: &DelayTimer) {
+            let (job_store_writer, task_restorer) =
+                match (self.shared_header.job_store_writer.as_ref(), self.task_restorer.as_ref()) {
+                    (Some(job_store_writer), Some(task_restorer)) => (job_store_writer, task_restorer),
+                    _ => return,
+                };
+
+            let records = match job_store_writer.job_store.load() {
+                Ok(records) => records,
+                Err(e) => {
+                    error!("Can't load tasks from job-store: {}", e);
+                    return;
+                }
+            };
+
+            for record in records {
+                let restored_task = record
+                    .restore_by_handler(&self.shared_header.handler_registry)
+                    .or_else(|| (task_restorer.0)(&record).map(Ok));
+
+                let task = match restored_task {
+                    Some(Ok(task)) => task,
+                    Some(Err(e)) => {
+                        error!("Can't restore the task {} from job-store: {}", record.task_id, e);
+                        continue;
+                    }
+                    None => {
+                        error!("Can't restore the task {} from job-store.", record.task_id);
+                        continue;
+                    }
+                };
+
+                if let Err(e) = delay_timer.add_task_with_own_id(task) {
+                    error!("Can't restore the task {} from job-store: {}", record.task_id, e);
+                    continue;
+                }
+
+                // The missed fires are made up by one run at the next tick.
+                if record.missed_fire(get_timestamp()) {
+                    delay_timer
+                        .advance_task(record.task_id)
+                        .unwrap_or_else(|e| error!("Can't run the missed task {}: {}", record.task_id, e));
+                }
+            }
+        }
+    }
+
+    impl SharedHeader {
+        pub(crate) fn save_task_record(&self, task: &Task) {
+            if let Some(job_store_writer) = self.job_store_writer.as_ref() {
+                job_store_writer.save(TaskRecord::from(task));
+            }
+        }
+
+        pub(crate) fn remove_task_record(&self, task_id: u64) {
+            if let Some(job_store_writer) = self.job_store_writer.as_ref() {
+                job_store_writer.remove(task_id);
+            }
+        }
+    }
+);
+
 cfg_status_report!(
 /// # Required features
 ///
@@ -420,16 +1186,26 @@ cfg_status_report!(
             self
         }
 
-        fn get_status_report_sender(&mut self) -> AsyncSender<PublicEvent> {
+        /// Set the capacity and overflow policy of the status-report channel, it's unbounded by default.
+        ///
+        /// Note that with `OverflowPolicy::Block`, public events that are not consumed block the whole timer.
+        pub fn status_report_channel_capacity(mut self, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+            self.status_report_channel_config = ChannelConfig::new(capacity, overflow_policy);
+            self
+        }
+
+        fn get_status_report_sender(&mut self) -> OverflowSender<PublicEvent> {
+            let status_report_channel_config = self.status_report_channel_config;
             self.status_report_channel
-                .get_or_insert_with(unbounded::<PublicEvent>)
+                .get_or_insert_with(|| status_report_channel_config.overflow_channel::<PublicEvent>())
                 .0
                 .clone()
         }
 
         fn get_status_report_receiver(&mut self) -> AsyncReceiver<PublicEvent> {
+            let status_report_channel_config = self.status_report_channel_config;
             self.status_report_channel
-                .get_or_insert_with(unbounded::<PublicEvent>)
+                .get_or_insert_with(|| status_report_channel_config.overflow_channel::<PublicEvent>())
                 .1
                 .clone()
         }
@@ -14,6 +14,30 @@ pub enum TaskError {
     /// Task event get failed.
     #[error("Task event get failed.")]
     DisGetEvent(#[from] channel::TryRecvError),
+    /// Internal channel communication abnormality.
+    #[error("Task reply channel exception.")]
+    InternalChannelAnomaly(#[from] channel::RecvError),
+    /// The task is not found in the timer.
+    #[error("Task {0} is not found.")]
+    TaskNotFound(u64),
+    /// The running instance of the task is not found.
+    #[error("Running instance {1} of task {0} is not found.")]
+    InstanceNotFound(u64, i64),
+    /// The task has no time to fire in the future.
+    #[error("Task {0} has no future fire time.")]
+    NoFutureFireTime(u64),
+    /// The running instance of the task can't be cancelled.
+    #[error("Task instance cancel failure: {0}")]
+    DisCancel(String),
+    /// The task id is already in use.
+    #[error("Task id {0} is already in use.")]
+    DuplicateTaskId(u64),
+    /// Too many labels are added to the task.
+    #[error("Task can have at most {0} labels.")]
+    TooManyLabels(usize),
+    /// The handler is not registered.
+    #[error("Handler `{0}` is not registered.")]
+    HandlerNotFound(String),
 }
 
 /// Error enumeration for `TaskInstance`-related operations.
@@ -42,6 +66,110 @@ pub enum TaskInstanceError {
     Expired,
 }
 
+cfg_job_store!(
+    /// Error enumeration for `JobStore`-related operations.
+    #[derive(Error, Debug)]
+    pub enum JobStoreError {
+        /// Reading or writing the store file failed.
+        #[error("Job store io error.")]
+        Io(#[from] std::io::Error),
+        /// Task records can't be encoded or decoded by json.
+        #[error("Job store json error.")]
+        Json(#[from] serde_json::Error),
+        /// Task records can't be encoded or decoded by bincode.
+        #[error("Job store bincode error.")]
+        Bincode(#[from] bincode::Error),
+        /// The operation of sqlite failed.
+        #[cfg(feature = "sqlite-store")]
+        #[error("Job store sqlite error.")]
+        Sqlite(#[from] rusqlite::Error),
+        /// The lock of job store is poisoned.
+        #[error("Job store lock is poisoned.")]
+        Poisoned,
+    }
+);
+
+/// Error enumeration for `HistoryStore`-related operations.
+#[derive(Error, Debug)]
+pub enum HistoryStoreError {
+    /// Reading or writing the store file failed.
+    #[error("History store io error.")]
+    Io(#[from] std::io::Error),
+    /// Execution records can't be encoded or decoded by json.
+    #[cfg(feature = "history-store")]
+    #[error("History store json error.")]
+    Json(#[from] serde_json::Error),
+    /// The lock of history store is poisoned.
+    #[error("History store lock is poisoned.")]
+    Poisoned,
+}
+
+cfg_config!(
+    /// Error enumeration for loading the config file.
+    #[derive(Error, Debug)]
+    pub enum ConfigError {
+        /// No config file is set by `DelayTimerBuilder::config_file`.
+        #[error("No config file is set.")]
+        MissingConfigFile,
+        /// Reading the config file failed.
+        #[error("Config file can't be read.")]
+        Io(#[from] std::io::Error),
+        /// The format of config file can't be decided by its extension.
+        #[error("Unknown format of config file `{0}`.")]
+        UnknownFormat(std::path::PathBuf),
+        /// The TOML config can't be parsed.
+        #[error("Config toml error.")]
+        Toml(#[from] toml::de::Error),
+        /// The YAML config can't be parsed.
+        #[error("Config yaml error.")]
+        Yaml(#[from] serde_yaml::Error),
+        /// The task id appears more than once.
+        #[error("Task id {0} appears more than once.")]
+        DuplicateTaskId(u64),
+        /// The task must have either a shell command or a handler.
+        #[error("Task {0} must have either `shell` or `handler`.")]
+        InvalidBody(u64),
+        /// The time zone of task is invalid.
+        #[error("Task {0} has invalid time zone `{1}`.")]
+        InvalidTimeZone(u64, String),
+        /// The task can't be spawned or applied.
+        #[error("Task {0} can't be applied.")]
+        Task(u64, #[source] TaskError),
+        /// The lock of config loader is poisoned.
+        #[error("Config loader lock is poisoned.")]
+        Poisoned,
+    }
+);
+
+/// Error enumeration for crontab importing, the number is the line of the crontab file.
+#[derive(Error, Debug)]
+pub enum CrontabError {
+    /// Reading the crontab file failed.
+    #[error("Crontab file can't be read.")]
+    Io(#[from] std::io::Error),
+    /// The line doesn't have 5 time fields and a command.
+    #[error("Line {0}: expected 5 time fields and a command.")]
+    MissingFields(usize),
+    /// The line of system crontab doesn't have a user field.
+    #[error("Line {0}: expected a user field before the command.")]
+    MissingUser(usize),
+    /// The `@` macro is unknown.
+    #[error("Line {0}: unknown macro `{1}`.")]
+    UnknownMacro(usize, String),
+    /// The day of week field is invalid.
+    #[error("Line {0}: invalid day of week `{1}`.")]
+    InvalidDayOfWeek(usize, String),
+    /// The time fields can't be parsed.
+    #[error("Line {0}: invalid time fields.")]
+    InvalidExpression(usize, #[source] CronExpressionAnalyzeError),
+    /// The entry of system crontab runs as another user, but the tasks run as the current user.
+    #[error("Line {0}: the entry runs as `{1}`, which isn't the user of the current process.")]
+    ForeignUser(usize, String),
+    /// The task of the line can't be spawned.
+    #[error("Line {0}: task can't be spawned.")]
+    Task(usize, #[source] TaskError),
+}
+
 /// Error enumeration for Cron expression parsing.
 #[derive(Error, Debug)]
 pub enum CronExpressionAnalyzeError {
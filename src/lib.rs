@@ -256,3 +256,7 @@ pub mod utils;
 pub use anyhow;
 pub use cron_clock;
 pub use snowflake;
+
+// It's used by `create_async_fn_body!` and `create_async_fn_tokio_body!`.
+#[doc(hidden)]
+pub use concat_idents;
@@ -13,6 +13,66 @@ macro_rules! cfg_tokio_support {
     }
 }
 
+macro_rules! cfg_task_spec {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "task-spec")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "task-spec")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_config {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "config")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "config")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_job_store {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "job-store")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "job-store")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_sqlite_store {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "sqlite-store")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "sqlite-store")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_history_store {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "history-store")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "history-store")))]
+            $item
+        )*
+    }
+}
+
+macro_rules! cfg_admin_api {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "admin-api")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "admin-api")))]
+            $item
+        )*
+    }
+}
+
 macro_rules! cfg_status_report {
     ($($item:item)*) => {
         $(
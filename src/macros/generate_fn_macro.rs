@@ -5,78 +5,104 @@
 //! refer to generate_closure_template in convenience mod).
 
 /// Create a closure that return a DelayTaskHandel by macro.
-
+///
+/// The variables in parentheses are cloned for every task running instance,
+/// and they're named with the suffix `_ref` in the block.
+///
+/// ```
+/// use delay_timer::prelude::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering::Release};
+/// use std::sync::Arc;
+///
+/// let share_num = Arc::new(AtomicUsize::new(0));
+/// let body = create_async_fn_body!((share_num){
+///     share_num_ref.fetch_add(1, Release);
+/// });
+/// let task = TaskBuilder::default().set_task_id(1).spawn(body);
+/// ```
 #[macro_export]
 macro_rules! create_async_fn_body {
     ($async_body:block) => {
-        move |context: TaskContext| {
-            let f = async move {
+        $crate::__create_async_fn_body_by!($crate::prelude::async_spawn, $async_body)
+    };
+
+    (($($capture_variable:ident),+)$async_body:block) => {
+        $crate::__create_async_fn_body_by!(
+            $crate::prelude::async_spawn,
+            ($($capture_variable),+)$async_body
+        )
+    };
+}
+
+cfg_tokio_support!(
+    /// Create a closure that return a DelayTaskHandel by macro, the block is spawned on tokio.
+    ///
+    /// It accepts the same syntax as `create_async_fn_body!`,
+    /// and the tasks should run in the `DelayTimer` built with `DelayTimerBuilder::tokio_runtime`.
+    ///
+    /// ```
+    /// use delay_timer::prelude::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering::Release};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// let share_num = Arc::new(AtomicUsize::new(0));
+    /// let body = create_async_fn_tokio_body!((share_num){
+    ///     sleep_by_tokio(Duration::from_millis(100)).await;
+    ///     share_num_ref.fetch_add(1, Release);
+    /// });
+    /// let task = TaskBuilder::default().set_task_id(1).spawn(body);
+    /// ```
+    #[macro_export]
+    macro_rules! create_async_fn_tokio_body {
+        ($async_body:block) => {
+            $crate::__create_async_fn_body_by!($crate::prelude::async_spawn_by_tokio, $async_body)
+        };
+
+        (($($capture_variable:ident),+)$async_body:block) => {
+            $crate::__create_async_fn_body_by!(
+                $crate::prelude::async_spawn_by_tokio,
+                ($($capture_variable),+)$async_body
+            )
+        };
+    }
+);
+
+// The shared implementation of `create_async_fn_body!` and `create_async_fn_tokio_body!`,
+// the future of task running instance is spawned by the function.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __create_async_fn_body_by {
+    ($spawn:path, $async_body:block) => {
+        move |context: $crate::prelude::TaskContext| {
+            let f = context.instrument(|context| async move {
                 let future_inner = async move { $async_body };
                 future_inner.await;
 
                 context.finishe_task(None).await;
-            };
-            let handle = async_spawn(f);
-            create_delay_task_handler(handle)
+            });
+            let handle = $spawn(f);
+            $crate::prelude::create_delay_task_handler(handle)
         }
     };
 
-    (($($capture_variable:ident),+)$async_body:block) => {
+    ($spawn:path, ($($capture_variable:ident),+)$async_body:block) => {
 
-        move |context: TaskContext| {
+        move |context: $crate::prelude::TaskContext| {
 
             $(
-                concat_idents::concat_idents!(variable_ref_name = $capture_variable, "_ref" {
+                $crate::concat_idents::concat_idents!(variable_ref_name = $capture_variable, "_ref" {
                     let variable_ref_name = $capture_variable.clone();
                 });
             )+
-            let f = async move {
+            let f = context.instrument(|context| async move {
                 let future_inner = async move { $async_body };
                 future_inner.await;
 
                 context.finishe_task(None).await;
-            };
-            let handle = async_spawn(f);
-            create_delay_task_handler(handle)
+            });
+            let handle = $spawn(f);
+            $crate::prelude::create_delay_task_handler(handle)
         }
-    }
+    };
 }
-
-cfg_tokio_support!(
-    /// Create a closure that return a DelayTaskHandel by macro.
-
-    #[macro_export]
-    macro_rules! create_async_fn_tokio_body {
-        ($async_body:block) => {
-            |context| {
-                let handle = tokio_async_spawn(async move {
-                    let future_inner = async move { $async_body };
-                    future_inner.await;
-
-                    context.finishe_task().await;
-                });
-                create_delay_task_handler(handle)
-            }
-
-            (($($capture_variable:ident),+)$async_body:block) => {
-
-                move |context: TaskContext| {
-
-                    $(
-                        concat_idents::concat_idents!(variable_ref_name = $capture_variable, "_ref" {
-                            let variable_ref_name = $capture_variable.clone();
-                        });
-                    )+
-                    let f = async move {
-                        let future_inner = async move { $async_body };
-                        future_inner.await;
-
-                        context.finishe_task().await;
-                    };
-                    let handle = async_spawn(f);
-                    create_delay_task_handler(handle)
-                }
-            }
-        };
-    }
-);
@@ -10,15 +10,20 @@
 //!
 //! The prelude may grow over time as additional items see ubiquitous use.
 
-pub use crate::entity::{get_timestamp, get_timestamp_micros, DelayTimer, DelayTimerBuilder};
+pub use crate::entity::{
+    get_timestamp, get_timestamp_micros, DelayTimer, DelayTimerBuilder, DuplicateTaskPolicy,
+};
 pub use crate::error::*;
 pub use crate::macros::*;
+pub use crate::timer::job::Job;
 pub use crate::timer::runtime_trace::state::instance;
 pub use crate::timer::runtime_trace::task_handle::DelayTaskHandler;
 pub use crate::timer::runtime_trace::task_instance::{Instance, TaskInstance, TaskInstancesChain};
-pub use crate::timer::task::TaskContext;
-pub use crate::timer::task::{Frequency, ScheduleIteratorTimeZone, Task, TaskBuilder};
-pub use crate::timer::timer_core::{FinishOutput, FinishTaskBody, TimerEvent};
+pub use crate::timer::task::{
+    Frequency, ScheduleIteratorTimeZone, Task, TaskBuilder, TaskInfo, TaskSelector, MAX_TASK_LABELS,
+};
+pub use crate::timer::task::{IntoFinishOutput, TaskContext};
+pub use crate::timer::timer_core::{FinishOutput, FinishTaskBody, ReconcileReport, TimerEvent};
 
 pub use crate::utils::convenience::cron_expression_grammatical_candy::{
     CandyCron, CandyCronStr, CandyFrequency,
@@ -26,8 +31,20 @@ pub use crate::utils::convenience::cron_expression_grammatical_candy::{
 pub use crate::utils::convenience::functions::{
     create_default_delay_task_handler, create_delay_task_handler, unblock_process_task_fn,
 };
+pub use crate::utils::crontab::{Crontab, CrontabEntry, CrontabFormat};
+pub use crate::utils::describe::{describe_cron_expression, preview_cron_expression, CronPreview};
+pub use crate::utils::history::{
+    ExecutionRecord, HistoryFilter, HistoryStore, DEFAULT_HISTORY_CAPACITY,
+};
+pub use crate::utils::output::{OutputLine, OutputLogConfig, OutputSource};
+pub use crate::utils::overflow::OverflowPolicy;
+pub use crate::utils::parse::shell_command::PipelineOutput;
+pub use crate::utils::process::ProcessTaskBuilder;
+pub use crate::utils::resource::{IoPriority, ResourceLimits, ResourceUsage};
+pub use crate::utils::template::render_template;
 
 pub use anyhow::{anyhow, Result as AnyResult};
+pub use async_trait::async_trait;
 pub use cron_clock::{self, error as cron_error, FixedOffset, Local, TimeZone, Utc};
 pub use smol::channel;
 pub use smol::future as future_lite;
@@ -66,3 +83,28 @@ cfg_tokio_support!(
 cfg_status_report!(
     pub use crate::utils::status_report::PublicEvent;
 );
+
+cfg_task_spec!(
+    pub use crate::utils::task_spec::{FrequencySpec, TaskSpec};
+);
+
+cfg_job_store!(
+    pub use crate::utils::job_store::{
+        FileFormat, FileJobStore, JobStore, TaskRecord, TaskRecordTimeZone,
+    };
+);
+
+cfg_history_store!(
+    pub use crate::utils::history::FileHistoryStore;
+);
+
+cfg_config!(
+    pub use crate::utils::config::{
+        ConfigFormat, ConfigReloadReport, ScheduleConfig, TaskConfig,
+        DEFAULT_CONFIG_RELOAD_INTERVAL,
+    };
+);
+
+cfg_sqlite_store!(
+    pub use crate::utils::job_store::SqliteJobStore;
+);
@@ -13,16 +13,19 @@ use super::runtime_trace::task_handle::TaskTrace;
 pub(crate) use super::timer_core::{TimerEvent, DEFAULT_TIMER_SLOT_COUNT};
 use super::{Slot, Task, TaskMark};
 use crate::prelude::*;
+use crate::utils::metrics::Metrics;
+use crate::utils::overflow::ChannelConfig;
 
+use std::collections::HashSet;
 use std::sync::atomic::Ordering::{Acquire, Release};
 use std::sync::Arc;
 
 use anyhow::Result;
-use smol::channel::unbounded;
 
 cfg_status_report!(
     use std::convert::TryFrom;
-    type StatusReportSender = Option<AsyncSender<PublicEvent>>;
+    use crate::utils::overflow::OverflowSender;
+    type StatusReportSender = Option<OverflowSender<PublicEvent>>;
 );
 #[derive(Debug, Default, Clone)]
 pub(crate) struct EventHandleBuilder {
@@ -31,6 +34,8 @@ pub(crate) struct EventHandleBuilder {
     //The core of the event recipient, dealing with the global event.
     pub(crate) timer_event_receiver: Option<TimerEventReceiver>,
     pub(crate) timer_event_sender: Option<TimerEventSender>,
+    //The capacity of channel for sending recycle-units.
+    pub(crate) recycle_unit_channel_config: ChannelConfig,
     #[warn(dead_code)]
     #[cfg(feature = "status-report")]
     pub(crate) status_report_sender: StatusReportSender,
@@ -55,12 +60,24 @@ impl EventHandleBuilder {
         self
     }
 
+    pub(crate) fn recycle_unit_channel_config(
+        &mut self,
+        recycle_unit_channel_config: ChannelConfig,
+    ) -> &mut Self {
+        self.recycle_unit_channel_config = recycle_unit_channel_config;
+        self
+    }
+
     pub(crate) fn build(self) -> Option<EventHandle> {
         let task_trace = TaskTrace::default();
-        let sub_wokers = SubWorkers::new(self.timer_event_sender?);
+        let shared_header = self.shared_header?;
+        let sub_wokers = SubWorkers::new(
+            self.timer_event_sender?,
+            self.recycle_unit_channel_config,
+            shared_header.metrics.clone(),
+        );
 
         let timer_event_receiver = self.timer_event_receiver?;
-        let shared_header = self.shared_header?;
         #[cfg(feature = "status-report")]
         let status_report_sender = self.status_report_sender;
 
@@ -162,70 +179,128 @@ impl EventHandle {
     }
 
     async fn handle_event(&mut self) {
-        // Turn on `feature` and have `status_report_sender` go this piece of logic.
+        while let Ok(event) = self.timer_event_receiver.recv().await {
+            self.report_event(&event).await;
+            self.event_dispatch(event).await;
+        }
+    }
+
+    // Turn on `feature` and have `status_report_sender`, the event will be reported as `PublicEvent`.
+    // The events derived inside `EventHandle` (e.g. by bulk operations) are reported through it too.
+    #[allow(unused_variables)]
+    async fn report_event(&self, event: &TimerEvent) {
         #[cfg(feature = "status-report")]
-        if let Some(status_report_sender) = self.status_report_sender.take() {
-            while let Ok(event) = self.timer_event_receiver.recv().await {
-                if let Ok(public_event) = PublicEvent::try_from(&event) {
-                    status_report_sender
-                        .send(public_event)
-                        .await
-                        .unwrap_or_else(|e| print!("{}", e));
-                }
-                self.event_dispatch(event).await;
+        if let Some(status_report_sender) = self.status_report_sender.as_ref() {
+            if let Ok(public_event) = PublicEvent::try_from(event) {
+                status_report_sender
+                    .send(public_event)
+                    .await
+                    .unwrap_or_else(|e| print!("{}", e));
             }
-            return;
         }
+    }
 
-        // Did not turn on `feature` or no `status_report_sender` go this piece of logic.
-        while let Ok(event) = self.timer_event_receiver.recv().await {
-            self.event_dispatch(event).await;
+    pub(crate) async fn event_dispatch(&mut self, event: TimerEvent) {
+        // The acknowledged event carries a reply for the result of handling.
+        let (event, reply_sender) = match event {
+            TimerEvent::Acknowledged(event, reply_sender) => (*event, Some(reply_sender)),
+            event => (event, None),
+        };
+
+        let result = self.handle_timer_event(event).await;
+        self.shared_header
+            .metrics
+            .observe_event(self.timer_event_receiver.len(), result.is_err());
+
+        match reply_sender {
+            Some(reply_sender) => reply_sender
+                .send(result)
+                .await
+                .unwrap_or_else(|e| error!("`event_dispatch`: {}", e)),
+            None => result.unwrap_or_else(|e| error!("{}", e)),
         }
     }
 
-    pub(crate) async fn event_dispatch(&mut self, event: TimerEvent) {
+    async fn handle_timer_event(&mut self, event: TimerEvent) -> Result<(), TaskError> {
         match event {
             TimerEvent::StopTimer => {
                 self.shared_header.shared_motivation.store(false, Release);
-                return;
             }
             TimerEvent::AddTask(task) => {
-                self.add_task(task)
-                    .map(|task_mark| self.record_task_mark(task_mark))
-                    .map_err(|e| error!("{}", e))
-                    .ok();
+                let task_mark = self.add_task(task).await?;
+                self.record_task_mark(task_mark);
             }
 
             TimerEvent::InsertTask(task, task_instances_chain_maintainer) => {
-                self.add_task(task)
-                    .map(|mut task_mark| {
-                        task_mark
-                            .set_task_instances_chain_maintainer(task_instances_chain_maintainer);
-                        self.record_task_mark(task_mark);
-                    })
-                    .map_err(|e| error!("{}", e))
-                    .ok();
+                let mut task_mark = self.add_task(task).await?;
+                task_mark.set_task_instances_chain_maintainer(task_instances_chain_maintainer);
+                self.record_task_mark(task_mark);
             }
 
             TimerEvent::UpdateTask(task) => {
-                self.update_task(task).await;
+                self.update_task(task).await?;
             }
 
             TimerEvent::AdvanceTask(task_id) => {
-                self.advance_task(task_id).await;
+                self.advance_task(task_id)
+                    .await
+                    .ok_or(TaskError::TaskNotFound(task_id))?;
             }
 
             TimerEvent::RemoveTask(task_id) => {
-                self.remove_task(task_id).await;
-
-                self.shared_header.task_flag_map.remove(&task_id);
+                self.remove_task_mark(task_id)
+                    .await
+                    .ok_or(TaskError::TaskNotFound(task_id))?;
             }
+
             TimerEvent::CancelTask(task_id, record_id) => {
-                self.cancel_task(task_id, record_id, state::instance::CANCELLED);
+                self.cancel_task(task_id, record_id, state::instance::CANCELLED)?;
+            }
+
+            TimerEvent::PauseTask(task_id) => {
+                self.set_task_paused(task_id, true)
+                    .ok_or(TaskError::TaskNotFound(task_id))?;
+            }
+
+            TimerEvent::ResumeTask(task_id) => {
+                self.set_task_paused(task_id, false)
+                    .ok_or(TaskError::TaskNotFound(task_id))?;
+            }
+
+            TimerEvent::RemoveTasks(task_selector) => {
+                for task_id in self.select_task_ids(&task_selector) {
+                    self.report_event(&TimerEvent::RemoveTask(task_id)).await;
+                    self.remove_task_mark(task_id).await;
+                }
+            }
+
+            TimerEvent::PauseTasks(task_selector) => {
+                for task_id in self.select_task_ids(&task_selector) {
+                    self.report_event(&TimerEvent::PauseTask(task_id)).await;
+                    self.set_task_paused(task_id, true);
+                }
+            }
+
+            TimerEvent::ResumeTasks(task_selector) => {
+                for task_id in self.select_task_ids(&task_selector) {
+                    self.report_event(&TimerEvent::ResumeTask(task_id)).await;
+                    self.set_task_paused(task_id, false);
+                }
+            }
+
+            TimerEvent::CancelInstances(task_selector) => {
+                for task_id in self.select_task_ids(&task_selector) {
+                    for record_id in self.task_trace.get_record_ids(task_id) {
+                        self.report_event(&TimerEvent::CancelTask(task_id, record_id))
+                            .await;
+                        self.cancel_task(task_id, record_id, state::instance::CANCELLED)
+                            .unwrap_or_else(|e| error!("{}", e));
+                    }
+                }
             }
 
             TimerEvent::TimeoutTask(task_id, record_id) => {
-                self.cancel_task(task_id, record_id, state::instance::TIMEOUT);
+                self.cancel_task(task_id, record_id, state::instance::TIMEOUT)?;
             }
 
             TimerEvent::AppendTaskHandle(task_id, delay_task_handler_box) => {
@@ -234,14 +309,44 @@ impl EventHandle {
             }
 
             TimerEvent::FinishTask(FinishTaskBody {
-                task_id, record_id, ..
+                task_id,
+                record_id,
+                finish_time,
+                finish_output,
+                state,
             }) => {
+                self.observe_instance_finished(task_id, record_id, state);
+                #[cfg(feature = "tracing-support")]
+                self.trace_instance_finished(task_id, record_id, state);
+                let execution_record = self
+                    .execution_record(task_id, record_id, state, finish_time)
+                    .map(|mut execution_record| {
+                        execution_record.set_finish_output(finish_output.as_ref());
+                        execution_record
+                    });
+                self.record_execution(execution_record);
+
                 //TODO: maintain a outside-task-handle , through it pass the _finish_time and final-state.
                 // Provide a separate start time for the external, record_id time with a delay.
                 // Or use snowflake.real_time to generate record_id , so you don't have to add a separate field.
-                self.finish_task(task_id, record_id);
+                self.finish_task(task_id, record_id, state);
+            }
+
+            TimerEvent::Reconcile(tasks, reply_sender) => {
+                let reconcile_report = self.reconcile(tasks).await;
+                reply_sender
+                    .send(reconcile_report)
+                    .await
+                    .unwrap_or_else(|e| error!("`reconcile`: {}", e));
+            }
+
+            // `DelayTimer` never nests the acknowledged event.
+            TimerEvent::Acknowledged(event, _) => {
+                error!("Nested acknowledged event : {:?}", event);
             }
         }
+
+        Ok(())
     }
 
     pub(crate) async fn send_recycle_unit_sources_sender(&self, recycle_unit: RecycleUnit) {
@@ -254,12 +359,24 @@ impl EventHandle {
     }
 
     // Add task to wheel_queue  slot
-    fn add_task(&mut self, mut task: Box<Task>) -> AnyResult<TaskMark> {
-        let second_hand = self.shared_header.second_hand.load(Acquire);
+    async fn add_task(&mut self, mut task: Box<Task>) -> Result<TaskMark, TaskError> {
+        let task_id = task.task_id;
+
+        let exec_time: u64 = match task.get_next_exec_timestamp() {
+            Some(exec_time) => exec_time,
+            None => {
+                // Release the `TaskMark` reserved by `DelayTimer`.
+                self.shared_header
+                    .task_flag_map
+                    .remove_if(&task_id, |_, task_mark| task_mark.is_reserved());
+                return Err(TaskError::NoFutureFireTime(task_id));
+            }
+        };
 
-        let exec_time: u64 = task
-            .get_next_exec_timestamp()
-            .ok_or_else(|| anyhow!("can't get_next_exec_timestamp in {}", &task.task_id))?;
+        // The old task with the same id is replaced.
+        self.remove_task(task_id).await;
+
+        let second_hand = self.shared_header.second_hand.load(Acquire);
 
         let timestamp = self.shared_header.global_time.load(Acquire);
         let time_seed: u64 = exec_time
@@ -269,9 +386,17 @@ impl EventHandle {
         let slot_seed: u64 = time_seed % DEFAULT_TIMER_SLOT_COUNT;
 
         task.set_cylinder_line(time_seed / DEFAULT_TIMER_SLOT_COUNT);
+        task.set_next_exec_timestamp(Some(exec_time));
+
+        let cron_expression = task.cron_expression.clone();
+        let name = task.name.clone();
+        let labels = task.labels.clone();
+        let digest = task.digest();
+        let history_capacity = task.history_capacity;
+
+        #[cfg(feature = "job-store")]
+        self.shared_header.save_task_record(&task);
 
-        // copy task_id
-        let task_id = task.task_id;
         if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_seed) {
             slot.value_mut().add_task(*task);
         }
@@ -279,6 +404,11 @@ impl EventHandle {
         let mut task_mart = TaskMark::default();
         task_mart
             .set_task_id(task_id)
+            .set_cron_expression(cron_expression)
+            .set_name(name)
+            .set_labels(labels)
+            .set_digest(digest)
+            .set_history_capacity(history_capacity)
             .set_slot_mark(slot_seed)
             .set_parallel_runable_num(0);
 
@@ -286,27 +416,62 @@ impl EventHandle {
     }
 
     // for record task-mark.
-    pub(crate) fn record_task_mark(&mut self, task_mark: TaskMark) {
+    // Running instances of the replaced task are still counted,
+    // and the replaced task keeps its paused state and the chain of its task instances.
+    pub(crate) fn record_task_mark(&mut self, mut task_mark: TaskMark) {
+        if let Some(mut old_task_mark) =
+            self.shared_header.task_flag_map.get_mut(&task_mark.task_id)
+        {
+            let old_task_mark = old_task_mark.value_mut();
+            task_mark.set_parallel_runable_num(old_task_mark.get_parallel_runable_num());
+
+            // The id of the exhausted task is reused by a new task, nothing else is kept.
+            if !old_task_mark.is_exhausted() {
+                task_mark.set_paused(old_task_mark.is_paused());
+                if task_mark.task_instances_chain_maintainer.is_none() {
+                    task_mark.task_instances_chain_maintainer =
+                        old_task_mark.task_instances_chain_maintainer.take();
+                }
+            }
+        }
+
         self.shared_header
             .task_flag_map
             .insert(task_mark.task_id, task_mark);
     }
 
-    // for update task.
-    pub(crate) async fn update_task(&mut self, task: Box<Task>) -> Option<Task> {
-        let task_mark = self.shared_header.task_flag_map.get(&task.task_id)?;
-
-        let slot_mark = task_mark.value().get_slot_mark();
+    // Remove the `TaskMark` of the exhausted task after its last running instance finishes,
+    // the execution records are kept.
+    fn release_exhausted_task_mark(&mut self, task_id: u64) {
+        self.shared_header
+            .task_flag_map
+            .remove_if(&task_id, |_, task_mark| {
+                task_mark.is_exhausted() && task_mark.get_parallel_runable_num() == 0
+            });
+    }
 
-        if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_mark) {
-            return slot.value_mut().update_task(*task);
+    // for update task.
+    // The task is re-added at the slot computed from its new schedule,
+    // its running instances, paused state and chain of task instances are kept.
+    pub(crate) async fn update_task(&mut self, task: Box<Task>) -> Result<(), TaskError> {
+        let task_id = task.task_id;
+        let is_live = self
+            .shared_header
+            .task_flag_map
+            .get(&task_id)
+            .map(|task_mark| task_mark.value().is_live())
+            .unwrap_or(false);
+        if !is_live {
+            return Err(TaskError::TaskNotFound(task_id));
         }
 
-        None
+        let task_mark = self.add_task(task).await?;
+        self.record_task_mark(task_mark);
+        Ok(())
     }
 
     // Take the initiative to perform once Task.
-    pub(crate) async fn advance_task(&mut self, task_id: u64) -> Option<Task> {
+    pub(crate) async fn advance_task(&mut self, task_id: u64) -> Option<()> {
         let task_mark = self.shared_header.task_flag_map.get(&task_id)?;
 
         let slot_mark = task_mark.value().get_slot_mark();
@@ -318,14 +483,21 @@ impl EventHandle {
                 return None;
             }
         };
+        // The advanced task is scheduled to run at the next tick.
         task.clear_cylinder_line();
+        task.set_next_exec_timestamp(None);
+        drop(task_mark);
 
-        let slot_seed = self.shared_header.second_hand.load(Acquire) + 1;
+        let slot_seed =
+            (self.shared_header.second_hand.load(Acquire) + 1) % DEFAULT_TIMER_SLOT_COUNT;
 
-        if let Some(mut slot) = self.shared_header.wheel_queue.get_mut(&slot_seed) {
-            return slot.value_mut().add_task(task);
+        if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+            task_mark.value_mut().set_slot_mark(slot_seed);
         }
-        None
+
+        let mut slot = self.shared_header.wheel_queue.get_mut(&slot_seed)?;
+        slot.value_mut().add_task(task);
+        Some(())
     }
 
     // for remove task.
@@ -341,46 +513,253 @@ impl EventHandle {
         None
     }
 
+    // Remove the task and its `TaskMark`, return `None` if the task is not found.
+    pub(crate) async fn remove_task_mark(&mut self, task_id: u64) -> Option<TaskMark> {
+        self.remove_task(task_id).await;
+
+        #[cfg(feature = "job-store")]
+        self.shared_header.remove_task_record(task_id);
+        self.shared_header.execution_history.remove(task_id);
+        self.shared_header.metrics.remove_task(task_id);
+
+        self.shared_header
+            .task_flag_map
+            .remove(&task_id)
+            .map(|(_, task_mark)| task_mark)
+    }
+
+    // Converge the tasks in the wheel to the desired tasks,
+    // no other event is handled until the whole batch is applied.
+    pub(crate) async fn reconcile(&mut self, tasks: Vec<Task>) -> ReconcileReport {
+        let mut reconcile_report = ReconcileReport::default();
+        // The task ids are unique, it's checked before the batch is sent.
+        let desired_task_ids: HashSet<u64> = tasks.iter().map(|task| task.task_id).collect();
+
+        for task in tasks {
+            let task_id = task.task_id;
+
+            // The reserved `TaskMark` belongs to a task that is still in the event-channel.
+            let current_digest = self
+                .shared_header
+                .task_flag_map
+                .get(&task_id)
+                .filter(|task_mark| task_mark.value().is_live())
+                .map(|task_mark| task_mark.value().digest.clone());
+
+            match current_digest {
+                Some(digest) if digest == task.digest() => {
+                    reconcile_report.unchanged.push(task_id);
+                }
+                // The changed task is re-added at the slot computed from its new schedule.
+                Some(_) => match self.add_task(Box::new(task)).await {
+                    Ok(task_mark) => {
+                        self.record_task_mark(task_mark);
+                        reconcile_report.updated.push(task_id);
+                    }
+                    Err(e) => reconcile_report.failed.push((task_id, e)),
+                },
+                None => match self.add_task(Box::new(task)).await {
+                    Ok(task_mark) => {
+                        self.record_task_mark(task_mark);
+                        reconcile_report.added.push(task_id);
+                    }
+                    Err(e) => reconcile_report.failed.push((task_id, e)),
+                },
+            }
+        }
+
+        let redundant_task_ids: Vec<u64> = self
+            .shared_header
+            .task_flag_map
+            .iter()
+            .filter(|task_mark| {
+                task_mark.value().is_live() && !desired_task_ids.contains(task_mark.key())
+            })
+            .map(|task_mark| *task_mark.key())
+            .collect();
+
+        for task_id in redundant_task_ids {
+            self.report_event(&TimerEvent::RemoveTask(task_id)).await;
+            self.remove_task_mark(task_id).await;
+            reconcile_report.removed.push(task_id);
+        }
+
+        reconcile_report
+    }
+
+    // Collect the task_ids of tasks matched by the selector.
+    pub(crate) fn select_task_ids(&self, task_selector: &TaskSelector) -> Vec<u64> {
+        self.shared_header
+            .task_flag_map
+            .iter()
+            .filter(|task_mark| task_selector.matches(task_mark.value()))
+            .map(|task_mark| *task_mark.key())
+            .collect()
+    }
+
+    pub(crate) fn set_task_paused(&mut self, task_id: u64, paused: bool) -> Option<()> {
+        let mut task_mark = self.shared_header.task_flag_map.get_mut(&task_id)?;
+        task_mark.value_mut().set_paused(paused);
+        Some(())
+    }
+
     pub(crate) fn cancel_task(
         &mut self,
         task_id: u64,
         record_id: i64,
         state: usize,
-    ) -> Option<Result<()>> {
-        if let Some(mut task_mark_ref_mut) = self.shared_header.task_flag_map.get_mut(&task_id) {
-            let task_mark = task_mark_ref_mut.value_mut();
+    ) -> Result<(), TaskError> {
+        let execution_record = self.execution_record(task_id, record_id, state, get_timestamp());
+        let run_duration = self.run_duration(task_id, record_id);
+
+        let mut task_mark_ref_mut = self
+            .shared_header
+            .task_flag_map
+            .get_mut(&task_id)
+            .ok_or(TaskError::TaskNotFound(task_id))?;
 
-            task_mark.dec_parallel_runable_num();
+        #[cfg(feature = "tracing-support")]
+        self.trace_instance_finished(task_id, record_id, state);
 
-            // Here the user can be notified that the task instance has disappeared via `Instance`.
-            task_mark.notify_cancel_finish(record_id, state);
+        let quit_result = self
+            .task_trace
+            .quit_one_task_handler(task_id, record_id, state)
+            .ok_or(TaskError::InstanceNotFound(task_id, record_id))?;
 
-            return self.task_trace.quit_one_task_handler(task_id, record_id);
+        let task_mark = task_mark_ref_mut.value_mut();
+
+        task_mark.dec_parallel_runable_num();
+
+        // Here the user can be notified that the task instance has disappeared via `Instance`.
+        task_mark.notify_cancel_finish(record_id, state);
+
+        let history_capacity = task_mark.history_capacity;
+        drop(task_mark_ref_mut);
+
+        if let Some(execution_record) = execution_record {
+            self.shared_header
+                .execution_history
+                .record(execution_record, history_capacity);
         }
-        Some(Err(anyhow!(
-            "Without the `task_mark_ref_mut` for task_id :{}, record_id : {}, state : {}",
-            task_id,
-            record_id,
-            state
-        )))
+        self.shared_header
+            .metrics
+            .observe_instance_finished(task_id, state, run_duration);
+        self.release_exhausted_task_mark(task_id);
+
+        quit_result.map_err(|e| TaskError::DisCancel(e.to_string()))
     }
 
-    pub(crate) fn finish_task(&mut self, task_id: u64, record_id: i64) -> Option<Result<()>> {
-        if let Some(mut task_mark_ref_mut) = self.shared_header.task_flag_map.get_mut(&task_id) {
-            let task_mark = task_mark_ref_mut.value_mut();
+    // Build the execution record from the handle of running instance.
+    fn execution_record(
+        &self,
+        task_id: u64,
+        record_id: i64,
+        state: InstanceState,
+        finish_time: u64,
+    ) -> Option<ExecutionRecord> {
+        self.task_trace
+            .get_task_handler_box(task_id, record_id)
+            .map(|delay_task_handler_box| {
+                ExecutionRecord::new(delay_task_handler_box, state, finish_time)
+            })
+    }
 
-            task_mark.dec_parallel_runable_num();
+    // The run duration of the running instance until now, in microseconds.
+    fn run_duration(&self, task_id: u64, record_id: i64) -> Option<u64> {
+        self.task_trace
+            .get_task_handler_box(task_id, record_id)
+            .map(|delay_task_handler_box| {
+                (get_timestamp_micros() as u64)
+                    .saturating_sub(delay_task_handler_box.get_start_time_micros())
+            })
+    }
+
+    // The metrics of the removed task are dropped.
+    fn observe_instance_finished(&self, task_id: u64, record_id: i64, state: InstanceState) {
+        if !self.shared_header.task_flag_map.contains_key(&task_id) {
+            return;
+        }
+
+        let run_duration = self.run_duration(task_id, record_id);
+        self.shared_header
+            .metrics
+            .observe_instance_finished(task_id, state, run_duration);
+    }
+
+    // Emit the event inside the span of the running instance.
+    #[cfg(feature = "tracing-support")]
+    fn trace_instance_finished(&self, task_id: u64, record_id: i64, state: InstanceState) {
+        let delay_task_handler_box = match self.task_trace.get_task_handler_box(task_id, record_id)
+        {
+            Some(delay_task_handler_box) => delay_task_handler_box,
+            None => return,
+        };
+        let span = delay_task_handler_box.get_span();
+
+        match state {
+            state::instance::TIMEOUT => {
+                tracing::warn!(parent: span, task_id, record_id, "Instance timed out.")
+            }
+            state::instance::CANCELLED => {
+                tracing::info!(parent: span, task_id, record_id, "Instance cancelled.")
+            }
+            state::instance::FAILED => {
+                tracing::warn!(parent: span, task_id, record_id, "Instance failed.")
+            }
+            _ => tracing::info!(parent: span, task_id, record_id, "Instance completed."),
+        }
+    }
+
+    // Keep the execution record if the task is still in the timer.
+    fn record_execution(&self, execution_record: Option<ExecutionRecord>) {
+        let execution_record = match execution_record {
+            Some(execution_record) => execution_record,
+            None => return,
+        };
 
-            // Here the user can be notified that the task instance has disappeared via `Instance`.
-            task_mark.notify_cancel_finish(record_id, state::instance::COMPLETED);
+        let history_capacity = self
+            .shared_header
+            .task_flag_map
+            .get(&execution_record.task_id)
+            .map(|task_mark| task_mark.value().history_capacity);
 
-            return self.task_trace.quit_one_task_handler(task_id, record_id);
+        if let Some(history_capacity) = history_capacity {
+            self.shared_header
+                .execution_history
+                .record(execution_record, history_capacity);
         }
-        Some(Err(anyhow!(
-            "Without the `task_mark_ref_mut` for task_id :{}, record_id : {}",
-            task_id,
-            record_id
-        )))
+    }
+
+    pub(crate) fn finish_task(
+        &mut self,
+        task_id: u64,
+        record_id: i64,
+        state: InstanceState,
+    ) -> Option<Result<()>> {
+        let mut task_mark_ref_mut = match self.shared_header.task_flag_map.get_mut(&task_id) {
+            Some(task_mark_ref_mut) => task_mark_ref_mut,
+            None => {
+                return Some(Err(anyhow!(
+                    "Without the `task_mark_ref_mut` for task_id :{}, record_id : {}",
+                    task_id,
+                    record_id
+                )))
+            }
+        };
+        let task_mark = task_mark_ref_mut.value_mut();
+
+        task_mark.dec_parallel_runable_num();
+
+        // Here the user can be notified that the task instance has disappeared via `Instance`.
+        task_mark.notify_cancel_finish(record_id, state);
+
+        let quit_result = self
+            .task_trace
+            .quit_one_task_handler(task_id, record_id, state);
+        drop(task_mark_ref_mut);
+
+        self.release_exhausted_task_mark(task_id);
+        quit_result
     }
 
     pub(crate) async fn maintain_task_status(
@@ -388,25 +767,24 @@ impl EventHandle {
         task_id: u64,
         delay_task_handler_box: DelayTaskHandlerBox,
     ) {
-        {
-            if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
-                let task_instances_chain_maintainer_option =
-                    task_mark.value_mut().get_task_instances_chain_maintainer();
-
-                if let Some(task_instances_chain_maintainer) =
-                    task_instances_chain_maintainer_option
-                {
-                    let instance = Instance::default()
-                        .set_task_id(task_id)
-                        .set_record_id(delay_task_handler_box.get_record_id());
-
-                    task_instances_chain_maintainer
-                        .push_instance(instance)
-                        .await;
-                }
-            } else {
-                error!("Missing task_mark for task_id : {}", task_id)
+        let instance = delay_task_handler_box.get_instance().clone();
+        let instance_sender = match self.shared_header.task_flag_map.get_mut(&task_id) {
+            Some(mut task_mark) => task_mark
+                .value_mut()
+                .get_task_instances_chain_maintainer()
+                .map(|task_instances_chain_maintainer| {
+                    task_instances_chain_maintainer.push_instance(instance.clone())
+                }),
+            None => {
+                error!("Missing task_mark for task_id : {}", task_id);
+                None
             }
+        };
+
+        // The `TaskMark` is released before sending, so the chain that isn't consumed
+        // doesn't block the callers that access the task.
+        if let Some(instance_sender) = instance_sender {
+            instance_sender.send(instance).await.ok();
         }
 
         // If has deadline, set recycle_unit.
@@ -437,7 +815,7 @@ cfg_status_report!(
 impl EventHandleBuilder {
     pub(crate) fn status_report_sender(
         &mut self,
-        status_report_sender: AsyncSender<PublicEvent>,
+        status_report_sender: OverflowSender<PublicEvent>,
     ) -> &mut Self {
         self.status_report_sender = Some(status_report_sender);
         self
@@ -446,8 +824,13 @@ impl EventHandleBuilder {
 );
 
 impl SubWorkers {
-    fn new(timer_event_sender: TimerEventSender) -> Self {
-        let recycling_bin_woker = RecyclingBinWorker::new(timer_event_sender);
+    fn new(
+        timer_event_sender: TimerEventSender,
+        recycle_unit_channel_config: ChannelConfig,
+        metrics: Metrics,
+    ) -> Self {
+        let recycling_bin_woker =
+            RecyclingBinWorker::new(timer_event_sender, recycle_unit_channel_config, metrics);
 
         SubWorkers {
             recycling_bin_woker,
@@ -456,13 +839,18 @@ impl SubWorkers {
 }
 
 impl RecyclingBinWorker {
-    fn new(timer_event_sender: TimerEventSender) -> Self {
+    fn new(
+        timer_event_sender: TimerEventSender,
+        recycle_unit_channel_config: ChannelConfig,
+        metrics: Metrics,
+    ) -> Self {
         let (recycle_unit_sources_sender, recycle_unit_sources_reciver) =
-            unbounded::<RecycleUnit>();
+            recycle_unit_channel_config.channel::<RecycleUnit>();
 
         let inner = Arc::new(RecyclingBins::new(
             recycle_unit_sources_reciver,
             timer_event_sender,
+            metrics,
         ));
 
         RecyclingBinWorker {
@@ -0,0 +1,135 @@
+//! Job
+//! It is a module that adapts the stateful `Job` to the body of task,
+//! the job is shared by all the running instances of task.
+use super::runtime_trace::task_handle::DelayTaskHandler;
+use super::task::spawn_by_runtime;
+use crate::prelude::*;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+/// A task implemented by a type, so the state can be shared by its running instances,
+/// the job can be tested without timer, and it can react to the lifecycle of instances by the hooks.
+///
+/// The job is spawned by `TaskBuilder::spawn_job`, `run` is called for every task running instance.
+/// The instance finishes with the output of `run` (see `IntoFinishOutput`),
+/// it's `instance::FAILED` with the error if `run` returns `Err`.
+///
+/// ```
+/// use delay_timer::prelude::*;
+/// use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+/// use std::sync::Arc;
+///
+/// #[derive(Default)]
+/// struct CountJob {
+///     runs: AtomicUsize,
+/// }
+///
+/// #[async_trait]
+/// impl Job for CountJob {
+///     type Output = ();
+///
+///     async fn run(&self, _context: TaskContext) -> AnyResult<()> {
+///         self.runs.fetch_add(1, Relaxed);
+///         Ok(())
+///     }
+///
+///     async fn on_failure(&self, context: &TaskContext, error: &anyhow::Error) {
+///         println!("The task {} failed: {}", context.task_id, error);
+///     }
+/// }
+///
+/// let task = TaskBuilder::default()
+///     .set_task_id(1)
+///     .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Secondly))
+///     .spawn_job(Arc::new(CountJob::default()));
+/// ```
+#[async_trait]
+pub trait Job: Send + Sync + 'static {
+    /// The output of the successful run, e.g. `()`.
+    type Output: IntoFinishOutput + Send + Sync;
+
+    /// Run the job for a task running instance.
+    async fn run(&self, context: TaskContext) -> AnyResult<Self::Output>;
+
+    /// It's called after `run` returns `Ok`, before the instance finishes.
+    async fn on_success(&self, _context: &TaskContext, _output: &Self::Output) {}
+
+    /// It's called after `run` returns `Err`, before the instance finishes.
+    async fn on_failure(&self, _context: &TaskContext, _error: &anyhow::Error) {}
+
+    /// It's called after the instance is timeout and the future of `run` is dropped.
+    async fn on_timeout(&self, _context: &TaskContext) {}
+
+    /// It's called after the instance is cancelled and the future of `run` is dropped.
+    async fn on_cancel(&self, _context: &TaskContext) {}
+}
+
+/// Spawn the job for the task running instance,
+/// the handler runs the hook of job when the instance is cancelled or timeout.
+pub(crate) fn spawn_job_instance<J: Job>(
+    job: &Arc<J>,
+    context: TaskContext,
+) -> Box<dyn DelayTaskHandler> {
+    let job_ref = job.clone();
+    let future = context.clone().instrument(|context| async move {
+        let finish_output = match job_ref.run(context.clone()).await {
+            Ok(output) => {
+                job_ref.on_success(&context, &output).await;
+                output.into_finish_output()
+            }
+            Err(e) => {
+                job_ref.on_failure(&context, &e).await;
+                Some(FinishOutput::ExceptionOutput(format!("{:#}", e)))
+            }
+        };
+        context.finishe_task(finish_output).await;
+    });
+
+    let task_handler = spawn_by_runtime(context.runtime_kind, future);
+    Box::new(JobHandler {
+        job: job.clone(),
+        context,
+        task_handler,
+    })
+}
+
+struct JobHandler<J: Job> {
+    job: Arc<J>,
+    context: TaskContext,
+    task_handler: Box<dyn DelayTaskHandler>,
+}
+
+impl<J: Job> DelayTaskHandler for JobHandler<J> {
+    fn quit(self: Box<Self>) -> AnyResult<()> {
+        let JobHandler {
+            job,
+            context,
+            task_handler,
+        } = *self;
+        task_handler.quit()?;
+
+        // The state is set before the handler quits, the finished instance has no hook.
+        let state = context.instance.as_ref().map(Instance::get_state);
+        if state != Some(state::instance::CANCELLED) && state != Some(state::instance::TIMEOUT) {
+            return Ok(());
+        }
+
+        let runtime_kind = context.runtime_kind;
+        let hook = context.instrument(move |context| async move {
+            if state == Some(state::instance::TIMEOUT) {
+                job.on_timeout(&context).await;
+            } else {
+                job.on_cancel(&context).await;
+            }
+        });
+        match runtime_kind {
+            RuntimeKind::Smol => async_spawn(hook).detach(),
+            // The tokio task is detached when its handle is dropped.
+            #[cfg(feature = "tokio-support")]
+            RuntimeKind::Tokio => drop(async_spawn_by_tokio(hook)),
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 //! timer is the core module of the library , it can provide an API for task building ,
 //! task scheduling , event handling , resource recovery .
 pub(crate) mod event_handle;
+pub mod job;
 pub(crate) mod runtime_trace;
 pub(crate) mod slot;
 pub mod task;
@@ -11,6 +11,10 @@ pub mod instance {
 
     /// Set if the task has been Timeout.
     pub const TIMEOUT: usize = 1 << 4;
+
+    /// Set if the task has finished with the exception output,
+    /// or the process task has exited with the code that isn't declared as success.
+    pub const FAILED: usize = 1 << 5;
 }
 
 pub(crate) mod instance_chain {
@@ -11,6 +11,7 @@ use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd, Reverse};
 use std::collections::BinaryHeap;
 use std::sync::Arc;
 
+use crate::utils::metrics::Metrics;
 use smol::channel::TryRecvError::*;
 
 #[derive(Default, Eq, Debug, Copy, Clone)]
@@ -65,6 +66,9 @@ pub(crate) struct RecyclingBins {
 
     /// notify timeout-event to event-handler for cancel that.
     timer_event_sender: TimerEventSender,
+
+    /// count the recycle-units waiting in the heap.
+    metrics: Metrics,
 }
 
 impl RecyclingBins {
@@ -72,6 +76,7 @@ impl RecyclingBins {
     pub(crate) fn new(
         recycle_unit_sources: AsyncReceiver<RecycleUnit>,
         timer_event_sender: TimerEventSender,
+        metrics: Metrics,
     ) -> Self {
         let recycle_unit_heap: AsyncMutex<BinaryHeap<Reverse<RecycleUnit>>> =
             AsyncMutex::new(BinaryHeap::new());
@@ -81,6 +86,7 @@ impl RecyclingBins {
             recycle_unit_heap,
             recycle_unit_sources,
             timer_event_sender,
+            metrics,
         }
     }
 
@@ -94,6 +100,7 @@ impl RecyclingBins {
 
         loop {
             let mut recycle_unit_heap = self.recycle_unit_heap.lock().await;
+            let mut timeout_units = Vec::new();
 
             let now: u64 = get_timestamp();
             for _ in 0..200 {
@@ -103,28 +110,33 @@ impl RecyclingBins {
                 if let Some(recycle_flag) = (&recycle_unit_heap).peek().map(|r| r.0.deadline <= now)
                 {
                     if !recycle_flag {
-                        drop(recycle_unit_heap);
                         break;
                     }
 
                     if let Some(recycle_unit) = (&mut recycle_unit_heap).pop().map(|v| v.0) {
-                        //handle send-error.
-                        self.send_timer_event(TimerEvent::TimeoutTask(
-                            recycle_unit.task_id,
-                            recycle_unit.record_id,
-                        ))
-                        .await;
+                        self.metrics.dec_recycle_unit_num();
+                        timeout_units.push(recycle_unit);
                     }
-
-                //send msg to event_handle.
                 } else {
-                    drop(recycle_unit_heap);
                     break;
                 }
             }
 
+            // Drop lock before sending, the bounded event-channel may be full,
+            // and `add_recycle_unit` should not be blocked by it.
+            drop(recycle_unit_heap);
+
+            //send msg to event_handle.
+            for recycle_unit in timeout_units {
+                //handle send-error.
+                self.send_timer_event(TimerEvent::TimeoutTask(
+                    recycle_unit.task_id,
+                    recycle_unit.record_id,
+                ))
+                .await;
+            }
+
             yield_now().await;
-            //drop lock.
         }
     }
 
@@ -143,6 +155,7 @@ impl RecyclingBins {
                 match self.recycle_unit_sources.lock().await.try_recv() {
                     Ok(recycle_unit) => {
                         (&mut recycle_unit_heap).push(Reverse(recycle_unit));
+                        self.metrics.inc_recycle_unit_num();
                     }
 
                     Err(e) => match e {
@@ -171,7 +184,7 @@ mod tests {
 
     #[test]
     fn test_task_valid() -> AnyResult<()> {
-        use super::{get_timestamp, RecycleUnit, RecyclingBins, TimerEvent};
+        use super::{get_timestamp, Metrics, RecycleUnit, RecyclingBins, TimerEvent};
         use smol::{
             block_on,
             channel::{unbounded, TryRecvError},
@@ -189,6 +202,7 @@ mod tests {
         let recycling_bins = Arc::new(RecyclingBins::new(
             recycle_unit_receiver,
             timer_event_sender,
+            Metrics::default(),
         ));
         //TODO:optimize.
         thread_spawn(move || {
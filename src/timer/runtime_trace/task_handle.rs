@@ -25,6 +25,26 @@ impl TaskTrace {
             .push_back(task_handler_box);
     }
 
+    // Get the record_ids of all running instances of the task.
+    pub(crate) fn get_record_ids(&self, task_id: u64) -> Vec<i64> {
+        self.inner
+            .get(&task_id)
+            .map(|list| list.iter().map(|d| d.record_id).collect())
+            .unwrap_or_default()
+    }
+
+    // Get the handle of the running instance.
+    pub(crate) fn get_task_handler_box(
+        &self,
+        task_id: u64,
+        record_id: i64,
+    ) -> Option<&DelayTaskHandlerBox> {
+        self.inner
+            .get(&task_id)?
+            .iter()
+            .find(|d| d.record_id == record_id)
+    }
+
     #[allow(dead_code)]
     pub(crate) fn clear(self) {
         for (_task_id, task_handler_box_list) in self.inner.into_iter() {
@@ -45,6 +65,7 @@ impl TaskTrace {
         &mut self,
         task_id: u64,
         record_id: i64,
+        state: InstanceState,
     ) -> Option<Result<()>> {
         let task_handler_list = self.inner.get_mut(&task_id)?;
 
@@ -68,7 +89,7 @@ impl TaskTrace {
         //remove current task_handler_box.
         list_mut_cursor
             .remove_current()
-            .map(|mut task_handler_box| task_handler_box.quit())
+            .map(|mut task_handler_box| task_handler_box.quit_with_state(state))
     }
 
     #[cfg(not(RUSTC_IS_NIGHTLY))]
@@ -76,6 +97,7 @@ impl TaskTrace {
         &mut self,
         task_id: u64,
         record_id: i64,
+        state: InstanceState,
     ) -> Option<Result<()>> {
         let task_handler_list = self.inner.get_mut(&task_id)?;
         let index = task_handler_list
@@ -85,7 +107,7 @@ impl TaskTrace {
         let mut has_remove_element_list = task_handler_list.split_off(index);
         let mut remove_element = has_remove_element_list.pop_front()?;
         task_handler_list.append(&mut has_remove_element_list);
-        Some(remove_element.quit())
+        Some(remove_element.quit_with_state(state))
     }
 }
 
@@ -124,11 +146,17 @@ pub struct DelayTaskHandlerBox {
     task_id: u64,
     ///Globally unique ID.
     record_id: i64,
-    ///it's start_time.
-    #[allow(dead_code)]
-    start_time: u64,
+    ///it's scheduled_time.
+    scheduled_time: u64,
+    ///it's start_time in microseconds.
+    start_time_micros: u64,
     ///it's end_time.
     end_time: Option<u64>,
+    ///it's instance, that's pushed to the chain of task instances.
+    instance: Instance,
+    ///it's span, boxed to keep `TimerEvent` small.
+    #[cfg(feature = "tracing-support")]
+    span: Box<tracing::Span>,
 }
 
 impl Drop for DelayTaskHandlerBox {
@@ -144,12 +172,14 @@ impl Drop for DelayTaskHandlerBox {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy)]
+#[derive(Default, Debug, Clone)]
 pub(crate) struct DelayTaskHandlerBoxBuilder {
     task_id: u64,
     record_id: i64,
-    start_time: u64,
+    scheduled_time: u64,
+    start_time_micros: u64,
     end_time: Option<u64>,
+    instance: Instance,
 }
 
 impl DelayTaskHandlerBoxBuilder {
@@ -166,8 +196,14 @@ impl DelayTaskHandlerBoxBuilder {
     }
 
     #[inline(always)]
-    pub fn set_start_time(mut self, start_time: u64) -> Self {
-        self.start_time = start_time;
+    pub fn set_scheduled_time(mut self, scheduled_time: u64) -> Self {
+        self.scheduled_time = scheduled_time;
+        self
+    }
+
+    #[inline(always)]
+    pub fn set_start_time_micros(mut self, start_time_micros: u64) -> Self {
+        self.start_time_micros = start_time_micros;
         self
     }
 
@@ -177,14 +213,24 @@ impl DelayTaskHandlerBoxBuilder {
         self
     }
 
+    #[inline(always)]
+    pub fn set_instance(mut self, instance: Instance) -> Self {
+        self.instance = instance;
+        self
+    }
+
     pub fn spawn(self, task_handler: Box<dyn DelayTaskHandler>) -> DelayTaskHandlerBox {
         let task_handler = SafeStructBoxedDelayTaskHandler(task_handler);
         DelayTaskHandlerBox {
             task_handler: Some(task_handler),
             task_id: self.task_id,
             record_id: self.record_id,
-            start_time: self.start_time,
+            scheduled_time: self.scheduled_time,
+            start_time_micros: self.start_time_micros,
             end_time: self.end_time,
+            instance: self.instance,
+            #[cfg(feature = "tracing-support")]
+            span: Box::new(tracing::Span::none()),
         }
     }
 }
@@ -206,11 +252,51 @@ impl DelayTaskHandlerBox {
         self.record_id
     }
 
+    #[inline(always)]
+    pub fn get_scheduled_time(&self) -> u64 {
+        self.scheduled_time
+    }
+
+    #[inline(always)]
+    pub fn get_start_time(&self) -> u64 {
+        self.start_time_micros / 1_000_000
+    }
+
+    #[inline(always)]
+    pub fn get_start_time_micros(&self) -> u64 {
+        self.start_time_micros
+    }
+
     #[inline(always)]
     pub fn get_end_time(&self) -> Option<u64> {
         self.end_time
     }
 
+    #[inline(always)]
+    pub fn get_instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    #[cfg(feature = "tracing-support")]
+    #[inline(always)]
+    pub fn get_span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    #[cfg(feature = "tracing-support")]
+    #[inline(always)]
+    pub fn set_span(&mut self, span: tracing::Span) -> &mut Self {
+        *self.span = span;
+        self
+    }
+
+    // The state is set on the instance before the handler quits,
+    // the instance isn't in any chain if the task is added without `TaskInstancesChain`.
+    fn quit_with_state(&mut self, state: InstanceState) -> Result<()> {
+        self.instance.set_state(state);
+        self.quit()
+    }
+
     fn quit(&mut self) -> Result<()> {
         if let Some(task_handler) = self.task_handler.take() {
             return task_handler.get_inner().quit();
@@ -2,12 +2,14 @@ use crate::prelude::*;
 
 use std::collections::LinkedList;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use crate::utils::output::{OutputLine, OUTPUT_SUBSCRIBER_CAPACITY};
+use crate::utils::overflow::{ChannelConfig, OverflowSender};
 use event_listener::Event;
 use future_lite::block_on;
-use smol::channel::{unbounded, Receiver, Sender};
+use smol::channel::{bounded, Receiver, Sender};
 
 /// instance of task running.
 #[derive(Debug, Default, Clone)]
@@ -33,6 +35,8 @@ pub(crate) struct InstanceHeader {
     event: Event,
     /// The state of inner taskInstance.
     state: AtomicUsize,
+    /// The subscribers of the output of inner taskInstance.
+    output_subscribers: Mutex<Vec<Sender<OutputLine>>>,
 }
 
 impl Default for InstanceHeader {
@@ -40,22 +44,31 @@ impl Default for InstanceHeader {
         let event = Event::new();
         let state = AtomicUsize::new(state::instance::RUNNING);
 
-        InstanceHeader { event, state }
+        let output_subscribers = Mutex::new(Vec::new());
+
+        InstanceHeader {
+            event,
+            state,
+            output_subscribers,
+        }
     }
 }
 
-pub(crate) fn task_instance_chain_pair() -> (TaskInstancesChain, TaskInstancesChainMaintainer) {
-    let (inner_sender, inner_receiver) = unbounded::<Instance>();
+pub(crate) fn task_instance_chain_pair(
+    channel_config: ChannelConfig,
+) -> (TaskInstancesChain, TaskInstancesChainMaintainer) {
+    let (inner_sender, inner_receiver) = channel_config.overflow_channel::<Instance>();
     let inner_state = Arc::new(AtomicUsize::new(state::instance_chain::LIVING));
     let inner_list = LinkedList::new();
 
     let chain = TaskInstancesChain {
+        task_id: 0,
         inner_receiver,
         inner_state: inner_state.clone(),
         timer_event_sender: None,
     };
     let chain_maintainer = TaskInstancesChainMaintainer {
-        inner_sender,
+        inner_sender: Arc::new(inner_sender),
         inner_state,
         inner_list,
     };
@@ -67,6 +80,7 @@ pub(crate) fn task_instance_chain_pair() -> (TaskInstancesChain, TaskInstancesCh
 /// For User access to Running-Task's instance.
 #[derive(Debug)]
 pub struct TaskInstancesChain {
+    pub(crate) task_id: u64,
     pub(crate) inner_receiver: Receiver<Instance>,
     pub(crate) inner_state: Arc<AtomicUsize>,
     pub(crate) timer_event_sender: Option<TimerEventSender>,
@@ -76,7 +90,7 @@ pub struct TaskInstancesChain {
 /// For inner maintain to Running-Task's instance.
 #[derive(Debug)]
 pub struct TaskInstancesChainMaintainer {
-    pub(crate) inner_sender: Sender<Instance>,
+    pub(crate) inner_sender: Arc<OverflowSender<Instance>>,
     pub(crate) inner_state: Arc<AtomicUsize>,
     pub(crate) inner_list: LinkedList<Instance>,
 }
@@ -120,6 +134,28 @@ impl Instance {
     pub(crate) fn notify_cancel_finish(&self, state: usize) {
         self.set_state(state);
         self.header.event.notify(usize::MAX);
+
+        // The receivers of output are closed after the instance finishes.
+        if let Ok(mut output_subscribers) = self.header.output_subscribers.lock() {
+            output_subscribers.clear();
+        }
+    }
+
+    pub(crate) fn subscribe_output(&self) -> Receiver<OutputLine> {
+        let (sender, receiver) = bounded(OUTPUT_SUBSCRIBER_CAPACITY);
+        if let Ok(mut output_subscribers) = self.header.output_subscribers.lock() {
+            if self.get_state() == state::instance::RUNNING {
+                output_subscribers.push(sender);
+            }
+        }
+        receiver
+    }
+
+    // The subscriber that is full or dropped is removed, so its receiver is closed.
+    pub(crate) fn publish_output(&self, output_line: OutputLine) {
+        if let Ok(mut output_subscribers) = self.header.output_subscribers.lock() {
+            output_subscribers.retain(|sender| sender.try_send(output_line.clone()).is_ok());
+        }
     }
 }
 
@@ -142,6 +178,17 @@ impl TaskInstance {
         self.instance.get_state()
     }
 
+    /// Subscribe the output of the running process task line by line,
+    /// the receiver is closed when the instance finishes.
+    ///
+    /// The lines printed before subscribing are not received.
+    /// At most `OUTPUT_SUBSCRIBER_CAPACITY` lines are buffered for the receiver,
+    /// it's closed early if it can't keep up, so a slow subscriber can't exhaust the memory.
+    #[inline(always)]
+    pub fn subscribe_output(&self) -> Receiver<OutputLine> {
+        self.instance.subscribe_output()
+    }
+
     /// Cancel the currently running task instance and block the thread to wait.
     #[inline(always)]
     pub fn cancel_with_wait(&self) -> Result<InstanceState, TaskInstanceError> {
@@ -192,15 +239,20 @@ impl TaskInstance {
 }
 
 impl TaskInstancesChainMaintainer {
-    pub(crate) async fn push_instance(&mut self, instance: Instance) {
-        self.inner_sender
-            .send(instance.clone())
-            .await
-            .map_or((), |_| {});
+    // The instance is kept in the list, and it should be sent by the returned sender
+    // after the `TaskMark` is released, because the sender may wait for the consumer of chain.
+    pub(crate) fn push_instance(&mut self, instance: Instance) -> Arc<OverflowSender<Instance>> {
         self.inner_list.push_back(instance);
+        self.inner_sender.clone()
     }
 }
 impl TaskInstancesChain {
+    /// Get the id of task that the chain belongs to.
+    #[inline(always)]
+    pub fn get_task_id(&self) -> u64 {
+        self.task_id
+    }
+
     /// Non-blocking get the next task instance.
     pub fn next(&self) -> Result<TaskInstance, TaskInstanceError> {
         let timer_event_sender = self.get_timer_event_sender()?;
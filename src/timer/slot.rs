@@ -2,7 +2,6 @@
 //! It is the scale of the internal clock.
 use super::task::Task;
 use std::collections::HashMap;
-use std::mem::swap;
 
 //Slot is based on HashMap, It easy to add it and find it.
 pub(crate) struct Slot {
@@ -23,15 +22,8 @@ impl Slot {
         self.task_map.insert(task.task_id, task)
     }
 
-    pub(crate) fn update_task(&mut self, mut task: Task) -> Option<Task> {
-        match self.task_map.get_mut(&task.task_id) {
-            Some(t) => {
-                swap(t, &mut task);
-                Some(task)
-            }
-
-            None => self.task_map.insert(task.task_id, task),
-        }
+    pub(crate) fn contains_task(&self, task_id: u64) -> bool {
+        self.task_map.contains_key(&task_id)
     }
 
     pub(crate) fn remove_task(&mut self, task_id: u64) -> Option<Task> {
@@ -1,20 +1,51 @@
 //! Task
 //! It is a basic periodic task execution unit.
+use super::job::{spawn_job_instance, Job};
 use super::runtime_trace::task_handle::DelayTaskHandler;
 use crate::prelude::*;
 
+use std::any::Any;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Pointer;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use cron_clock::{Schedule, ScheduleIteratorOwned, Utc};
 use lru::LruCache;
 
+cfg_task_spec!(
+    use crate::utils::task_spec::HandlerSpec;
+);
+
+/// The maximum number of labels that can be added to a `TaskBuilder`.
+pub const MAX_TASK_LABELS: usize = 16;
+
 // Parsing cache for cron expressions, stored with thread-local storage.
 thread_local!(static CRON_EXPRESSION_CACHE: RefCell<LruCache<ScheduleIteratorTimeZoneQuery, DelayTimerScheduleIteratorOwned>> = RefCell::new(LruCache::new(256)));
 
+// TaskDigest is the comparable part of the task definition,
+// the task body can't be compared, so the tasks with the same digest are considered unchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TaskDigest {
+    cron_expression: String,
+    // The count of countdown task, it's `None` for the repeated task.
+    count: Option<u32>,
+    name: Option<String>,
+    labels: HashMap<String, String>,
+    maximum_running_time: Option<u64>,
+    maximun_parallel_runable_num: Option<u64>,
+    schedule_iterator_time_zone: ScheduleIteratorTimeZone,
+    history_capacity: usize,
+    success_exit_codes: Vec<i32>,
+    #[cfg(feature = "task-spec")]
+    handler_spec: Option<HandlerSpec>,
+}
+
 // TaskMark is used to maintain the status of running tasks.
 #[derive(Default, Debug)]
 pub(crate) struct TaskMark {
@@ -24,18 +55,112 @@ pub(crate) struct TaskMark {
     slot_mark: u64,
     // Number of tasks running in parallel.
     parallel_runable_num: u64,
+    // The cron expression that drives the task.
+    pub(crate) cron_expression: String,
+    // The name of task.
+    pub(crate) name: Option<String>,
+    // The labels of task, it can be matched by `TaskSelector`.
+    pub(crate) labels: HashMap<String, String>,
+    // The paused task stays in the wheel, but no new instances are derived.
+    paused: bool,
+    // The task id is reserved by `DelayTimer`, but the task isn't in the wheel yet.
+    reserved: bool,
+    // The task has run out and left the wheel, its id can be reused,
+    // the mark is kept until the last running instance finishes.
+    exhausted: bool,
+    // The comparable definition of task, it's used by reconciliation.
+    pub(crate) digest: TaskDigest,
+    // The number of execution records kept in memory.
+    pub(crate) history_capacity: usize,
     /// Chain of task run instances.
     /// For inner maintain to Running-Task's instance.
     pub(crate) task_instances_chain_maintainer: Option<TaskInstancesChainMaintainer>,
 }
 
 impl TaskMark {
+    // Reserve a `TaskMark` for the task that will be added to the wheel.
+    pub(crate) fn reserved(task: &Task) -> TaskMark {
+        let mut task_mark = TaskMark::default();
+        task_mark
+            .set_task_id(task.task_id)
+            .set_cron_expression(task.cron_expression.clone())
+            .set_name(task.name.clone())
+            .set_labels(task.labels.clone())
+            .set_digest(task.digest())
+            .set_history_capacity(task.history_capacity);
+        task_mark.reserved = true;
+        task_mark
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_reserved(&self) -> bool {
+        self.reserved
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_exhausted(&mut self, exhausted: bool) -> &mut Self {
+        self.exhausted = exhausted;
+        self
+    }
+
+    // The mark of the task that is added and still in the wheel.
+    #[inline(always)]
+    pub(crate) fn is_live(&self) -> bool {
+        !self.reserved && !self.exhausted
+    }
+
     #[inline(always)]
     pub(crate) fn set_task_id(&mut self, task_id: u64) -> &mut Self {
         self.task_id = task_id;
         self
     }
 
+    #[inline(always)]
+    pub(crate) fn set_cron_expression(&mut self, cron_expression: String) -> &mut Self {
+        self.cron_expression = cron_expression;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_name(&mut self, name: Option<String>) -> &mut Self {
+        self.name = name;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_labels(&mut self, labels: HashMap<String, String>) -> &mut Self {
+        self.labels = labels;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_digest(&mut self, digest: TaskDigest) -> &mut Self {
+        self.digest = digest;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_history_capacity(&mut self, history_capacity: usize) -> &mut Self {
+        self.history_capacity = history_capacity;
+        self
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    #[inline(always)]
+    pub(crate) fn set_paused(&mut self, paused: bool) -> &mut Self {
+        self.paused = paused;
+        self
+    }
+
     #[inline(always)]
     pub(crate) fn get_slot_mark(&self) -> u64 {
         self.slot_mark
@@ -116,6 +241,102 @@ impl TaskMark {
         }
         remove_instance
     }
+
+    pub(crate) fn get_task_info(&self) -> TaskInfo {
+        TaskInfo {
+            task_id: self.task_id,
+            name: self.name.clone(),
+            labels: self.labels.clone(),
+            cron_expression: self.cron_expression.clone(),
+            description: describe_cron_expression(&self.cron_expression).ok(),
+            running_instance_num: self.parallel_runable_num,
+            paused: self.paused,
+        }
+    }
+
+    // The next `count` fire times of the cron expression, in the time zone of task.
+    pub(crate) fn get_next_fire_times(&self, count: usize) -> Vec<u64> {
+        preview_cron_expression(
+            &self.cron_expression,
+            self.digest.schedule_iterator_time_zone,
+            count,
+        )
+        .map(|cron_preview| cron_preview.upcoming)
+        .unwrap_or_default()
+    }
+}
+
+/// Selector of tasks, used for bulk operations on `DelayTimer`.
+///
+/// A task is selected when it matches all the conditions of the selector,
+/// so an empty selector selects all tasks.
+///
+/// ```
+/// use delay_timer::prelude::*;
+///
+/// let selector = TaskSelector::default()
+///     .label("team", "billing")
+///     .label("env", "prod");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskSelector {
+    task_id: Option<u64>,
+    name: Option<String>,
+    labels: Vec<(String, String)>,
+}
+
+impl TaskSelector {
+    /// Only select the task with the `task_id`.
+    pub fn task_id(mut self, task_id: u64) -> Self {
+        self.task_id = Some(task_id);
+        self
+    }
+
+    /// Only select tasks with the `name`.
+    pub fn name<T: Into<String>>(mut self, name: T) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only select tasks with the label `key`=`value`.
+    pub fn label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.labels.push((key.into(), value.into()));
+        self
+    }
+
+    pub(crate) fn matches(&self, task_mark: &TaskMark) -> bool {
+        if matches!(self.task_id, Some(task_id) if task_id != task_mark.task_id) {
+            return false;
+        }
+
+        if self.name.is_some() && self.name != task_mark.name {
+            return false;
+        }
+
+        self.labels
+            .iter()
+            .all(|(key, value)| task_mark.labels.get(key) == Some(value))
+    }
+}
+
+/// The information of a task that has been added to the `DelayTimer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "admin-api", derive(serde::Serialize))]
+pub struct TaskInfo {
+    /// The id of task.
+    pub task_id: u64,
+    /// The name of task.
+    pub name: Option<String>,
+    /// The labels of task.
+    pub labels: HashMap<String, String>,
+    /// The cron expression that drives the task.
+    pub cron_expression: String,
+    /// Human-readable description of the cron expression.
+    pub description: Option<String>,
+    /// Number of running instances of the task.
+    pub running_instance_num: u64,
+    /// Whether the task is paused.
+    pub paused: bool,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -244,7 +465,7 @@ impl DelayTimerScheduleIteratorOwned {
     }
 
     // Analyze expressions, get cache.
-    fn analyze_cron_expression(
+    pub(crate) fn analyze_cron_expression(
         time_zone: ScheduleIteratorTimeZone,
         cron_expression: &str,
     ) -> Result<DelayTimerScheduleIteratorOwned, CronExpressionAnalyzeError> {
@@ -321,6 +542,15 @@ pub struct TaskBuilder<'a> {
     /// Task_id should unique.
     task_id: u64,
 
+    /// The name of task (optional).
+    name: Option<&'a str>,
+
+    /// The labels of task, a fixed capacity keep `TaskBuilder` is `Copy`.
+    labels: [Option<(&'a str, &'a str)>; MAX_TASK_LABELS],
+
+    /// Whether more than `MAX_TASK_LABELS` labels are added.
+    labels_overflow: bool,
+
     /// Maximum execution time (optional).
     /// it can be use to deadline (excution-time + maximum_running_time).
     maximum_running_time: Option<u64>,
@@ -333,6 +563,12 @@ pub struct TaskBuilder<'a> {
 
     /// Time zone for cron-expression iteration time.
     schedule_iterator_time_zone: ScheduleIteratorTimeZone,
+
+    /// The number of execution records kept in memory (optional).
+    history_capacity: Option<usize>,
+
+    /// The exit codes of the process task that count as success (optional).
+    success_exit_codes: Option<&'a [i32]>,
 }
 
 //TODO:Future tasks will support single execution (not multiple executions in the same time frame).
@@ -345,10 +581,27 @@ pub struct TaskContext {
     pub task_id: u64,
     /// The id of the task running instance.
     pub record_id: i64,
+    /// The timestamp that the task running instance is scheduled to run.
+    pub(crate) scheduled_time: u64,
+    /// The attempt of the task running instance, it's always 1 since the instances aren't retried.
+    pub(crate) attempt: u32,
+    /// The time zone of task, the scheduled time is rendered in it.
+    pub(crate) time_zone: ScheduleIteratorTimeZone,
     /// Hook functions that may be used in the future.
     pub then_fn: Option<fn()>,
     /// Event Sender for Timer Wheel Core.
     pub(crate) timer_event_sender: Option<TimerEventSender>,
+    /// The exit codes of the process task that count as success, only zero is success without them.
+    pub(crate) success_exit_codes: Option<Vec<i32>>,
+    /// The instance that the output of process is published to.
+    pub(crate) instance: Option<Instance>,
+    /// The config of the log files that the output of process is written to.
+    pub(crate) output_log: Option<Arc<OutputLogConfig>>,
+    /// The runtime of `DelayTimer` that the task running instance is spawned on.
+    pub(crate) runtime_kind: RuntimeKind,
+    /// The span of the task running instance.
+    #[cfg(feature = "tracing-support")]
+    pub(crate) span: Option<tracing::Span>,
 }
 
 impl TaskContext {
@@ -364,11 +617,90 @@ impl TaskContext {
         self
     }
 
+    /// Get the timestamp that the task running instance is scheduled to run.
+    pub fn get_scheduled_time(&self) -> u64 {
+        self.scheduled_time
+    }
+
+    /// Get the attempt of the task running instance, it starts from 1.
+    pub fn get_attempt(&self) -> u32 {
+        self.attempt.max(1)
+    }
+
+    pub(crate) fn scheduled_time(&mut self, scheduled_time: u64) -> &mut Self {
+        self.scheduled_time = scheduled_time;
+        self
+    }
+
+    pub(crate) fn attempt(&mut self, attempt: u32) -> &mut Self {
+        self.attempt = attempt;
+        self
+    }
+
+    pub(crate) fn time_zone(&mut self, time_zone: ScheduleIteratorTimeZone) -> &mut Self {
+        self.time_zone = time_zone;
+        self
+    }
+
     pub(crate) fn timer_event_sender(&mut self, timer_event_sender: TimerEventSender) -> &mut Self {
         self.timer_event_sender = Some(timer_event_sender);
         self
     }
 
+    pub(crate) fn success_exit_codes(&mut self, success_exit_codes: Vec<i32>) -> &mut Self {
+        self.success_exit_codes = Some(success_exit_codes);
+        self
+    }
+
+    pub(crate) fn instance(&mut self, instance: Instance) -> &mut Self {
+        self.instance = Some(instance);
+        self
+    }
+
+    pub(crate) fn output_log(&mut self, output_log: Arc<OutputLogConfig>) -> &mut Self {
+        self.output_log = Some(output_log);
+        self
+    }
+
+    pub(crate) fn runtime_kind(&mut self, runtime_kind: RuntimeKind) -> &mut Self {
+        self.runtime_kind = runtime_kind;
+        self
+    }
+
+    #[cfg(feature = "tracing-support")]
+    pub(crate) fn span(&mut self, span: tracing::Span) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Build the future of the task running instance from the context,
+    /// the future runs inside the span of the instance if the `tracing-support` feature is enabled.
+    pub fn instrument<F, Fut>(self, f: F) -> impl Future<Output = Fut::Output>
+    where
+        F: FnOnce(TaskContext) -> Fut,
+        Fut: Future,
+    {
+        #[cfg(feature = "tracing-support")]
+        {
+            use tracing::Instrument;
+
+            let span = self.span.clone().unwrap_or_else(tracing::Span::none);
+            f(self).instrument(span)
+        }
+
+        #[cfg(not(feature = "tracing-support"))]
+        f(self)
+    }
+
+    /// Whether the task running instance is cancelled or timeout,
+    /// the body that can't be dropped (e.g. the closure of `TaskBuilder::spawn_blocking`) can check it to stop early.
+    pub fn is_cancelled(&self) -> bool {
+        matches!(
+            self.instance.as_ref().map(Instance::get_state),
+            Some(state::instance::CANCELLED) | Some(state::instance::TIMEOUT)
+        )
+    }
+
     /// Get hook functions that may be used in the future.
     pub fn then_fn(&mut self, then_fn: fn()) -> &mut Self {
         self.then_fn = Some(then_fn);
@@ -376,7 +708,11 @@ impl TaskContext {
     }
 
     /// Send a task-Finish signal to EventHandle.
+    ///
+    /// The instance is failed if the process task exits with the code that isn't declared as success
+    /// (see `TaskBuilder::set_success_exit_codes`), or it finishes with the exception output.
     pub async fn finishe_task(self, finish_output: Option<FinishOutput>) {
+        let state = self.finish_state(finish_output.as_ref());
         if let Some(timer_event_sender) = self.timer_event_sender {
             timer_event_sender
                 .send(TimerEvent::FinishTask(FinishTaskBody {
@@ -384,11 +720,97 @@ impl TaskContext {
                     record_id: self.record_id,
                     finish_time: get_timestamp(),
                     finish_output,
+                    state,
                 }))
                 .await
                 .unwrap_or_else(|e| error!("{}", e));
         }
     }
+
+    fn finish_state(&self, finish_output: Option<&FinishOutput>) -> InstanceState {
+        let status = match finish_output {
+            Some(FinishOutput::ProcessOutput(output)) => output.status,
+            Some(FinishOutput::PipelineOutput(pipeline_output)) => pipeline_output.output.status,
+            Some(FinishOutput::ExceptionOutput(_)) => return state::instance::FAILED,
+            None => return state::instance::COMPLETED,
+        };
+
+        // The process killed by signal has no exit code.
+        let success = match (self.success_exit_codes.as_ref(), status.code()) {
+            (Some(success_exit_codes), Some(code)) => success_exit_codes.contains(&code),
+            (None, _) => status.success(),
+            (_, None) => false,
+        };
+        if success {
+            state::instance::COMPLETED
+        } else {
+            state::instance::FAILED
+        }
+    }
+}
+
+/// The result of the body of `TaskBuilder::spawn_async` and `TaskBuilder::spawn_blocking`,
+/// it's converted to the output that the task running instance finishes with.
+///
+/// The instance with `FinishOutput::ExceptionOutput` is `instance::FAILED`, e.g. the `Err` of body.
+pub trait IntoFinishOutput {
+    /// Convert the result to the output of `TaskContext::finishe_task`.
+    fn into_finish_output(self) -> Option<FinishOutput>;
+}
+
+impl IntoFinishOutput for () {
+    fn into_finish_output(self) -> Option<FinishOutput> {
+        None
+    }
+}
+
+impl IntoFinishOutput for FinishOutput {
+    fn into_finish_output(self) -> Option<FinishOutput> {
+        Some(self)
+    }
+}
+
+impl IntoFinishOutput for Option<FinishOutput> {
+    fn into_finish_output(self) -> Option<FinishOutput> {
+        self
+    }
+}
+
+impl<T: IntoFinishOutput, E: fmt::Display> IntoFinishOutput for Result<T, E> {
+    fn into_finish_output(self) -> Option<FinishOutput> {
+        match self {
+            Ok(output) => output.into_finish_output(),
+            // The alternate format prints the context chain of `anyhow::Error`.
+            Err(e) => Some(FinishOutput::ExceptionOutput(format!("{:#}", e))),
+        }
+    }
+}
+
+// Get the message of the panic, the payload of `panic!` is a `&str` or a `String`.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+    match message {
+        Some(message) => format!("The task body panicked: {}", message),
+        None => String::from("The task body panicked."),
+    }
+}
+
+// Spawn the future of the task running instance on the runtime of `DelayTimer`.
+pub(crate) fn spawn_by_runtime<Fut>(
+    runtime_kind: RuntimeKind,
+    future: Fut,
+) -> Box<dyn DelayTaskHandler>
+where
+    Fut: Future<Output = ()> + 'static + Send,
+{
+    match runtime_kind {
+        RuntimeKind::Smol => create_delay_task_handler(async_spawn(future)),
+        #[cfg(feature = "tokio-support")]
+        RuntimeKind::Tokio => create_delay_task_handler(async_spawn_by_tokio(future)),
+    }
 }
 
 pub(crate) struct SafeStructBoxedFn(pub(crate) SafeBoxFn);
@@ -405,10 +827,22 @@ pub struct Task {
     pub task_id: u64,
     /// Iter of frequencies and executive clocks.
     frequency: FrequencyInner,
+    /// The cron expression that drives the task.
+    pub(crate) cron_expression: String,
+    /// The name of task.
+    pub(crate) name: Option<String>,
+    /// The labels of task.
+    pub(crate) labels: HashMap<String, String>,
     /// A Fn in box it can be run and return delayTaskHandler.
     pub(crate) body: SafeStructBoxedFn,
     /// Maximum execution time (optional).
-    maximum_running_time: Option<u64>,
+    pub(crate) maximum_running_time: Option<u64>,
+    /// Time zone for cron-expression iteration time.
+    pub(crate) schedule_iterator_time_zone: ScheduleIteratorTimeZone,
+    /// The timestamp of the last time the task fired.
+    pub(crate) last_fire_time: Option<u64>,
+    /// The timestamp the task is scheduled to fire, it's set when the task is put in the wheel.
+    next_exec_timestamp: Option<u64>,
     /// Loop the line and check how many more clock cycles it will take to execute it.
     cylinder_line: u64,
     /// Validity.
@@ -416,6 +850,13 @@ pub struct Task {
     valid: bool,
     /// Maximum parallel runable num (optional).
     pub(crate) maximun_parallel_runable_num: Option<u64>,
+    /// The number of execution records kept in memory.
+    pub(crate) history_capacity: usize,
+    /// The exit codes of the process task that count as success.
+    pub(crate) success_exit_codes: Vec<i32>,
+    /// The handler name and payload if the task is built from `TaskSpec`.
+    #[cfg(feature = "task-spec")]
+    pub(crate) handler_spec: Option<HandlerSpec>,
 }
 
 //bak type BoxFn
@@ -478,6 +919,29 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
+    /// Set the name of task (optional).
+    #[inline(always)]
+    pub fn set_name(&mut self, name: &'a str) -> &mut Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Add a label to the task, tasks can be selected by labels for bulk operations.
+    /// A task can have at most `MAX_TASK_LABELS` labels, adding a label with an existing key overrides it.
+    #[inline(always)]
+    pub fn add_label(&mut self, key: &'a str, value: &'a str) -> &mut Self {
+        let slot = self.labels.iter_mut().find(|label| match label {
+            Some((k, _)) => *k == key,
+            None => true,
+        });
+
+        match slot {
+            Some(slot) => *slot = Some((key, value)),
+            None => self.labels_overflow = true,
+        }
+        self
+    }
+
     /// Set maximum execution time (optional).
     #[inline(always)]
     pub fn set_maximum_running_time(&mut self, maximum_running_time: u64) -> &mut Self {
@@ -505,6 +969,103 @@ impl<'a> TaskBuilder<'a> {
         self
     }
 
+    /// Set the number of execution records of the task kept in memory,
+    /// the oldest record is dropped when it's full, the default is `DEFAULT_HISTORY_CAPACITY`.
+    #[inline(always)]
+    pub fn set_history_capacity(&mut self, history_capacity: usize) -> &mut Self {
+        self.history_capacity = Some(history_capacity);
+        self
+    }
+
+    /// Declare the exit codes of the process task that count as success, the default is `[0]`.
+    ///
+    /// The instance that exits with other codes or is killed by signal is `instance::FAILED`,
+    /// and it's reported by `PublicEvent::FailTask`.
+    #[inline(always)]
+    pub fn set_success_exit_codes(&mut self, success_exit_codes: &'a [i32]) -> &mut Self {
+        self.success_exit_codes = Some(success_exit_codes);
+        self
+    }
+
+    /// Spawn a task whose body is an ordinary async closure, e.g. `|context| async move { ... }`.
+    ///
+    /// The future is spawned on the runtime of `DelayTimer` for every task running instance,
+    /// and the instance finishes with its output (see `IntoFinishOutput`).
+    /// The future is dropped when the instance is cancelled or timeout.
+    ///
+    /// ```
+    /// use delay_timer::prelude::*;
+    ///
+    /// let task = TaskBuilder::default()
+    ///     .set_task_id(1)
+    ///     .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Secondly))
+    ///     .spawn_async(|context| async move {
+    ///         println!("run task {} instance {}", context.task_id, context.record_id);
+    ///         Ok::<(), anyhow::Error>(())
+    ///     });
+    /// ```
+    pub fn spawn_async<F, Fut>(self, body: F) -> Result<Task, TaskError>
+    where
+        F: Fn(TaskContext) -> Fut + 'static + Send + Sync,
+        Fut: Future + 'static + Send,
+        Fut::Output: IntoFinishOutput,
+    {
+        self.spawn(move |context: TaskContext| {
+            let runtime_kind = context.runtime_kind;
+            let body_future = body(context.clone());
+            let future = context.instrument(|context| async move {
+                let finish_output = body_future.await.into_finish_output();
+                context.finishe_task(finish_output).await;
+            });
+            spawn_by_runtime(runtime_kind, future)
+        })
+    }
+
+    /// Spawn a task whose body is an ordinary blocking closure, e.g. `|context| std::fs::remove_file(..)`.
+    ///
+    /// The closure runs on the blocking thread pool of the runtime of `DelayTimer` for every task running instance,
+    /// and the instance finishes with its result (see `IntoFinishOutput`).
+    /// The thread can't be interrupted when the instance is cancelled or timeout,
+    /// the result is dropped then, and the closure can stop early by checking `TaskContext::is_cancelled`.
+    pub fn spawn_blocking<F, T>(self, body: F) -> Result<Task, TaskError>
+    where
+        F: Fn(TaskContext) -> T + 'static + Send + Sync,
+        T: IntoFinishOutput + 'static + Send,
+    {
+        let body = Arc::new(body);
+        self.spawn_async(move |context: TaskContext| {
+            let body = body.clone();
+            let runtime_kind = context.runtime_kind;
+            let blocking_fn = move || body(context).into_finish_output();
+
+            // The panic of closure is reported as the exception, so the instance always finishes.
+            async move {
+                match runtime_kind {
+                    // The closure is dropped after it panics, nothing observes its broken state.
+                    RuntimeKind::Smol => {
+                        unblock_spawn(move || {
+                            panic::catch_unwind(AssertUnwindSafe(blocking_fn)).unwrap_or_else(
+                                |payload| {
+                                    Some(FinishOutput::ExceptionOutput(panic_message(payload)))
+                                },
+                            )
+                        })
+                        .await
+                    }
+                    #[cfg(feature = "tokio-support")]
+                    RuntimeKind::Tokio => unblock_spawn_by_tokio(blocking_fn)
+                        .await
+                        .unwrap_or_else(|e| Some(FinishOutput::ExceptionOutput(e.to_string()))),
+                }
+            }
+        })
+    }
+
+    /// Spawn a task whose body is the job, it's shared by all the running instances of task (see `Job`).
+    pub fn spawn_job<J: Job>(self, job: Arc<J>) -> Result<Task, TaskError> {
+        self.spawn(move |context: TaskContext| spawn_job_instance(&job, context))
+    }
+
     /// Spawn a task.
     pub fn spawn<F>(self, body: F) -> Result<Task, TaskError>
     where
@@ -512,6 +1073,10 @@ impl<'a> TaskBuilder<'a> {
     {
         let frequency_inner;
 
+        if self.labels_overflow {
+            return Err(TaskError::TooManyLabels(MAX_TASK_LABELS));
+        }
+
         // The user inputs are pattern matched for different repetition types.
         let (expression_str, repeat_type) = match self.frequency {
             Frequency::Once(expression_str) => (expression_str, RepeatType::Num(1)),
@@ -537,11 +1102,28 @@ impl<'a> TaskBuilder<'a> {
         Ok(Task {
             task_id: self.task_id,
             frequency: frequency_inner,
+            cron_expression: expression_str.trim_matches(' ').to_owned(),
+            name: self.name.map(str::to_owned),
+            labels: self
+                .labels
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
             body,
             maximum_running_time: self.maximum_running_time,
+            schedule_iterator_time_zone: self.schedule_iterator_time_zone,
+            last_fire_time: None,
+            next_exec_timestamp: None,
             cylinder_line: 0,
             valid: true,
             maximun_parallel_runable_num: self.maximun_parallel_runable_num,
+            history_capacity: self.history_capacity.unwrap_or(DEFAULT_HISTORY_CAPACITY),
+            success_exit_codes: self
+                .success_exit_codes
+                .map_or_else(|| vec![0], <[i32]>::to_vec),
+            #[cfg(feature = "task-spec")]
+            handler_spec: None,
         })
     }
 
@@ -618,6 +1200,16 @@ impl Task {
         self.is_can_running()
     }
 
+    #[inline(always)]
+    pub(crate) fn set_next_exec_timestamp(&mut self, next_exec_timestamp: Option<u64>) {
+        self.next_exec_timestamp = next_exec_timestamp;
+    }
+
+    #[inline(always)]
+    pub(crate) fn take_next_exec_timestamp(&mut self) -> Option<u64> {
+        self.next_exec_timestamp.take()
+    }
+
     #[inline(always)]
     pub(crate) fn clear_cylinder_line(&mut self) {
         self.cylinder_line = 0;
@@ -653,6 +1245,68 @@ impl Task {
         self.valid
     }
 
+    /// Get the cron expression that drives the task.
+    #[inline(always)]
+    pub fn get_cron_expression(&self) -> &str {
+        &self.cron_expression
+    }
+
+    /// Get the name of task.
+    #[inline(always)]
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Get the labels of task.
+    #[inline(always)]
+    pub fn get_labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Get the remaining count of the `CountDown` task, it's `None` for the repeated task.
+    #[inline(always)]
+    pub fn get_remaining_count(&self) -> Option<u32> {
+        match self.frequency {
+            FrequencyInner::CountDown(remaining_count, _) => Some(remaining_count),
+            FrequencyInner::Repeated(_) => None,
+        }
+    }
+
+    // The digest is taken when the task is added,
+    // so the count of countdown task is the configured one rather than the remaining one.
+    pub(crate) fn digest(&self) -> TaskDigest {
+        TaskDigest {
+            cron_expression: self.cron_expression.clone(),
+            count: self.get_remaining_count(),
+            name: self.name.clone(),
+            labels: self.labels.clone(),
+            maximum_running_time: self.maximum_running_time,
+            maximun_parallel_runable_num: self.maximun_parallel_runable_num,
+            schedule_iterator_time_zone: self.schedule_iterator_time_zone,
+            history_capacity: self.history_capacity,
+            success_exit_codes: self.success_exit_codes.clone(),
+            #[cfg(feature = "task-spec")]
+            handler_spec: self.handler_spec.clone(),
+        }
+    }
+
+    /// Get the time zone for cron-expression iteration time.
+    #[inline(always)]
+    pub fn get_schedule_iterator_time_zone(&self) -> ScheduleIteratorTimeZone {
+        self.schedule_iterator_time_zone
+    }
+
+    /// Get the timestamp of the last time the task fired.
+    #[inline(always)]
+    pub fn get_last_fire_time(&self) -> Option<u64> {
+        self.last_fire_time
+    }
+
+    /// Describe the frequency of the task in English.
+    pub fn describe_frequency(&self) -> Result<String, CronExpressionAnalyzeError> {
+        describe_cron_expression(&self.cron_expression)
+    }
+
     /// get_next_exec_timestamp
     #[inline(always)]
     pub fn get_next_exec_timestamp(&mut self) -> Option<u64> {
@@ -701,6 +1355,54 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_task_selector() -> AnyResult<()> {
+        use super::{TaskBuilder, TaskMark, TaskSelector, MAX_TASK_LABELS};
+        use crate::error::TaskError;
+        use crate::utils::convenience::functions::create_default_delay_task_handler;
+        let mut task_builder = TaskBuilder::default();
+
+        task_builder
+            .set_task_id(1)
+            .set_name("report")
+            .add_label("team", "billing")
+            .add_label("env", "dev")
+            .add_label("env", "prod");
+        let task = task_builder.spawn(|_context| create_default_delay_task_handler())?;
+
+        assert_eq!(task.get_name(), Some("report"));
+        assert_eq!(task.get_labels().len(), 2);
+
+        let mut task_mark = TaskMark::default();
+        task_mark
+            .set_task_id(task.task_id)
+            .set_name(task.name.clone())
+            .set_labels(task.labels.clone());
+
+        assert!(TaskSelector::default().matches(&task_mark));
+        assert!(TaskSelector::default().name("report").matches(&task_mark));
+        assert!(TaskSelector::default()
+            .task_id(1)
+            .label("team", "billing")
+            .label("env", "prod")
+            .matches(&task_mark));
+        assert!(!TaskSelector::default().task_id(2).matches(&task_mark));
+        assert!(!TaskSelector::default()
+            .label("env", "dev")
+            .matches(&task_mark));
+
+        let labels: Vec<String> = (0..=MAX_TASK_LABELS).map(|i| i.to_string()).collect();
+        for label in labels.iter() {
+            task_builder.add_label(label, label);
+        }
+        assert!(matches!(
+            task_builder.spawn(|_context| create_default_delay_task_handler()),
+            Err(TaskError::TooManyLabels(MAX_TASK_LABELS))
+        ));
+
+        Ok(())
+    }
+
     // struct CandyCron
 
     #[test]
@@ -722,6 +1424,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_into_finish_output() {
+        use super::{panic_message, IntoFinishOutput};
+        use crate::prelude::FinishOutput;
+
+        assert!(().into_finish_output().is_none());
+        assert!(Ok::<(), String>(()).into_finish_output().is_none());
+
+        // The output of `Ok` is kept.
+        let output = Ok::<_, String>(FinishOutput::ExceptionOutput("output".into()));
+        match output.into_finish_output() {
+            Some(FinishOutput::ExceptionOutput(output)) => assert_eq!(output, "output"),
+            _ => panic!("The output of `Ok` is lost."),
+        }
+
+        match Err::<(), _>(anyhow::anyhow!("inner").context("outer")).into_finish_output() {
+            Some(FinishOutput::ExceptionOutput(error)) => assert_eq!(error, "outer: inner"),
+            _ => panic!("The error is lost."),
+        }
+
+        assert_eq!(
+            panic_message(Box::new("boom")),
+            "The task body panicked: boom"
+        );
+        assert_eq!(
+            panic_message(Box::new(String::from("boom"))),
+            "The task body panicked: boom"
+        );
+        assert_eq!(panic_message(Box::new(1)), "The task body panicked.");
+    }
+
     #[test]
     fn test_analyze_cron_expression() -> AnyResult<()> {
         use super::{DelayTimerScheduleIteratorOwned, ScheduleIteratorTimeZone};
@@ -2,8 +2,8 @@
 //! It is the core of the entire cycle scheduling task.
 use crate::prelude::*;
 
-use crate::entity::get_timestamp;
 use crate::entity::RuntimeKind;
+use crate::entity::{get_timestamp, get_timestamp_micros};
 
 use std::mem::replace;
 use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
@@ -109,6 +109,7 @@ pub struct FinishTaskBody {
     pub(crate) record_id: i64,
     pub(crate) finish_time: u64,
     pub(crate) finish_output: Option<FinishOutput>,
+    pub(crate) state: InstanceState,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -116,6 +117,8 @@ pub struct FinishTaskBody {
 pub enum FinishOutput {
     /// The output generated when the process task is completed.
     ProcessOutput(std::process::Output),
+    /// The output generated when the pipeline of shell commands is completed.
+    PipelineOutput(Box<PipelineOutput>),
     /// Exception output for a task that did not run successfully.
     ExceptionOutput(String),
 }
@@ -145,6 +148,44 @@ pub enum TimerEvent {
     AppendTaskHandle(u64, DelayTaskHandlerBox),
     /// Take the initiative to perform once Task.
     AdvanceTask(u64),
+    /// Pause a Task in Timer, it doesn't derive new running instances until resumed.
+    PauseTask(u64),
+    /// Resume a paused Task in Timer.
+    ResumeTask(u64),
+    /// Remove the Tasks matched by the selector in Timer.
+    RemoveTasks(TaskSelector),
+    /// Pause the Tasks matched by the selector in Timer.
+    PauseTasks(TaskSelector),
+    /// Resume the Tasks matched by the selector in Timer.
+    ResumeTasks(TaskSelector),
+    /// Cancel all running instances of the Tasks matched by the selector in Timer.
+    CancelInstances(TaskSelector),
+    /// The event with a reply, the result of handling the event is sent back by it.
+    Acknowledged(Box<TimerEvent>, channel::Sender<Result<(), TaskError>>),
+    /// Converge the Tasks in Timer to the desired Tasks, the report is sent back by the sender.
+    Reconcile(Vec<Task>, channel::Sender<ReconcileReport>),
+}
+
+/// The report of reconciliation, it describes what changed.
+///
+/// The task is updated if its cron expression, frequency type, count, name, labels, limits,
+/// or the handler and payload of `TaskSpec` changed.
+/// The count of countdown task is the one it's added with, the remaining count isn't compared.
+///
+/// The task bodies can't be compared, so a task whose only change is its body is reported unchanged,
+/// unless it's built from `TaskSpec`.
+#[derive(Debug, Default)]
+pub struct ReconcileReport {
+    /// The ids of the tasks that are added.
+    pub added: Vec<u64>,
+    /// The ids of the tasks that are updated.
+    pub updated: Vec<u64>,
+    /// The ids of the tasks that are removed.
+    pub removed: Vec<u64>,
+    /// The ids of the tasks that are unchanged.
+    pub unchanged: Vec<u64>,
+    /// The ids of the tasks that can't be applied, and the errors.
+    pub failed: Vec<(u64, TaskError)>,
 }
 #[derive(Clone, Debug)]
 /// delay-timer internal timer wheel core.
@@ -259,13 +300,28 @@ impl Timer {
         timestamp: u64,
         next_second_hand: u64,
     ) -> AnyResult<()> {
+        let task_id: u64 = task.task_id;
+
+        // The paused task keeps its place in the wheel, but doesn't run.
+        let paused = self
+            .shared_header
+            .task_flag_map
+            .get(&task_id)
+            .map(|task_mark| task_mark.value().is_paused())
+            .unwrap_or(false);
+        if paused {
+            #[cfg(feature = "tracing-support")]
+            tracing::debug!(task_id, "Instance skipped, the task is paused.");
+
+            return self.handle_task(task, timestamp, next_second_hand, false);
+        }
+
         let record_id: i64 = self
             .shared_header
             .id_generator
             .lock()
             .await
             .real_time_generate();
-        let task_id: u64 = task.task_id;
 
         if let Some(maximun_parallel_runable_num) = task.maximun_parallel_runable_num {
             let parallel_runable_num: u64;
@@ -284,36 +340,127 @@ impl Timer {
             //if runable_task.parallel_runable_num >= task.maximun_parallel_runable_num doesn't run it.
 
             if parallel_runable_num >= maximun_parallel_runable_num {
+                #[cfg(feature = "tracing-support")]
+                tracing::warn!(
+                    task_id,
+                    parallel_runable_num,
+                    maximun_parallel_runable_num,
+                    "Instance skipped, the task reaches the maximum parallel runable num."
+                );
+
                 return self.handle_task(task, timestamp, next_second_hand, false);
             }
         }
 
+        // The instance is scheduled at the computed fire time,
+        // the advanced task is scheduled at the tick.
+        let scheduled_time = task.take_next_exec_timestamp().unwrap_or(timestamp);
+
+        // The instance is shared by the context and the chain of task instances,
+        // so the output of process can be published to the subscribers of instance.
+        let instance = Instance::default()
+            .set_task_id(task_id)
+            .set_record_id(record_id);
+
         let mut task_context = TaskContext::default();
         task_context
             .task_id(task_id)
             .record_id(record_id)
+            .scheduled_time(scheduled_time)
+            .attempt(1)
+            .time_zone(task.schedule_iterator_time_zone)
+            .instance(instance.clone())
+            .success_exit_codes(task.success_exit_codes.clone())
+            .runtime_kind(self.shared_header.runtime_instance.kind)
             .timer_event_sender(self.timer_event_sender.clone());
+        if let Some(output_log) = self.shared_header.output_log.as_ref() {
+            task_context.output_log(output_log.clone());
+        }
 
+        // The instance runs inside its span, the span is passed to the future by `TaskContext::instrument`.
+        #[cfg(feature = "tracing-support")]
+        let span = tracing::info_span!(
+            "task_instance",
+            task_id,
+            record_id,
+            name = task.get_name().unwrap_or_default(),
+            scheduled_time
+        );
+        #[cfg(feature = "tracing-support")]
+        task_context.span(span.clone());
+
+        let start_time_micros = get_timestamp_micros() as u64;
+
+        #[cfg(feature = "tracing-support")]
+        let task_handler_box = span.in_scope(|| (task.get_body())(task_context));
+        #[cfg(not(feature = "tracing-support"))]
         let task_handler_box = (task.get_body())(task_context);
 
         let delay_task_handler_box_builder = DelayTaskHandlerBoxBuilder::default();
-        let tmp_task_handler_box = delay_task_handler_box_builder
+        #[allow(unused_mut)]
+        let mut tmp_task_handler_box = delay_task_handler_box_builder
             .set_task_id(task_id)
             .set_record_id(record_id)
-            .set_start_time(timestamp)
+            .set_scheduled_time(scheduled_time)
+            .set_start_time_micros(start_time_micros)
             .set_end_time(task.get_maximum_running_time(timestamp))
+            .set_instance(instance)
             .spawn(task_handler_box);
 
+        #[cfg(feature = "tracing-support")]
+        {
+            tracing::info!(parent: &span, task_id, record_id, "Instance dispatched.");
+            tmp_task_handler_box.set_span(span);
+        }
+
         self.send_timer_event(task_id, tmp_task_handler_box).await;
 
+        // The lag is the difference between the actual start and the intended fire time.
+        self.shared_header.metrics.observe_instance_started(
+            task_id,
+            start_time_micros.saturating_sub(scheduled_time * 1_000_000),
+        );
+
         let task_valid = task.down_count_and_set_vaild();
+
+        #[cfg(feature = "job-store")]
+        {
+            task.last_fire_time = Some(scheduled_time);
+            if task_valid {
+                self.shared_header.save_task_record(&task);
+            } else {
+                self.shared_header.remove_task_record(task_id);
+            }
+        }
+
         if !task_valid {
+            self.exhaust_task(task_id);
             return Ok(());
         }
 
         self.handle_task(task, timestamp, next_second_hand, true)
     }
 
+    // The task has run out, so it isn't put back to the wheel,
+    // and its `TaskMark` is removed by `EventHandle` after the last running instance finishes.
+    fn exhaust_task(&mut self, task_id: u64) {
+        if let Some(mut task_mark) = self.shared_header.task_flag_map.get_mut(&task_id) {
+            task_mark.value_mut().inc_parallel_runable_num();
+
+            // The mark belongs to the new task if the id has been reused.
+            let slot_mark = task_mark.value().get_slot_mark();
+            let reused = self
+                .shared_header
+                .wheel_queue
+                .get(&slot_mark)
+                .map(|slot| slot.value().contains_task(task_id))
+                .unwrap_or(false);
+            if !reused {
+                task_mark.value_mut().set_exhausted(true);
+            }
+        }
+    }
+
     // Use `next_second_hand` to solve a problem
     // (when exec_timestamp - timestamp = 0, a task that needs to be executed immediately
     // is instead put on the next turn)
@@ -335,6 +482,7 @@ impl Timer {
         let step = task_excute_timestamp.checked_sub(timestamp).unwrap_or(1) + next_second_hand;
         let quan = step / DEFAULT_TIMER_SLOT_COUNT;
         task.set_cylinder_line(quan);
+        task.set_next_exec_timestamp(Some(task_excute_timestamp));
         let slot_seed = step % DEFAULT_TIMER_SLOT_COUNT;
 
         {
@@ -0,0 +1,650 @@
+//! admin_api
+//! It is a module that serves a local HTTP/JSON API to manage the `DelayTimer`,
+//! the server is started by `DelayTimerBuilder::admin_api`.
+//!
+//! | Method | Path | Action |
+//! | --- | --- | --- |
+//! | `GET` | `/tasks?name=&label=key:value&count=` | List the tasks and their next `count` fire times. |
+//! | `GET` | `/tasks/{task_id}?count=` | Show the task and its next `count` fire times. |
+//! | `GET` | `/tasks/{task_id}/history?state=&since=&until=&limit=` | Show the execution records of the task. |
+//! | `POST` | `/tasks/{task_id}/pause` | Pause the task. |
+//! | `POST` | `/tasks/{task_id}/resume` | Resume the task. |
+//! | `POST` | `/tasks/{task_id}/advance` | Advance the task. |
+//! | `POST` | `/tasks/{task_id}/cancel` | Cancel all running instances of the task. |
+//! | `POST` | `/tasks/{task_id}/instances/{record_id}/cancel` | Cancel the running instance of the task. |
+//! | `DELETE` | `/tasks/{task_id}` | Remove the task. |
+//! | `GET` | `/events?task_id=` | Stream the `PublicEvent`s by server-sent events. |
+//! | `GET` | `/metrics` | Render the metrics in the Prometheus text exposition format. |
+//!
+//! The `state` of history is one of `completed`, `failed`, `cancelled` and `timeout`.
+//! The API isn't authenticated, so it should be bound to a local address.
+//!
+//! # Required features
+//!
+//! This module requires the `admin-api` feature of the `delay_timer` crate to be enabled.
+use crate::prelude::*;
+use crate::utils::status_report::{PublicFinishOutput, StatusReporter};
+
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::atomic::Ordering::Acquire;
+use std::sync::{Arc, Mutex};
+use std::thread::Builder;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use smol::future::FutureExt;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use smol::{Async, Timer};
+
+/// The default number of next fire times shown for every task.
+pub const DEFAULT_FIRE_TIME_PREVIEW: usize = 5;
+
+// The maximum length of the request head.
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+
+// The number of public events buffered for every event stream,
+// the stream is closed when its client can't keep up.
+const EVENT_STREAM_CAPACITY: usize = 1024;
+
+// The interval of checking whether the `DelayTimer` is stopped while waiting for connections,
+// the listener is closed after the timer is stopped.
+const SHUTDOWN_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+// Serve the admin api on the `listener` in a new thread until the `DelayTimer` is stopped,
+// the public events of `status_reporter` are streamed by `/events`.
+pub(crate) fn serve(
+    delay_timer: DelayTimer,
+    listener: TcpListener,
+    status_reporter: Option<StatusReporter>,
+) -> io::Result<()> {
+    let listener = Async::new(listener)?;
+    let event_hub = EventHub::new(status_reporter.is_some());
+
+    Builder::new()
+        .name("admin_api".into())
+        .spawn(move || {
+            smol::block_on(async {
+                if let Some(status_reporter) = status_reporter {
+                    smol::spawn(event_hub.clone().forward(status_reporter)).detach();
+                }
+
+                while delay_timer.shared_header.shared_motivation.load(Acquire) {
+                    let accept = async { Some(listener.accept().await) };
+                    let tick = async {
+                        Timer::after(SHUTDOWN_CHECK_INTERVAL).await;
+                        None
+                    };
+
+                    match accept.or(tick).await {
+                        None => continue,
+                        Some(Ok((stream, _))) => {
+                            smol::spawn(handle_connection(
+                                delay_timer.clone(),
+                                event_hub.clone(),
+                                stream,
+                            ))
+                            .detach();
+                        }
+                        Some(Err(e)) => error!("Admin api can't accept the connection: {}", e),
+                    }
+                }
+            })
+        })
+        .expect("admin_api can't start.");
+
+    Ok(())
+}
+
+async fn handle_connection(
+    delay_timer: DelayTimer,
+    event_hub: EventHub,
+    mut stream: Async<TcpStream>,
+) -> io::Result<()> {
+    let request = match read_request_head(&mut stream).await? {
+        Some(head) => Request::parse(&head),
+        None => Err(Response::error(431, "The request head is too large.")),
+    };
+
+    let response = match request {
+        Ok(request) if request.method == "GET" && request.path == "/events" => {
+            match event_hub.subscribe(&request) {
+                Ok(event_stream) => return event_stream.write_to(&mut stream).await,
+                Err(response) => response,
+            }
+        }
+        Ok(request) => route(&delay_timer, &request)
+            .await
+            .unwrap_or_else(|response| response),
+        Err(response) => response,
+    };
+
+    stream.write_all(&response.to_bytes()).await?;
+    stream.flush().await
+}
+
+// Read the request head, `None` if it's too large.
+async fn read_request_head(stream: &mut Async<TcpStream>) -> io::Result<Option<String>> {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+
+    loop {
+        if let Some(end) = head.windows(4).position(|window| window == b"\r\n\r\n") {
+            head.truncate(end);
+            return Ok(Some(String::from_utf8_lossy(&head).into_owned()));
+        }
+
+        if head.len() > MAX_REQUEST_HEAD_LEN {
+            return Ok(None);
+        }
+
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+}
+
+async fn route(delay_timer: &DelayTimer, request: &Request) -> Result<Response, Response> {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["tasks"]) => list_tasks(delay_timer, request),
+        ("GET", ["tasks", task_id]) => show_task(delay_timer, request, parse_id(task_id)?),
+        ("GET", ["tasks", task_id, "history"]) => {
+            show_history(delay_timer, request, parse_id(task_id)?)
+        }
+        ("POST", ["tasks", task_id, action]) => {
+            control_task(delay_timer, request, parse_id(task_id)?, action).await
+        }
+        ("POST", ["tasks", task_id, "instances", record_id, "cancel"]) => {
+            delay_timer
+                .cancel_task_with_async_ack(parse_id(task_id)?, parse_id(record_id)?)
+                .await?;
+            Ok(Response::no_content())
+        }
+        ("DELETE", ["tasks", task_id]) => {
+            delay_timer
+                .remove_task_with_async_ack(parse_id(task_id)?)
+                .await?;
+            Ok(Response::no_content())
+        }
+        ("GET", ["metrics"]) => Ok(Response {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body: delay_timer.render_metrics(),
+        }),
+        _ => Err(Response::not_found(request)),
+    }
+}
+
+fn list_tasks(delay_timer: &DelayTimer, request: &Request) -> Result<Response, Response> {
+    let mut task_selector = TaskSelector::default();
+    if let Some(name) = request.param("name") {
+        task_selector = task_selector.name(name);
+    }
+
+    for (_, label) in request.query.iter().filter(|(key, _)| key == "label") {
+        let index = label.find(':').ok_or_else(|| {
+            Response::error(
+                400,
+                format!("Invalid label `{}`, expect `key:value`.", label),
+            )
+        })?;
+        task_selector = task_selector.label(&label[..index], &label[index + 1..]);
+    }
+
+    let count = request
+        .parse_param("count")?
+        .unwrap_or(DEFAULT_FIRE_TIME_PREVIEW);
+    let task_views: Vec<TaskView> = delay_timer
+        .get_task_infos(task_selector)
+        .into_iter()
+        .map(|task_info| TaskView::new(delay_timer, task_info, count))
+        .collect();
+    Ok(Response::json(&task_views))
+}
+
+fn show_task(
+    delay_timer: &DelayTimer,
+    request: &Request,
+    task_id: u64,
+) -> Result<Response, Response> {
+    let count = request
+        .parse_param("count")?
+        .unwrap_or(DEFAULT_FIRE_TIME_PREVIEW);
+    let task_info = delay_timer
+        .get_task_info(task_id)
+        .ok_or(TaskError::TaskNotFound(task_id))?;
+    Ok(Response::json(&TaskView::new(
+        delay_timer,
+        task_info,
+        count,
+    )))
+}
+
+fn show_history(
+    delay_timer: &DelayTimer,
+    request: &Request,
+    task_id: u64,
+) -> Result<Response, Response> {
+    let mut filter = HistoryFilter::default();
+    if let Some(state) = request.param("state") {
+        filter = filter.state(parse_state(state)?);
+    }
+    if let Some(since) = request.parse_param("since")? {
+        filter = filter.since(since);
+    }
+    if let Some(until) = request.parse_param("until")? {
+        filter = filter.until(until);
+    }
+    if let Some(limit) = request.parse_param("limit")? {
+        filter = filter.limit(limit);
+    }
+
+    let records = delay_timer
+        .history(task_id, filter)
+        .map_err(|e| Response::error(500, e))?;
+    Ok(Response::json(&records))
+}
+
+async fn control_task(
+    delay_timer: &DelayTimer,
+    request: &Request,
+    task_id: u64,
+    action: &str,
+) -> Result<Response, Response> {
+    // Pausing, resuming and cancelling aren't acknowledged, so check the task first.
+    let ensure_task = || {
+        delay_timer
+            .get_task_info(task_id)
+            .map(|_| ())
+            .ok_or(TaskError::TaskNotFound(task_id))
+    };
+
+    match action {
+        "pause" => {
+            ensure_task()?;
+            delay_timer.pause_task(task_id)?;
+        }
+        "resume" => {
+            ensure_task()?;
+            delay_timer.resume_task(task_id)?;
+        }
+        "cancel" => {
+            ensure_task()?;
+            delay_timer.cancel_instances(TaskSelector::default().task_id(task_id))?;
+        }
+        "advance" => delay_timer.advance_task_with_async_ack(task_id).await?,
+        _ => return Err(Response::not_found(request)),
+    }
+
+    Ok(Response::no_content())
+}
+
+fn parse_id<T: FromStr>(segment: &str) -> Result<T, Response> {
+    segment
+        .parse()
+        .map_err(|_| Response::error(400, format!("Invalid id `{}`.", segment)))
+}
+
+fn parse_state(state: &str) -> Result<InstanceState, Response> {
+    match state {
+        "completed" => Ok(instance::COMPLETED),
+        "cancelled" => Ok(instance::CANCELLED),
+        "timeout" => Ok(instance::TIMEOUT),
+        "failed" => Ok(instance::FAILED),
+        _ => Err(Response::error(
+            400,
+            format!(
+                "Invalid state `{}`, expect `completed`, `cancelled`, `timeout` or `failed`.",
+                state
+            ),
+        )),
+    }
+}
+
+// The task and its next fire times.
+#[derive(Debug, Serialize)]
+struct TaskView {
+    #[serde(flatten)]
+    task_info: TaskInfo,
+    next_fire_times: Vec<u64>,
+}
+
+impl TaskView {
+    fn new(delay_timer: &DelayTimer, task_info: TaskInfo, count: usize) -> TaskView {
+        let next_fire_times = delay_timer
+            .get_next_fire_times(task_info.task_id, count)
+            .unwrap_or_default();
+
+        TaskView {
+            task_info,
+            next_fire_times,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+}
+
+impl Request {
+    // Parse the request line of the head, the headers are not used.
+    fn parse(head: &str) -> Result<Request, Response> {
+        let bad_request = || Response::error(400, "Invalid request line.");
+
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().ok_or_else(bad_request)?;
+        let target = request_line.next().ok_or_else(bad_request)?;
+        match request_line.next() {
+            Some(version) if version.starts_with("HTTP/1.") => {}
+            _ => return Err(bad_request()),
+        }
+
+        let (path, query) = match target.find('?') {
+            Some(index) => (&target[..index], &target[index + 1..]),
+            None => (target, ""),
+        };
+
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.find('=') {
+                Some(index) => (
+                    percent_decode(&pair[..index]),
+                    percent_decode(&pair[index + 1..]),
+                ),
+                None => (percent_decode(pair), String::new()),
+            })
+            .collect();
+
+        Ok(Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parse_param<T: FromStr>(&self, key: &str) -> Result<Option<T>, Response> {
+        self.param(key)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    Response::error(400, format!("Invalid query parameter `{}`.", key))
+                })
+            })
+            .transpose()
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(value: &T) -> Response {
+        match serde_json::to_string(value) {
+            Ok(body) => Response {
+                status: 200,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Response::error(500, e),
+        }
+    }
+
+    fn no_content() -> Response {
+        Response {
+            status: 204,
+            content_type: "application/json",
+            body: String::new(),
+        }
+    }
+
+    fn error<T: ToString>(status: u16, message: T) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": message.to_string() }).to_string(),
+        }
+    }
+
+    fn not_found(request: &Request) -> Response {
+        Response::error(
+            404,
+            format!("No route for `{} {}`.", request.method, request.path),
+        )
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+        .into_bytes()
+    }
+}
+
+impl From<TaskError> for Response {
+    fn from(e: TaskError) -> Self {
+        let status = match e {
+            TaskError::TaskNotFound(_) | TaskError::InstanceNotFound(_, _) => 404,
+            _ => 500,
+        };
+        Response::error(status, e)
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// Fan out the public events to all event streams.
+#[derive(Clone)]
+struct EventHub {
+    // Whether the public events are available.
+    enabled: bool,
+    subscribers: Arc<Mutex<Vec<AsyncSender<PublicEvent>>>>,
+}
+
+impl EventHub {
+    fn new(enabled: bool) -> EventHub {
+        EventHub {
+            enabled,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    async fn forward(self, status_reporter: StatusReporter) {
+        while let Ok(public_event) = status_reporter.next_public_event_with_async_wait().await {
+            if let Ok(mut subscribers) = self.subscribers.lock() {
+                subscribers.retain(|subscriber| subscriber.try_send(public_event.clone()).is_ok());
+            }
+        }
+    }
+
+    fn subscribe(&self, request: &Request) -> Result<EventStream, Response> {
+        if !self.enabled {
+            return Err(Response::error(404, "Public events are not available."));
+        }
+
+        let task_id = request.parse_param("task_id")?;
+        let (sender, receiver) = channel::bounded(EVENT_STREAM_CAPACITY);
+        self.subscribers
+            .lock()
+            .map_err(|_| Response::error(500, "Event hub lock is poisoned."))?
+            .push(sender);
+
+        Ok(EventStream { task_id, receiver })
+    }
+}
+
+// The public events of a `/events` request.
+struct EventStream {
+    task_id: Option<u64>,
+    receiver: AsyncReceiver<PublicEvent>,
+}
+
+impl EventStream {
+    async fn write_to(self, stream: &mut Async<TcpStream>) -> io::Result<()> {
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+            )
+            .await?;
+        stream.flush().await?;
+
+        while let Ok(public_event) = self.receiver.recv().await {
+            if matches!(self.task_id, Some(task_id) if task_id != public_event.get_task_id()) {
+                continue;
+            }
+
+            let (event, data) = public_event_json(&public_event);
+            stream
+                .write_all(format!("event: {}\ndata: {}\n\n", event, data).as_bytes())
+                .await?;
+            stream.flush().await?;
+        }
+
+        Ok(())
+    }
+}
+
+// The event name and json data of the public event.
+fn public_event_json(public_event: &PublicEvent) -> (&'static str, Value) {
+    let event = match public_event {
+        PublicEvent::RemoveTask(_) => "remove_task",
+        PublicEvent::RunningTask(_, _) => "running_task",
+        PublicEvent::FinishTask(_) => "finish_task",
+        PublicEvent::FailTask(_) => "fail_task",
+        PublicEvent::TimeoutTask(_, _) => "timeout_task",
+        PublicEvent::CancelTask(_, _) => "cancel_task",
+        PublicEvent::PauseTask(_) => "pause_task",
+        PublicEvent::ResumeTask(_) => "resume_task",
+    };
+
+    let mut data = json!({
+        "task_id": public_event.get_task_id(),
+        "record_id": public_event.get_record_id(),
+    });
+
+    if let PublicEvent::FinishTask(finish_task_body) | PublicEvent::FailTask(finish_task_body) =
+        public_event
+    {
+        data["finish_time"] = finish_task_body.get_finish_time().into();
+        match finish_task_body.finish_output.as_ref() {
+            Some(PublicFinishOutput::ProcessOutput(output)) => {
+                data["exit_code"] = output.status.code().into();
+            }
+            Some(PublicFinishOutput::PipelineOutput(pipeline_output)) => {
+                data["exit_code"] = pipeline_output.output.status.code().into();
+                data["stage_exit_codes"] = pipeline_output
+                    .stage_statuses
+                    .iter()
+                    .map(|status| status.code())
+                    .collect::<Vec<_>>()
+                    .into();
+            }
+            Some(PublicFinishOutput::ExceptionOutput(error)) => {
+                data["error"] = error.as_str().into();
+            }
+            None => {}
+        }
+    }
+
+    (event, data)
+}
+
+mod tests {
+
+    #[test]
+    fn test_request_parse() {
+        use super::Request;
+
+        let request = Request::parse(
+            "GET /tasks?label=team%3Abilling&name=daily+report&count HTTP/1.1\r\nHost: localhost",
+        )
+        .unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/tasks");
+        assert_eq!(request.param("label"), Some("team:billing"));
+        assert_eq!(request.param("name"), Some("daily report"));
+        assert_eq!(request.param("count"), Some(""));
+        assert!(request.parse_param::<usize>("count").is_err());
+        assert_eq!(request.parse_param::<usize>("limit").unwrap(), None);
+
+        // The malformed escape is kept as it is.
+        let request = Request::parse("GET /tasks?name=100%zz HTTP/1.1").unwrap();
+        assert_eq!(request.param("name"), Some("100%zz"));
+
+        assert_eq!(Request::parse("GET /tasks").unwrap_err().status, 400);
+        assert_eq!(Request::parse("").unwrap_err().status, 400);
+    }
+
+    #[test]
+    fn test_public_event_json() {
+        use super::public_event_json;
+        use crate::prelude::*;
+
+        let (event, data) = public_event_json(&PublicEvent::CancelTask(1, 7));
+        assert_eq!(event, "cancel_task");
+        assert_eq!(data.to_string(), r#"{"record_id":7,"task_id":1}"#);
+
+        let (event, data) = public_event_json(&PublicEvent::PauseTask(1));
+        assert_eq!(event, "pause_task");
+        assert_eq!(data["record_id"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,409 @@
+//! config
+//! It is a module that loads the schedule from a TOML/YAML file,
+//! and reconciles the running tasks with the file when it changes.
+//!
+//! ```toml
+//! [[tasks]]
+//! id = 1
+//! name = "clean"
+//! expression = "0 0 3 * * * *"
+//! time_zone = "+08:00"
+//! shell = "rm -rf /tmp/cache"
+//! timeout = 600
+//!
+//! [[tasks]]
+//! id = 2
+//! expression = "@hourly"
+//! handler = "send_report"
+//! payload = { to = "ops" }
+//! max_parallel = 1
+//! ```
+//!
+//! # Required features
+//!
+//! This module requires the `config` feature of the `delay_timer` crate to be enabled.
+use crate::prelude::*;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::utils::task_spec::{HandlerRegistry, HandlerSpec};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The default interval of checking the config file for changes.
+pub const DEFAULT_CONFIG_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The format of config file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// TOML.
+    Toml,
+    /// YAML.
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Get the format by the extension of file (`.toml`, `.yaml` and `.yml`).
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ConfigFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// The schedule defined by config file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// The tasks of schedule.
+    #[serde(default, alias = "task")]
+    pub tasks: Vec<TaskConfig>,
+}
+
+/// The task defined by config file, its body is either a shell command or a registered handler.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskConfig {
+    /// The id of task.
+    pub id: u64,
+    /// The name of task.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The cron expression that drives the task.
+    pub expression: String,
+    /// Run the task `count` times, the task is repeated without it.
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// Time zone for cron-expression iteration time, `utc`, `local` (default) or an offset like `+08:00`.
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    /// The shell command that runs by `unblock_process_task_fn`.
+    #[serde(default)]
+    pub shell: Option<String>,
+    /// The name of the registered handler.
+    #[serde(default)]
+    pub handler: Option<String>,
+    /// The payload passed to the handler.
+    #[serde(default)]
+    pub payload: Value,
+    /// Maximum parallel runable num.
+    #[serde(default)]
+    pub max_parallel: Option<u64>,
+    /// Maximum execution time in seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// The labels of task.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// The changes applied by a reload.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigReloadReport {
+    /// The ids of added tasks.
+    pub added: Vec<u64>,
+    /// The ids of updated tasks.
+    pub updated: Vec<u64>,
+    /// The ids of removed tasks.
+    pub removed: Vec<u64>,
+}
+
+impl ScheduleConfig {
+    /// Parse the content of config.
+    pub fn parse(content: &str, format: ConfigFormat) -> Result<ScheduleConfig, ConfigError> {
+        Ok(match format {
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+
+    /// Read and parse the config file, the format is decided by the extension.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ScheduleConfig, ConfigError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)
+            .ok_or_else(|| ConfigError::UnknownFormat(path.to_path_buf()))?;
+
+        Self::parse(&fs::read_to_string(path)?, format)
+    }
+}
+
+impl TaskConfig {
+    pub(crate) fn spawn_task(
+        &self,
+        handler_registry: &HandlerRegistry,
+    ) -> Result<Task, ConfigError> {
+        let time_zone = match self.time_zone.as_deref() {
+            Some(time_zone) => parse_time_zone(time_zone)
+                .ok_or_else(|| ConfigError::InvalidTimeZone(self.id, time_zone.to_string()))?,
+            None => ScheduleIteratorTimeZone::Local,
+        };
+
+        let frequency = match self.count {
+            Some(count) => Frequency::CountDown(count, &self.expression),
+            None => Frequency::Repeated(&self.expression),
+        };
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency(frequency)
+            .set_task_id(self.id)
+            .set_schedule_iterator_time_zone(time_zone);
+
+        if let Some(timeout) = self.timeout {
+            task_builder.set_maximum_running_time(timeout);
+        }
+
+        if let Some(max_parallel) = self.max_parallel {
+            task_builder.set_maximun_parallel_runable_num(max_parallel);
+        }
+
+        if let Some(name) = self.name.as_deref() {
+            task_builder.set_name(name);
+        }
+
+        for (key, value) in self.labels.iter() {
+            task_builder.add_label(key, value);
+        }
+
+        let task_result = match (self.shell.as_ref(), self.handler.as_ref()) {
+            (Some(shell), None) => task_builder.spawn(unblock_process_task_fn(shell.clone())),
+            (None, Some(handler_name)) => {
+                let handler = handler_registry
+                    .get(handler_name)
+                    .ok_or_else(|| TaskError::HandlerNotFound(handler_name.clone()))
+                    .map_err(|e| ConfigError::Task(self.id, e))?;
+                let payload = self.payload.clone();
+
+                task_builder
+                    .spawn(move |context| handler(context, &payload))
+                    .map(|mut task| {
+                        task.handler_spec = Some(HandlerSpec {
+                            handler: handler_name.clone(),
+                            payload: self.payload.clone(),
+                        });
+                        task
+                    })
+            }
+            _ => return Err(ConfigError::InvalidBody(self.id)),
+        };
+
+        task_result.map_err(|e| ConfigError::Task(self.id, e))
+    }
+}
+
+// `utc`, `local` or an offset like `+08:00`, `-0530`.
+fn parse_time_zone(time_zone: &str) -> Option<ScheduleIteratorTimeZone> {
+    match time_zone.to_ascii_lowercase().as_str() {
+        "utc" => return Some(ScheduleIteratorTimeZone::Utc),
+        "local" => return Some(ScheduleIteratorTimeZone::Local),
+        _ => {}
+    }
+
+    let sign = match time_zone.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = time_zone[1..].chars().filter(|c| *c != ':').collect();
+    if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(ScheduleIteratorTimeZone::FixedOffset)
+}
+
+/// Keep the tasks of `DelayTimer` the same as the config file.
+#[derive(Debug)]
+pub(crate) struct ConfigLoader {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    // The configs of the tasks that have been applied.
+    applied_tasks: BTreeMap<u64, TaskConfig>,
+}
+
+impl ConfigLoader {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        ConfigLoader {
+            path,
+            last_modified: None,
+            applied_tasks: BTreeMap::new(),
+        }
+    }
+
+    // Whether the file has been modified since the last load.
+    pub(crate) fn is_modified(&self) -> bool {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified());
+
+        match modified {
+            Ok(modified) => self.last_modified != Some(modified),
+            Err(_) => false,
+        }
+    }
+
+    // Nothing is changed if the file is invalid, the unchanged tasks are left alone.
+    // The modified time is kept after the whole file is applied,
+    // so the file is reloaded again if some tasks failed.
+    pub(crate) fn reload(
+        &mut self,
+        delay_timer: &DelayTimer,
+    ) -> Result<ConfigReloadReport, ConfigError> {
+        let last_modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let schedule_config = ScheduleConfig::from_file(&self.path)?;
+
+        let mut desired_tasks = BTreeMap::new();
+        for task_config in schedule_config.tasks {
+            let task_id = task_config.id;
+            if desired_tasks.insert(task_id, task_config).is_some() {
+                return Err(ConfigError::DuplicateTaskId(task_id));
+            }
+        }
+
+        // The task that is removed by the api or has run out is added again.
+        let mut spawned_tasks = Vec::new();
+        for (task_id, task_config) in desired_tasks.iter() {
+            if self.applied_tasks.get(task_id) != Some(task_config)
+                || delay_timer.get_task_info(*task_id).is_none()
+            {
+                spawned_tasks
+                    .push(task_config.spawn_task(&delay_timer.shared_header.handler_registry)?);
+            }
+        }
+
+        let mut report = ConfigReloadReport::default();
+
+        let removed_task_ids: Vec<u64> = self
+            .applied_tasks
+            .keys()
+            .filter(|task_id| !desired_tasks.contains_key(task_id))
+            .copied()
+            .collect();
+        // The task that has run out is already removed.
+        for task_id in removed_task_ids {
+            match delay_timer.remove_task_with_ack(task_id) {
+                Ok(_) | Err(TaskError::TaskNotFound(_)) => {}
+                Err(e) => return Err(ConfigError::Task(task_id, e)),
+            }
+            self.applied_tasks.remove(&task_id);
+            report.removed.push(task_id);
+        }
+
+        for task in spawned_tasks {
+            let task_id = task.task_id;
+
+            // The task with the same id that isn't added by the config is left alone.
+            let is_applied = self.applied_tasks.contains_key(&task_id)
+                && delay_timer.get_task_info(task_id).is_some();
+            if is_applied {
+                delay_timer
+                    .update_task_with_ack(task)
+                    .map_err(|e| ConfigError::Task(task_id, e))?;
+                report.updated.push(task_id);
+            } else {
+                delay_timer
+                    .add_task_with_own_id_ack(task)
+                    .map_err(|e| ConfigError::Task(task_id, e))?;
+                report.added.push(task_id);
+            }
+
+            if let Some(task_config) = desired_tasks.remove(&task_id) {
+                self.applied_tasks.insert(task_id, task_config);
+            }
+        }
+
+        self.last_modified = last_modified;
+        Ok(report)
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_schedule_config() -> anyhow::Result<()> {
+        use super::{parse_time_zone, ConfigFormat, ScheduleConfig};
+        use crate::prelude::*;
+        use crate::utils::task_spec::HandlerRegistry;
+
+        let toml_config = ScheduleConfig::parse(
+            r#"
+            [[tasks]]
+            id = 1
+            expression = "@hourly"
+            shell = "echo hello"
+            time_zone = "utc"
+
+            [[tasks]]
+            id = 2
+            name = "report"
+            expression = "0 0 9 * * * *"
+            count = 3
+            handler = "send_report"
+            payload = { to = "ops" }
+            max_parallel = 1
+            timeout = 60
+            "#,
+            ConfigFormat::Toml,
+        )?;
+
+        let yaml_config = ScheduleConfig::parse(
+            r#"
+tasks:
+  - id: 1
+    expression: "@hourly"
+    shell: echo hello
+    time_zone: utc
+  - id: 2
+    name: report
+    expression: "0 0 9 * * * *"
+    count: 3
+    handler: send_report
+    payload:
+      to: ops
+    max_parallel: 1
+    timeout: 60
+            "#,
+            ConfigFormat::Yaml,
+        )?;
+        assert_eq!(toml_config, yaml_config);
+
+        let handler_registry = HandlerRegistry::default();
+        assert!(matches!(
+            toml_config.tasks[1].spawn_task(&handler_registry),
+            Err(ConfigError::Task(2, TaskError::HandlerNotFound(_)))
+        ));
+
+        handler_registry.register(String::from("send_report"), |_, _| {
+            create_default_delay_task_handler()
+        });
+        let task = toml_config.tasks[1].spawn_task(&handler_registry)?;
+        assert_eq!(task.get_remaining_count(), Some(3));
+        assert_eq!(task.get_name(), Some("report"));
+
+        let task = toml_config.tasks[0].spawn_task(&handler_registry)?;
+        assert_eq!(
+            task.get_schedule_iterator_time_zone(),
+            ScheduleIteratorTimeZone::Utc
+        );
+
+        assert_eq!(
+            parse_time_zone("+08:00"),
+            FixedOffset::east_opt(8 * 3600).map(ScheduleIteratorTimeZone::FixedOffset)
+        );
+        assert_eq!(
+            parse_time_zone("-0530"),
+            FixedOffset::west_opt(5 * 3600 + 30 * 60).map(ScheduleIteratorTimeZone::FixedOffset)
+        );
+        assert!(parse_time_zone("Asia/Shanghai").is_none());
+
+        Ok(())
+    }
+}
@@ -15,19 +15,24 @@ impl DelayTaskHandler for MyUnit {
 /// The convenient functions to combine.
 pub mod functions {
 
-    use super::super::parse_and_run;
+    use super::super::parse::shell_command::{parse_and_run_in_context, wait_pipeline};
     use crate::prelude::*;
     use crate::timer::runtime_trace::task_handle::DelayTaskHandler;
 
     /// UnBlock execution of a command line task in delay-timer.
+    ///
+    /// The placeholders in the command (e.g. `{record_id}`) are rendered from the `TaskContext`,
+    /// see `render_template`.
     pub fn unblock_process_task_fn(
         shell_command: String,
     ) -> impl Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync {
         use smol::process::{Child, Command};
         move |context: TaskContext| {
             let shell_command_clone = shell_command.clone();
-            create_delay_task_handler(async_spawn(async move {
-                let childs = parse_and_run::<Child, Command>(&shell_command_clone).await;
+            create_delay_task_handler(async_spawn(context.instrument(|context| async move {
+                let childs =
+                    parse_and_run_in_context::<Child, Command>(&shell_command_clone, &context)
+                        .await;
 
                 if let Err(err) = childs {
                     context
@@ -36,16 +41,15 @@ pub mod functions {
                     return Err(anyhow!(err.to_string()));
                 }
 
-                let mut childs = childs?;
-
-                let last_child = childs.pop_back().ok_or_else(|| anyhow!("Without child."))?;
-                let output = last_child.wait_with_output().await?;
+                let pipeline_output = wait_pipeline(childs?).await?;
                 context
-                    .finishe_task(Some(FinishOutput::ProcessOutput(output)))
+                    .finishe_task(Some(FinishOutput::PipelineOutput(Box::new(
+                        pipeline_output,
+                    ))))
                     .await;
 
                 Ok(())
-            }))
+            })))
         }
     }
 
@@ -53,31 +57,38 @@ pub mod functions {
         use tokio::process::{Child, Command};
 
         /// UnBlock execution of a command line task in delay-timer `Runtime` based on tokio.
+        ///
+        /// The placeholders in the command are rendered as `unblock_process_task_fn` does.
         pub fn tokio_unblock_process_task_fn(
             shell_command: String,
         ) -> impl Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync {
             move |context: TaskContext| {
                 let shell_command_clone = shell_command.clone();
-                create_delay_task_handler(async_spawn_by_tokio(async move {
-                    let childs = parse_and_run::<Child, Command>(&shell_command_clone).await;
+                create_delay_task_handler(async_spawn_by_tokio(context.instrument(
+                    |context| async move {
+                        let childs = parse_and_run_in_context::<Child, Command>(
+                            &shell_command_clone,
+                            &context,
+                        )
+                        .await;
+
+                        if let Err(err) = childs {
+                            context
+                                .finishe_task(Some(FinishOutput::ExceptionOutput(err.to_string())))
+                                .await;
+                            return Err(anyhow!(err.to_string()));
+                        }
 
-                    if let Err(err) = childs {
+                        let pipeline_output = wait_pipeline(childs?).await?;
                         context
-                            .finishe_task(Some(FinishOutput::ExceptionOutput(err.to_string())))
+                            .finishe_task(Some(FinishOutput::PipelineOutput(Box::new(
+                                pipeline_output,
+                            ))))
                             .await;
-                        return Err(anyhow!(err.to_string()));
-                    }
-
-                    let mut childs = childs?;
-
-                    let last_child = childs.pop_back().ok_or_else(|| anyhow!("Without child."))?;
-                    let output = last_child.wait_with_output().await?;
-                    context
-                        .finishe_task(Some(FinishOutput::ProcessOutput(output)))
-                        .await;
 
-                    Ok(())
-                }))
+                        Ok(())
+                    },
+                )))
             }
         }
     );
@@ -0,0 +1,531 @@
+//! crontab
+//! It is a module that imports the classic 5-field crontab files,
+//! every entry is translated into the seconds+year form and runs by the shell as the cron daemon does.
+//!
+//! ```
+//! use delay_timer::prelude::*;
+//!
+//! let crontab = Crontab::parse(
+//!     "# Clean the tmp dir.\nPATH=/usr/bin:/bin\n*/5 9-17 * * 1-5 rm -rf /tmp/cache\n",
+//!     CrontabFormat::User,
+//! )
+//! .unwrap();
+//!
+//! let entry = &crontab.entries()[0];
+//! assert_eq!(entry.cron_expressions, vec!["0 */5 9-17 * * 2,3,4,5,6 *"]);
+//! assert_eq!(entry.command, "rm -rf /tmp/cache");
+//! assert_eq!(
+//!     entry.environment,
+//!     vec![(String::from("PATH"), String::from("/usr/bin:/bin"))]
+//! );
+//! ```
+use crate::prelude::*;
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use cron_clock::Schedule;
+
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+const DEFAULT_SHELL: &str = "/bin/sh";
+
+/// The format of crontab file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrontabFormat {
+    /// The crontab of a user, `m h dom mon dow command`.
+    User,
+    /// The system crontab (e.g. `/etc/crontab`), `m h dom mon dow user command`.
+    System,
+}
+
+/// A crontab that has been parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Crontab {
+    entries: Vec<CrontabEntry>,
+}
+
+/// An entry of crontab, one line of the crontab file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrontabEntry {
+    /// The line number of the entry, starting at 1.
+    pub line: usize,
+    /// The cron expressions in the seconds+year form.
+    /// When both day of month and day of week are restricted, the entry runs when either matches,
+    /// so there are two expressions. It's empty for `@reboot`.
+    pub cron_expressions: Vec<String>,
+    /// Whether the entry runs once when the tasks are spawned (`@reboot`).
+    pub reboot: bool,
+    /// The user field of the system crontab,
+    /// the tasks can only be spawned if it's the user of the current process.
+    pub user: Option<String>,
+    /// The command, the `%` escaped by `\` is kept.
+    pub command: String,
+    /// The standard input of the command, the text after the first unescaped `%`.
+    pub stdin: Option<String>,
+    /// The environment settings that precede the entry.
+    pub environment: Vec<(String, String)>,
+}
+
+impl Crontab {
+    /// Parse the content of crontab, all the invalid lines are reported.
+    pub fn parse(content: &str, format: CrontabFormat) -> Result<Crontab, Vec<CrontabError>> {
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        let mut environment: Vec<(String, String)> = Vec::new();
+
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = index + 1;
+            let trimmed_line = raw_line.trim();
+
+            if trimmed_line.is_empty() || trimmed_line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((key, value)) = parse_environment(trimmed_line) {
+                environment.retain(|(exist_key, _)| exist_key != &key);
+                environment.push((key, value));
+                continue;
+            }
+
+            match parse_entry(line, trimmed_line, format) {
+                Ok(mut entry) => {
+                    entry.environment = environment.clone();
+                    entries.push(entry);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Crontab { entries })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Read and parse the crontab file.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        format: CrontabFormat,
+    ) -> Result<Crontab, Vec<CrontabError>> {
+        let content = fs::read_to_string(path).map_err(|e| vec![CrontabError::Io(e)])?;
+        Self::parse(&content, format)
+    }
+
+    /// Get the entries of crontab.
+    pub fn entries(&self) -> &[CrontabEntry] {
+        &self.entries
+    }
+
+    /// Spawn the tasks of all entries, the task ids are allocated from `first_task_id` in order.
+    pub fn spawn_tasks(&self, first_task_id: u64) -> Result<Vec<Task>, Vec<CrontabError>> {
+        let mut tasks = Vec::new();
+        let mut errors = Vec::new();
+        let mut task_id = first_task_id;
+
+        for entry in self.entries.iter() {
+            match entry.spawn_tasks(task_id) {
+                Ok(entry_tasks) => {
+                    task_id += entry_tasks.len() as u64;
+                    tasks.extend(entry_tasks);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(tasks)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl CrontabEntry {
+    /// The builder of the task body, the command runs by `SHELL -c` (`/bin/sh` by default)
+    /// with the environment settings, and the standard input is written to it.
+    pub fn process_task_builder(&self) -> ProcessTaskBuilder {
+        let shell = self
+            .environment
+            .iter()
+            .find(|(key, _)| key == "SHELL")
+            .map_or(DEFAULT_SHELL, |(_, value)| value.as_str());
+
+        let mut process_task_builder = ProcessTaskBuilder::new(shell);
+        process_task_builder.add_args(["-c", &self.command]);
+        for (key, value) in self.environment.iter() {
+            process_task_builder.add_env(key.as_str(), value.as_str());
+        }
+        if let Some(stdin) = self.stdin.as_ref() {
+            process_task_builder.set_stdin(stdin.as_str());
+        }
+        process_task_builder
+    }
+
+    /// Spawn the tasks of the entry, the task ids are allocated from `first_task_id` in order.
+    ///
+    /// The `@reboot` entry runs once two seconds later.
+    /// The tasks run in the local time zone, as the cron daemon does.
+    pub fn spawn_tasks(&self, first_task_id: u64) -> Result<Vec<Task>, CrontabError> {
+        if let Some(user) = self.user.as_ref() {
+            if !is_current_user(user) {
+                return Err(CrontabError::ForeignUser(self.line, user.clone()));
+            }
+        }
+
+        let line_label = self.line.to_string();
+        let process_task_builder = self.process_task_builder();
+
+        let spawn_task = |task_id: u64, frequency: Frequency<'_>| {
+            let mut task_builder = TaskBuilder::default();
+            task_builder
+                .set_frequency(frequency)
+                .set_task_id(task_id)
+                .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Local)
+                .add_label("crontab-line", &line_label);
+
+            task_builder
+                .spawn(process_task_builder.build())
+                .map_err(|e| CrontabError::Task(self.line, e))
+        };
+
+        if self.reboot {
+            let reboot_expression = Local
+                .timestamp_opt(get_timestamp() as i64 + 2, 0)
+                .single()
+                .map(|reboot_time| reboot_time.format("%S %M %H %d %m * %Y").to_string())
+                .unwrap_or_default();
+            return Ok(vec![spawn_task(
+                first_task_id,
+                Frequency::Once(&reboot_expression),
+            )?]);
+        }
+
+        self.cron_expressions
+            .iter()
+            .zip(first_task_id..)
+            .map(|(cron_expression, task_id)| {
+                spawn_task(task_id, Frequency::Repeated(cron_expression))
+            })
+            .collect()
+    }
+}
+
+// The user name is resolved by the password database.
+#[cfg(unix)]
+fn is_current_user(user: &str) -> bool {
+    use std::ffi::CString;
+    use std::{mem, ptr};
+
+    let name = match CString::new(user) {
+        Ok(name) => name,
+        Err(_) => return false,
+    };
+
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    // Safety: The `passwd` is filled in by `getpwnam_r`, its strings point into the buffer.
+    unsafe {
+        let mut passwd: libc::passwd = mem::zeroed();
+        let mut result = ptr::null_mut();
+        let code = libc::getpwnam_r(
+            name.as_ptr(),
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        code == 0 && !result.is_null() && passwd.pw_uid == libc::geteuid()
+    }
+}
+
+#[cfg(not(unix))]
+fn is_current_user(_user: &str) -> bool {
+    false
+}
+
+// `NAME = value`, the value may be quoted.
+fn parse_environment(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_at(line.find('=')?);
+    let key = key.trim();
+
+    let is_name = key
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_name {
+        return None;
+    }
+
+    let value = value[1..].trim();
+    let value = ['"', '\'']
+        .iter()
+        .find(|quote| value.len() >= 2 && value.starts_with(**quote) && value.ends_with(**quote))
+        .map(|_| &value[1..value.len() - 1])
+        .unwrap_or(value);
+
+    Some((key.to_string(), value.to_string()))
+}
+
+fn parse_entry(
+    line: usize,
+    content: &str,
+    format: CrontabFormat,
+) -> Result<CrontabEntry, CrontabError> {
+    let mut rest = content;
+    let mut next_field = || -> Option<&str> {
+        let trimmed = rest.trim_start();
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        let (field, remain) = trimmed.split_at(end);
+        rest = remain;
+        if field.is_empty() {
+            None
+        } else {
+            Some(field)
+        }
+    };
+
+    let first_field = next_field().ok_or(CrontabError::MissingFields(line))?;
+
+    let (cron_expressions, reboot) = if let Some(name) = first_field.strip_prefix('@') {
+        match translate_macro(name) {
+            Some(cron_expression) => (vec![cron_expression.to_string()], false),
+            None if name.eq_ignore_ascii_case("reboot") => (Vec::new(), true),
+            None => return Err(CrontabError::UnknownMacro(line, first_field.to_string())),
+        }
+    } else {
+        let mut fields = vec![first_field];
+        for _ in 0..4 {
+            fields.push(next_field().ok_or(CrontabError::MissingFields(line))?);
+        }
+        (translate_fields(line, &fields)?, false)
+    };
+
+    let user = match format {
+        CrontabFormat::User => None,
+        CrontabFormat::System => Some(
+            next_field()
+                .ok_or(CrontabError::MissingUser(line))?
+                .to_string(),
+        ),
+    };
+
+    let (command, stdin) = split_percent(rest.trim());
+    if command.trim().is_empty() {
+        return Err(CrontabError::MissingFields(line));
+    }
+
+    Ok(CrontabEntry {
+        line,
+        cron_expressions,
+        reboot,
+        user,
+        command,
+        stdin,
+        environment: Vec::new(),
+    })
+}
+
+fn translate_macro(name: &str) -> Option<&'static str> {
+    match name.to_ascii_lowercase().as_str() {
+        "yearly" | "annually" => Some("0 0 0 1 1 * *"),
+        "monthly" => Some("0 0 0 1 * * *"),
+        "weekly" => Some("0 0 0 * * 1 *"),
+        "daily" | "midnight" => Some("0 0 0 * * * *"),
+        "hourly" => Some("0 0 * * * * *"),
+        _ => None,
+    }
+}
+
+// `m h dom mon dow` => `s m h dom mon dow year`.
+fn translate_fields(line: usize, fields: &[&str]) -> Result<Vec<String>, CrontabError> {
+    let (minute, hour, day_of_month, month) = (fields[0], fields[1], fields[2], fields[3]);
+    let day_of_week = translate_day_of_week(fields[4])
+        .ok_or_else(|| CrontabError::InvalidDayOfWeek(line, fields[4].to_string()))?;
+
+    // The cron daemon runs the entry when either day field matches, if both of them are restricted.
+    let cron_expressions = if day_of_month.starts_with('*') || fields[4].starts_with('*') {
+        vec![format!(
+            "0 {} {} {} {} {} *",
+            minute, hour, day_of_month, month, day_of_week
+        )]
+    } else {
+        vec![
+            format!("0 {} {} {} {} * *", minute, hour, day_of_month, month),
+            format!("0 {} {} * {} {} *", minute, hour, month, day_of_week),
+        ]
+    };
+
+    for cron_expression in cron_expressions.iter() {
+        Schedule::from_str(cron_expression)
+            .map_err(|e| CrontabError::InvalidExpression(line, e.into()))?;
+    }
+
+    Ok(cron_expressions)
+}
+
+// The crontab counts the days of week from 0 (or 7) as Sunday,
+// but the expression of delay-timer counts from 1 as Sunday, so the field is expanded into a list.
+fn translate_day_of_week(field: &str) -> Option<String> {
+    let mut days = BTreeSet::new();
+
+    for item in field.split(',') {
+        let (range, step) = match item.find('/') {
+            Some(index) => (&item[..index], item[index + 1..].parse::<usize>().ok()?),
+            None => (item, 1),
+        };
+        if step == 0 {
+            return None;
+        }
+
+        let (start, end) = if range == "*" {
+            (0, 6)
+        } else if let Some(index) = range.find('-') {
+            (
+                day_ordinal(&range[..index])?,
+                day_ordinal(&range[index + 1..])?,
+            )
+        } else {
+            let start = day_ordinal(range)?;
+            (start, if item.contains('/') { 7 } else { start })
+        };
+        if start > end {
+            return None;
+        }
+
+        days.extend((start..=end).step_by(step).map(|day| day % 7));
+    }
+
+    if days.len() == 7 {
+        return Some(String::from("*"));
+    }
+
+    Some(
+        days.iter()
+            .map(|day| (day + 1).to_string())
+            .collect::<Vec<String>>()
+            .join(","),
+    )
+}
+
+fn day_ordinal(day: &str) -> Option<usize> {
+    if let Ok(ordinal) = day.parse::<usize>() {
+        return if ordinal <= 7 { Some(ordinal) } else { None };
+    }
+
+    let day = day.to_ascii_lowercase();
+    DAY_NAMES.iter().position(|name| day.starts_with(name))
+}
+
+// The first unescaped `%` ends the command, the remaining `%` are newlines of the standard input.
+fn split_percent(content: &str) -> (String, Option<String>) {
+    let mut command = String::new();
+    let mut stdin: Option<String> = None;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '%' && stdin.is_none() {
+            stdin = Some(String::new());
+            continue;
+        }
+
+        let target = stdin.as_mut().unwrap_or(&mut command);
+        match c {
+            '\\' if chars.peek() == Some(&'%') => {
+                target.push('%');
+                chars.next();
+            }
+            '%' => target.push('\n'),
+            _ => target.push(c),
+        }
+    }
+
+    (command.trim_end().to_string(), stdin)
+}
+
+mod tests {
+
+    #[test]
+    fn test_parse_crontab() {
+        use super::{Crontab, CrontabFormat};
+        use crate::prelude::*;
+
+        let content = "\
+# m h dom mon dow command
+SHELL=/bin/sh
+MAILTO = \"ops@example.com\"
+0 4 * * 0 backup.sh
+30 2 1 * mon-fri report.sh
+15 */2 * jan,jul 5-7 sync.sh \\% done
+@weekly cleanup.sh
+@reboot start.sh
+0 0 * * * mail -s hi ops%line one%line two
+";
+        let crontab = Crontab::parse(content, CrontabFormat::User).unwrap();
+        let entries = crontab.entries();
+        assert_eq!(entries.len(), 6);
+
+        assert_eq!(entries[0].line, 4);
+        assert_eq!(entries[0].cron_expressions, vec!["0 0 4 * * 1 *"]);
+        assert_eq!(
+            entries[0].environment,
+            vec![
+                (String::from("SHELL"), String::from("/bin/sh")),
+                (String::from("MAILTO"), String::from("ops@example.com"))
+            ]
+        );
+
+        assert_eq!(
+            entries[1].cron_expressions,
+            vec!["0 30 2 1 * * *", "0 30 2 * * 2,3,4,5,6 *"]
+        );
+        assert_eq!(
+            entries[2].cron_expressions,
+            vec!["0 15 */2 * jan,jul 1,6,7 *"]
+        );
+        assert_eq!(entries[2].command, "sync.sh % done");
+        assert_eq!(entries[3].cron_expressions, vec!["0 0 0 * * 1 *"]);
+        assert!(entries[4].reboot);
+
+        assert_eq!(entries[5].command, "mail -s hi ops");
+        assert_eq!(entries[5].stdin.as_deref(), Some("line one\nline two"));
+
+        let tasks = crontab.spawn_tasks(1).unwrap();
+        assert_eq!(tasks.len(), 7);
+        assert_eq!(tasks[6].task_id, 7);
+
+        let system_crontab = Crontab::parse(
+            "17 * * * * root run-parts /etc/cron.hourly",
+            CrontabFormat::System,
+        )
+        .unwrap();
+        assert_eq!(system_crontab.entries()[0].user.as_deref(), Some("root"));
+        assert_eq!(
+            system_crontab.entries()[0].command,
+            "run-parts /etc/cron.hourly"
+        );
+
+        let system_crontab = Crontab::parse(
+            "17 * * * * no-such-user-of-delay-timer run.sh",
+            CrontabFormat::System,
+        )
+        .unwrap();
+        assert!(matches!(
+            system_crontab.spawn_tasks(1).unwrap_err()[0],
+            CrontabError::ForeignUser(1, _)
+        ));
+
+        let errors = Crontab::parse(
+            "* * *\n@often run.sh\n0 0 * * 8 run.sh\n61 * * * * run.sh",
+            CrontabFormat::User,
+        )
+        .unwrap_err();
+        assert!(matches!(errors[0], CrontabError::MissingFields(1)));
+        assert!(matches!(errors[1], CrontabError::UnknownMacro(2, _)));
+        assert!(matches!(errors[2], CrontabError::InvalidDayOfWeek(3, _)));
+        assert!(matches!(errors[3], CrontabError::InvalidExpression(4, _)));
+    }
+}
@@ -0,0 +1,440 @@
+//! describe
+//! It is a module that translates cron expressions into human-readable text.
+use crate::prelude::*;
+use crate::timer::task::DelayTimerScheduleIteratorOwned;
+
+use std::str::FromStr;
+
+use cron_clock::{Schedule, TimeUnitSpec};
+
+const DAYS_OF_WEEK: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A preview of a cron expression, its description and the upcoming fire times.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronPreview {
+    /// Human-readable description of the expression.
+    pub description: String,
+    /// The upcoming fire times (timestamp in seconds).
+    pub upcoming: Vec<u64>,
+}
+
+/// Describe a cron expression in English,
+/// any expression accepted by the crate (including the candy forms like `@daily`) is supported.
+///
+/// ```
+/// use delay_timer::prelude::*;
+///
+/// let description = describe_cron_expression("0 */15 9-17 * * Mon-Fri *").unwrap();
+/// assert_eq!(
+///     description,
+///     "every 15 minutes between 09:00 and 17:59, Monday through Friday"
+/// );
+/// ```
+pub fn describe_cron_expression(
+    cron_expression: &str,
+) -> Result<String, CronExpressionAnalyzeError> {
+    let schedule = Schedule::from_str(cron_expression.trim_matches(' '))?;
+    Ok(CronDescriber::new(&schedule).describe())
+}
+
+/// Preview a cron expression, get its description and the next `count` fire times.
+pub fn preview_cron_expression(
+    cron_expression: &str,
+    time_zone: ScheduleIteratorTimeZone,
+    count: usize,
+) -> Result<CronPreview, CronExpressionAnalyzeError> {
+    let description = describe_cron_expression(cron_expression)?;
+    let mut schedule_iterator =
+        DelayTimerScheduleIteratorOwned::analyze_cron_expression(time_zone, cron_expression)?;
+
+    let upcoming = std::iter::from_fn(|| schedule_iterator.next())
+        .take(count)
+        .map(|t| t as u64)
+        .collect();
+
+    Ok(CronPreview {
+        description,
+        upcoming,
+    })
+}
+
+// The shape of the ordinals of one field in the expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FieldPattern {
+    All,
+    Single(u32),
+    Range(u32, u32),
+    // (start, step)
+    Step(u32, u32),
+    List(Vec<u32>),
+}
+
+impl FieldPattern {
+    fn new<T: TimeUnitSpec>(spec: &T, inclusive_min: u32, inclusive_max: u32) -> FieldPattern {
+        if spec.is_all() {
+            return FieldPattern::All;
+        }
+
+        let ordinals: Vec<u32> = spec.iter().collect();
+        match ordinals.as_slice() {
+            [single] => return FieldPattern::Single(*single),
+            [first, second, ..] => {
+                let step = second - first;
+                let is_evenly_spaced = ordinals.windows(2).all(|w| w[1] - w[0] == step);
+                let last = ordinals[ordinals.len() - 1];
+
+                if is_evenly_spaced && step == 1 {
+                    return FieldPattern::Range(*first, last);
+                }
+
+                // Only a step that runs through the whole field reads as `every n`.
+                if is_evenly_spaced && first - inclusive_min < step && last + step > inclusive_max {
+                    return FieldPattern::Step(*first, step);
+                }
+            }
+            [] => {}
+        }
+
+        FieldPattern::List(ordinals)
+    }
+
+    fn is_single(&self) -> bool {
+        matches!(self, FieldPattern::Single(_))
+    }
+}
+
+// Describer for a parsed `Schedule`.
+struct CronDescriber {
+    seconds: FieldPattern,
+    minutes: FieldPattern,
+    hours: FieldPattern,
+    days_of_month: FieldPattern,
+    months: FieldPattern,
+    days_of_week: FieldPattern,
+    years: FieldPattern,
+}
+
+impl CronDescriber {
+    fn new(schedule: &Schedule) -> Self {
+        CronDescriber {
+            seconds: FieldPattern::new(schedule.seconds(), 0, 59),
+            minutes: FieldPattern::new(schedule.minutes(), 0, 59),
+            hours: FieldPattern::new(schedule.hours(), 0, 23),
+            days_of_month: FieldPattern::new(schedule.days_of_month(), 1, 31),
+            months: FieldPattern::new(schedule.months(), 1, 12),
+            days_of_week: FieldPattern::new(schedule.days_of_week(), 1, 7),
+            years: FieldPattern::new(schedule.years(), 1970, 2100),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let (time_part, is_clock_time) = self.describe_time();
+        let date_parts: Vec<String> = [
+            self.describe_days_of_week(),
+            self.describe_days_of_month(),
+            self.describe_months(),
+            self.describe_years(),
+        ]
+        .iter()
+        .flatten()
+        .cloned()
+        .collect();
+
+        if date_parts.is_empty() {
+            if is_clock_time {
+                return format!("every day {}", time_part);
+            }
+            return time_part;
+        }
+
+        format!("{}, {}", time_part, date_parts.join(", "))
+    }
+
+    // Return the description and whether it is a fixed clock time.
+    fn describe_time(&self) -> (String, bool) {
+        use FieldPattern::*;
+
+        match (&self.seconds, &self.minutes, &self.hours) {
+            (Single(s), Single(m), Single(h)) => {
+                return (format!("at {}", clock(*h, *m, *s)), true)
+            }
+            (Single(s), Single(m), List(hours)) => {
+                let clocks: Vec<String> = hours.iter().map(|h| clock(*h, *m, *s)).collect();
+                return (format!("at {}", join_english(&clocks)), true);
+            }
+            _ => {}
+        }
+
+        let mut parts = Vec::new();
+
+        if !self.seconds.is_single() {
+            // Seconds lead, minutes and hours only qualify it.
+            parts.push(unit_phrase(&self.seconds, "second"));
+            parts.extend(self.minute_qualifier());
+            parts.extend(self.hour_qualifier());
+        } else if !self.minutes.is_single() {
+            parts.push(unit_phrase(&self.minutes, "minute"));
+            if matches!(self.minutes, List(_)) && matches!(self.hours, All) {
+                parts.push(String::from("past the hour"));
+            }
+            if let Single(s) = self.seconds {
+                if s != 0 {
+                    parts.push(format!("at second {}", s));
+                }
+            }
+            parts.extend(self.hour_qualifier());
+        } else {
+            // Both seconds and minutes are fixed, hours lead.
+            parts.push(self.hour_lead());
+            if let (Single(s), Single(m)) = (&self.seconds, &self.minutes) {
+                match (*m, *s) {
+                    (0, 0) => {}
+                    (m, 0) => parts.push(format!("at minute {}", m)),
+                    (m, s) => parts.push(format!("at {:02}:{:02} past the hour", m, s)),
+                }
+            }
+        }
+
+        (parts.join(" "), false)
+    }
+
+    fn minute_qualifier(&self) -> Option<String> {
+        Some(match &self.minutes {
+            FieldPattern::All => return None,
+            FieldPattern::Single(m) => format!("during minute {}", m),
+            FieldPattern::Range(a, b) => format!("during minutes {} through {}", a, b),
+            FieldPattern::Step(start, step) => step_phrase(*start, *step, "minute"),
+            FieldPattern::List(minutes) => format!("during minutes {}", join_numbers(minutes)),
+        })
+    }
+
+    fn hour_qualifier(&self) -> Option<String> {
+        Some(match &self.hours {
+            FieldPattern::All => return None,
+            FieldPattern::Single(h) => {
+                format!("between {} and {}", clock(*h, 0, 0), clock(*h, 59, 0))
+            }
+            FieldPattern::Range(a, b) => {
+                format!("between {} and {}", clock(*a, 0, 0), clock(*b, 59, 0))
+            }
+            FieldPattern::Step(start, step) => step_phrase(*start, *step, "hour"),
+            FieldPattern::List(hours) => {
+                let hours: Vec<String> = hours.iter().map(|h| format!("{:02}", h)).collect();
+                format!("during hours {}", join_english(&hours))
+            }
+        })
+    }
+
+    fn hour_lead(&self) -> String {
+        match &self.hours {
+            FieldPattern::Range(a, b) => format!(
+                "every hour between {} and {}",
+                clock(*a, 0, 0),
+                clock(*b, 59, 0)
+            ),
+            other => unit_phrase(other, "hour"),
+        }
+    }
+
+    fn describe_days_of_week(&self) -> Option<String> {
+        let name = |d: &u32| DAYS_OF_WEEK[(*d as usize - 1) % 7].to_string();
+
+        Some(match &self.days_of_week {
+            FieldPattern::All => return None,
+            FieldPattern::Single(d) => format!("only on {}", name(d)),
+            FieldPattern::Range(a, b) => format!("{} through {}", name(a), name(b)),
+            FieldPattern::Step(..) | FieldPattern::List(_) => {
+                let days: Vec<String> = self
+                    .ordinals(&self.days_of_week, 1, 7)
+                    .iter()
+                    .map(name)
+                    .collect();
+                format!("on {}", join_english(&days))
+            }
+        })
+    }
+
+    fn describe_days_of_month(&self) -> Option<String> {
+        Some(match &self.days_of_month {
+            FieldPattern::All => return None,
+            FieldPattern::Single(d) => format!("on day {} of the month", d),
+            FieldPattern::Range(a, b) => format!("on days {} through {} of the month", a, b),
+            FieldPattern::Step(start, step) => {
+                format!("{} of the month", step_phrase(*start, *step, "day"))
+            }
+            FieldPattern::List(days) => format!("on days {} of the month", join_numbers(days)),
+        })
+    }
+
+    fn describe_months(&self) -> Option<String> {
+        let name = |m: &u32| MONTHS[(*m as usize - 1) % 12].to_string();
+
+        Some(match &self.months {
+            FieldPattern::All => return None,
+            FieldPattern::Single(m) => format!("only in {}", name(m)),
+            FieldPattern::Range(a, b) => format!("{} through {}", name(a), name(b)),
+            FieldPattern::Step(..) | FieldPattern::List(_) => {
+                let months: Vec<String> = self
+                    .ordinals(&self.months, 1, 12)
+                    .iter()
+                    .map(name)
+                    .collect();
+                format!("in {}", join_english(&months))
+            }
+        })
+    }
+
+    fn describe_years(&self) -> Option<String> {
+        Some(match &self.years {
+            FieldPattern::All => return None,
+            FieldPattern::Single(y) => format!("only in {}", y),
+            FieldPattern::Range(a, b) => format!("from {} through {}", a, b),
+            FieldPattern::Step(..) | FieldPattern::List(_) => {
+                format!(
+                    "in {}",
+                    join_numbers(&self.ordinals(&self.years, 1970, 2100))
+                )
+            }
+        })
+    }
+
+    // Expand a pattern back to its ordinals, used where a step reads worse than a list.
+    fn ordinals(&self, pattern: &FieldPattern, inclusive_min: u32, inclusive_max: u32) -> Vec<u32> {
+        match pattern {
+            FieldPattern::All => (inclusive_min..=inclusive_max).collect(),
+            FieldPattern::Single(o) => vec![*o],
+            FieldPattern::Range(a, b) => (*a..=*b).collect(),
+            FieldPattern::Step(start, step) => {
+                (*start..=inclusive_max).step_by(*step as usize).collect()
+            }
+            FieldPattern::List(ordinals) => ordinals.clone(),
+        }
+    }
+}
+
+fn unit_phrase(pattern: &FieldPattern, unit: &str) -> String {
+    match pattern {
+        FieldPattern::All => format!("every {}", unit),
+        FieldPattern::Single(o) => format!("at {} {}", unit, o),
+        FieldPattern::Range(a, b) => format!("every {} from {} through {}", unit, a, b),
+        FieldPattern::Step(start, step) => step_phrase(*start, *step, unit),
+        FieldPattern::List(ordinals) => format!("at {}s {}", unit, join_numbers(ordinals)),
+    }
+}
+
+fn step_phrase(start: u32, step: u32, unit: &str) -> String {
+    let base_start = if unit == "day" { 1 } else { 0 };
+    if start == base_start {
+        format!("every {} {}s", step, unit)
+    } else {
+        format!("every {} {}s starting at {} {}", step, unit, unit, start)
+    }
+}
+
+fn clock(hour: u32, minute: u32, second: u32) -> String {
+    if second == 0 {
+        format!("{:02}:{:02}", hour, minute)
+    } else {
+        format!("{:02}:{:02}:{:02}", hour, minute, second)
+    }
+}
+
+fn join_numbers(ordinals: &[u32]) -> String {
+    let ordinals: Vec<String> = ordinals.iter().map(|o| o.to_string()).collect();
+    join_english(&ordinals)
+}
+
+fn join_english(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [single] => single.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_describe_cron_expression() -> anyhow::Result<()> {
+        use super::describe_cron_expression;
+
+        let cases = [
+            ("* * * * * * *", "every second"),
+            ("*/5 * * * * * *", "every 5 seconds"),
+            ("0 * * * * * *", "every minute"),
+            ("0 */15 * * * * *", "every 15 minutes"),
+            ("0 0 * * * * *", "every hour"),
+            ("0 30 * * * * *", "every hour at minute 30"),
+            ("0 0 */2 * * * *", "every 2 hours"),
+            ("0 30 9 * * * *", "every day at 09:30"),
+            ("0 0 9,12,18 * * * *", "every day at 09:00, 12:00 and 18:00"),
+            ("0 0,30 * * * * *", "every 30 minutes"),
+            ("0 5,20 * * * * *", "at minutes 5 and 20 past the hour"),
+            (
+                "0 */15 9-17 * * Mon-Fri *",
+                "every 15 minutes between 09:00 and 17:59, Monday through Friday",
+            ),
+            (
+                "0 0 8 1,15 * * *",
+                "at 08:00, on days 1 and 15 of the month",
+            ),
+            (
+                "0 0 0 * Jan-Mar * 2030",
+                "at 00:00, January through March, only in 2030",
+            ),
+        ];
+
+        for (expression, expected) in cases.iter() {
+            assert_eq!(&describe_cron_expression(expression)?, expected);
+        }
+
+        assert!(describe_cron_expression("* * *").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_describe_candy_cron() -> anyhow::Result<()> {
+        use super::describe_cron_expression;
+        use crate::prelude::{CandyCron, CandyCronStr};
+
+        let describe = |candy: CandyCron| describe_cron_expression(&CandyCronStr::from(candy));
+
+        assert_eq!(describe(CandyCron::Secondly)?, "every second");
+        assert_eq!(describe(CandyCron::Minutely)?, "every minute");
+        assert_eq!(describe(CandyCron::Hourly)?, "every hour");
+        assert_eq!(describe(CandyCron::Daily)?, "every day at 00:00");
+        assert_eq!(describe(CandyCron::Weekly)?, "at 00:00, only on Sunday");
+        assert_eq!(
+            describe(CandyCron::Monthly)?,
+            "at 00:00, on day 1 of the month"
+        );
+        assert_eq!(
+            describe(CandyCron::Yearly)?,
+            "at 00:00, on day 1 of the month, only in January"
+        );
+        Ok(())
+    }
+}
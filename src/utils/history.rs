@@ -0,0 +1,524 @@
+//! history
+//! It is a module that records the execution history of tasks,
+//! the record is kept when the running instance finishes, times out or is cancelled.
+//!
+//! The latest records of each task are kept in memory,
+//! and the `HistoryStore` can be set to persist all of them by a dedicated thread.
+//!
+//! # Required features
+//!
+//! `FileHistoryStore` requires the `history-store` feature of the `delay_timer` crate to be enabled.
+use crate::prelude::*;
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread::Builder;
+
+use futures::executor::block_on;
+use smol::channel::{unbounded, Receiver, Sender};
+
+cfg_history_store!(
+    use std::ffi::OsString;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+);
+
+/// The default number of execution records of a task kept in memory.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 16;
+
+/// The default maximum size of the file of `FileHistoryStore` before it's rotated.
+#[cfg(feature = "history-store")]
+pub const DEFAULT_HISTORY_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+// The maximum length of the output excerpt in the execution record.
+pub(crate) const OUTPUT_EXCERPT_LEN: usize = 1024;
+
+/// The record of a finished running instance of task.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(
+    any(feature = "history-store", feature = "admin-api"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ExecutionRecord {
+    /// The id of task.
+    pub task_id: u64,
+    /// The id of the task running instance.
+    pub record_id: i64,
+    /// The timestamp that the instance is scheduled at.
+    pub scheduled_time: u64,
+    /// The timestamp that the instance starts.
+    pub start_time: u64,
+    /// The timestamp that the instance finishes.
+    pub finish_time: u64,
+    /// The final state of the instance, e.g. `instance::COMPLETED`.
+    pub state: InstanceState,
+    /// The exit code of the process task.
+    pub exit_code: Option<i32>,
+    /// The error of the instance, it's the stderr excerpt of the failed process task.
+    pub error: Option<String>,
+    /// The tail of the stdout of the process task.
+    pub output: Option<String>,
+}
+
+impl ExecutionRecord {
+    pub(crate) fn new(
+        delay_task_handler_box: &DelayTaskHandlerBox,
+        state: InstanceState,
+        finish_time: u64,
+    ) -> ExecutionRecord {
+        ExecutionRecord {
+            task_id: delay_task_handler_box.get_task_id(),
+            record_id: delay_task_handler_box.get_record_id(),
+            scheduled_time: delay_task_handler_box.get_scheduled_time(),
+            start_time: delay_task_handler_box.get_start_time(),
+            finish_time,
+            state,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn set_finish_output(&mut self, finish_output: Option<&FinishOutput>) -> &mut Self {
+        let output = match finish_output {
+            Some(FinishOutput::ProcessOutput(output)) => output,
+            Some(FinishOutput::PipelineOutput(pipeline_output)) => &pipeline_output.output,
+            Some(FinishOutput::ExceptionOutput(error)) => {
+                self.error = Some(error.clone());
+                return self;
+            }
+            None => return self,
+        };
+
+        self.exit_code = output.status.code();
+        self.output = Some(output_excerpt(&output.stdout));
+        if !output.status.success() {
+            self.error = Some(output_excerpt(&output.stderr));
+        }
+        self
+    }
+}
+
+// Keep the tail of output, the latest lines are the most useful.
+fn output_excerpt(output: &[u8]) -> String {
+    let start = output.len().saturating_sub(OUTPUT_EXCERPT_LEN);
+    String::from_utf8_lossy(&output[start..]).into_owned()
+}
+
+/// The filter of execution records, the default filter matches all records.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    state: Option<InstanceState>,
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<usize>,
+}
+
+impl HistoryFilter {
+    /// Only select the records with the final `state`.
+    pub fn state(mut self, state: InstanceState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Only select the records that start at or after the timestamp.
+    pub fn since(mut self, timestamp: u64) -> Self {
+        self.since = Some(timestamp);
+        self
+    }
+
+    /// Only select the records that start at or before the timestamp.
+    pub fn until(mut self, timestamp: u64) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
+    /// Only select the latest `limit` records.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Whether the record is matched by the state and time range of filter.
+    pub fn matches(&self, record: &ExecutionRecord) -> bool {
+        if matches!(self.state, Some(state) if state != record.state) {
+            return false;
+        }
+
+        if matches!(self.since, Some(since) if record.start_time < since) {
+            return false;
+        }
+
+        !matches!(self.until, Some(until) if record.start_time > until)
+    }
+
+    /// Filter the records that are ordered from oldest to newest, and apply the limit.
+    pub fn apply<I>(&self, records: I) -> Vec<ExecutionRecord>
+    where
+        I: IntoIterator<Item = ExecutionRecord>,
+    {
+        let mut records: Vec<ExecutionRecord> = records
+            .into_iter()
+            .filter(|record| self.matches(record))
+            .collect();
+
+        if let Some(limit) = self.limit {
+            let start = records.len().saturating_sub(limit);
+            records.drain(..start);
+        }
+        records
+    }
+}
+
+/// The storage of `ExecutionRecord`, it is written when the running instance finishes,
+/// and is read by `DelayTimer::history`.
+///
+/// The records are appended by a dedicated thread of `DelayTimer`, so the timer isn't blocked by the storage,
+/// and the records are queried by the thread that calls `DelayTimer::history`.
+pub trait HistoryStore: Send + Sync {
+    /// Append the record.
+    fn append(&self, record: &ExecutionRecord) -> Result<(), HistoryStoreError>;
+
+    /// Query the records of the task, they are ordered from oldest to newest.
+    fn query(
+        &self,
+        task_id: u64,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<ExecutionRecord>, HistoryStoreError>;
+}
+
+// The writer that sends the records to a dedicated thread, which appends them to the `HistoryStore`.
+#[derive(Clone)]
+pub(crate) struct HistoryStoreWriter {
+    history_store: Arc<dyn HistoryStore>,
+    sender: Sender<ExecutionRecord>,
+}
+
+impl HistoryStoreWriter {
+    // The thread exits after all the writers are dropped and the pending records are appended.
+    pub(crate) fn new(history_store: Arc<dyn HistoryStore>) -> Self {
+        let (sender, receiver) = unbounded();
+        let writer_history_store = history_store.clone();
+        Builder::new()
+            .name("history_store_writer".into())
+            .spawn(move || Self::append_records(writer_history_store.as_ref(), receiver))
+            .expect("history_store_writer can't start.");
+
+        HistoryStoreWriter {
+            history_store,
+            sender,
+        }
+    }
+
+    fn append(&self, record: ExecutionRecord) {
+        let (task_id, record_id) = (record.task_id, record.record_id);
+        self.sender.try_send(record).unwrap_or_else(|e| {
+            error!(
+                "Can't append the record {} of task {} to history-store: {}",
+                record_id, task_id, e
+            )
+        });
+    }
+
+    fn append_records(history_store: &dyn HistoryStore, receiver: Receiver<ExecutionRecord>) {
+        while let Ok(record) = block_on(receiver.recv()) {
+            history_store.append(&record).unwrap_or_else(|e| {
+                error!(
+                    "Can't append the record {} of task {} to history-store: {}",
+                    record.record_id, record.task_id, e
+                )
+            });
+        }
+    }
+}
+
+// The execution history shared by `DelayTimer` and `EventHandle`.
+#[derive(Clone, Default)]
+pub(crate) struct ExecutionHistory {
+    records: Arc<DashMap<u64, VecDeque<ExecutionRecord>>>,
+    pub(crate) store_writer: Option<HistoryStoreWriter>,
+}
+
+impl ExecutionHistory {
+    pub(crate) fn record(&self, record: ExecutionRecord, capacity: usize) {
+        if let Some(store_writer) = self.store_writer.as_ref() {
+            store_writer.append(record.clone());
+        }
+
+        if capacity == 0 {
+            return;
+        }
+
+        let mut records = self.records.entry(record.task_id).or_default();
+        records.push_back(record);
+        while records.len() > capacity {
+            records.pop_front();
+        }
+    }
+
+    pub(crate) fn remove(&self, task_id: u64) {
+        self.records.remove(&task_id);
+    }
+
+    pub(crate) fn query(
+        &self,
+        task_id: u64,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<ExecutionRecord>, HistoryStoreError> {
+        if let Some(store_writer) = self.store_writer.as_ref() {
+            return store_writer.history_store.query(task_id, filter);
+        }
+
+        let records = self
+            .records
+            .get(&task_id)
+            .map(|records| records.value().iter().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        Ok(filter.apply(records))
+    }
+}
+
+cfg_history_store!(
+    /// The `HistoryStore` that appends records to a file, one json per line.
+    ///
+    /// The file is rotated to `{path}.1` when it exceeds the maximum size,
+    /// so at most two files are kept and the oldest records are dropped.
+    /// The lines that can't be decoded, e.g. the one truncated by a crash, are skipped when querying.
+    #[derive(Debug)]
+    pub struct FileHistoryStore {
+        path: PathBuf,
+        max_file_size: u64,
+        lock: Mutex<()>,
+    }
+
+    impl FileHistoryStore {
+        /// New a `FileHistoryStore`, the file is created when the first record is appended.
+        pub fn new<P: Into<PathBuf>>(path: P) -> FileHistoryStore {
+            FileHistoryStore {
+                path: path.into(),
+                max_file_size: DEFAULT_HISTORY_FILE_SIZE,
+                lock: Mutex::new(()),
+            }
+        }
+
+        /// Set the maximum size of the file before it's rotated.
+        pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+            self.max_file_size = max_file_size;
+            self
+        }
+
+        fn rotated_path(&self) -> PathBuf {
+            let mut path = OsString::from(self.path.as_os_str());
+            path.push(".1");
+            PathBuf::from(path)
+        }
+
+        // Open the file and get its length, only the complete lines before the length are read.
+        fn open_with_len(path: &PathBuf) -> Result<Option<(File, u64)>, HistoryStoreError> {
+            match File::open(path) {
+                Ok(file) => {
+                    let len = file.metadata()?.len();
+                    Ok(Some((file, len)))
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    impl HistoryStore for FileHistoryStore {
+        fn append(&self, record: &ExecutionRecord) -> Result<(), HistoryStoreError> {
+            let mut line = serde_json::to_string(record)?;
+            line.push('\n');
+
+            let _guard = self.lock.lock().map_err(|_| HistoryStoreError::Poisoned)?;
+            let size = match fs::metadata(&self.path) {
+                Ok(metadata) => metadata.len(),
+                Err(e) if e.kind() == ErrorKind::NotFound => 0,
+                Err(e) => return Err(e.into()),
+            };
+            if size > 0 && size + line.len() as u64 > self.max_file_size {
+                fs::rename(&self.path, self.rotated_path())?;
+            }
+
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?
+                .write_all(line.as_bytes())?;
+            Ok(())
+        }
+
+        fn query(
+            &self,
+            task_id: u64,
+            filter: &HistoryFilter,
+        ) -> Result<Vec<ExecutionRecord>, HistoryStoreError> {
+            // The files are parsed without the lock, the records appended later are not read.
+            let files = {
+                let _guard = self.lock.lock().map_err(|_| HistoryStoreError::Poisoned)?;
+                vec![
+                    Self::open_with_len(&self.rotated_path())?,
+                    Self::open_with_len(&self.path)?,
+                ]
+            };
+
+            let mut records = Vec::new();
+            for (file, len) in files.into_iter().flatten() {
+                for line in BufReader::new(file.take(len)).split(b'\n') {
+                    let line = line?;
+                    if line.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+
+                    match serde_json::from_slice::<ExecutionRecord>(&line) {
+                        Ok(record) if record.task_id == task_id => records.push(record),
+                        Ok(_) => {}
+                        Err(e) => error!(
+                            "Skip the invalid line of history-store `{}`: {}",
+                            self.path.display(),
+                            e
+                        ),
+                    }
+                }
+            }
+            Ok(filter.apply(records))
+        }
+    }
+);
+
+mod tests {
+
+    #[test]
+    fn test_execution_history() {
+        use super::{ExecutionHistory, ExecutionRecord, HistoryFilter};
+        use crate::prelude::*;
+
+        let execution_history = ExecutionHistory::default();
+        for record_id in 0..5 {
+            let record = ExecutionRecord {
+                task_id: 1,
+                record_id,
+                start_time: record_id as u64 * 10,
+                state: if record_id % 2 == 0 {
+                    instance::COMPLETED
+                } else {
+                    instance::TIMEOUT
+                },
+                ..Default::default()
+            };
+            execution_history.record(record, 3);
+        }
+
+        let record_ids = |filter: HistoryFilter| -> Vec<i64> {
+            execution_history
+                .query(1, &filter)
+                .unwrap()
+                .iter()
+                .map(|record| record.record_id)
+                .collect()
+        };
+
+        // Only the latest 3 records are kept.
+        assert_eq!(record_ids(HistoryFilter::default()), vec![2, 3, 4]);
+        assert_eq!(
+            record_ids(HistoryFilter::default().state(instance::COMPLETED)),
+            vec![2, 4]
+        );
+        assert_eq!(record_ids(HistoryFilter::default().since(30)), vec![3, 4]);
+        assert_eq!(record_ids(HistoryFilter::default().until(30)), vec![2, 3]);
+        assert_eq!(record_ids(HistoryFilter::default().limit(1)), vec![4]);
+        assert!(record_ids(HistoryFilter::default().state(instance::CANCELLED)).is_empty());
+
+        execution_history.remove(1);
+        assert!(record_ids(HistoryFilter::default()).is_empty());
+    }
+
+    #[cfg(feature = "history-store")]
+    #[test]
+    fn test_file_history_store() -> anyhow::Result<()> {
+        use super::{
+            ExecutionHistory, ExecutionRecord, FileHistoryStore, HistoryFilter, HistoryStore,
+            HistoryStoreWriter,
+        };
+        use std::io::Write;
+        use std::sync::Arc;
+        use std::time::{Duration, Instant};
+
+        let path =
+            std::env::temp_dir().join(format!("delay_timer_history_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let history_store = FileHistoryStore::new(&path);
+        assert!(history_store
+            .query(1, &HistoryFilter::default())?
+            .is_empty());
+
+        for task_id in 1..=2 {
+            history_store.append(&ExecutionRecord {
+                task_id,
+                exit_code: Some(0),
+                output: Some(String::from("ok\n")),
+                ..Default::default()
+            })?;
+        }
+
+        let records = history_store.query(2, &HistoryFilter::default())?;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].output.as_deref(), Some("ok\n"));
+
+        // The line truncated by a crash is skipped.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)?
+            .write_all(b"{\"task_id\":2,\"rec")?;
+        assert_eq!(history_store.query(2, &HistoryFilter::default())?.len(), 1);
+        std::fs::remove_file(&path)?;
+
+        // The file is rotated once it's full, only the latest two files are kept.
+        let line_len = serde_json::to_string(&ExecutionRecord::default())?.len() as u64 + 1;
+        let history_store = FileHistoryStore::new(&path).max_file_size(line_len * 2);
+        for record_id in 0..5 {
+            history_store.append(&ExecutionRecord {
+                task_id: 1,
+                record_id,
+                ..Default::default()
+            })?;
+        }
+        let record_ids: Vec<i64> = history_store
+            .query(1, &HistoryFilter::default())?
+            .iter()
+            .map(|record| record.record_id)
+            .collect();
+        assert_eq!(record_ids, vec![2, 3, 4]);
+
+        std::fs::remove_file(&path)?;
+        std::fs::remove_file(path.with_extension("jsonl.1"))?;
+
+        // The records are appended by the writer thread.
+        let execution_history = ExecutionHistory {
+            store_writer: Some(HistoryStoreWriter::new(Arc::new(FileHistoryStore::new(
+                &path,
+            )))),
+            ..Default::default()
+        };
+        execution_history.record(
+            ExecutionRecord {
+                task_id: 3,
+                ..Default::default()
+            },
+            0,
+        );
+        let deadline = Instant::now() + Duration::from_secs(3);
+        while execution_history
+            .query(3, &HistoryFilter::default())?
+            .is_empty()
+        {
+            assert!(Instant::now() < deadline);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
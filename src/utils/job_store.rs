@@ -0,0 +1,638 @@
+//! job_store
+//! It is a module that persists the definitions and running state of tasks,
+//! so tasks can be restored after the process restarts.
+//!
+//! # Required features
+//!
+//! This module requires the `job-store` feature of the `delay_timer` crate to be enabled,
+//! and `SqliteJobStore` requires the `sqlite-store` feature.
+use crate::prelude::*;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread::Builder;
+
+use crate::utils::task_spec::{HandlerRegistry, HandlerSpec};
+use cron_clock::Schedule;
+use futures::executor::block_on;
+use serde::{Deserialize, Serialize};
+use smol::channel::{unbounded, Receiver, Sender};
+
+cfg_sqlite_store!(
+    use rusqlite::{params, Connection, OptionalExtension};
+    use std::path::Path;
+);
+
+/// The storage of `TaskRecord`, it is written when the task is added, updated, fired and removed,
+/// and is read when `DelayTimerBuilder::build`.
+///
+/// The records are written by a dedicated thread of `DelayTimer`, so the timer isn't blocked by the storage,
+/// and the changes of a task that are pending at the same time are merged, only the latest one is written.
+pub trait JobStore: Send + Sync {
+    /// Save the record, the record with the same task_id is overwritten.
+    fn save(&self, record: &TaskRecord) -> Result<(), JobStoreError>;
+
+    /// Remove the record of the task.
+    fn remove(&self, task_id: u64) -> Result<(), JobStoreError>;
+
+    /// Load all records.
+    fn load(&self) -> Result<Vec<TaskRecord>, JobStoreError>;
+}
+
+/// The time zone of `TaskRecord`, it can be serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskRecordTimeZone {
+    /// Utc time zone.
+    Utc,
+    /// The system local time zone.
+    Local,
+    /// The fixed time zone, the offset is the number of seconds east of UTC.
+    FixedOffset(i32),
+}
+
+impl From<ScheduleIteratorTimeZone> for TaskRecordTimeZone {
+    fn from(value: ScheduleIteratorTimeZone) -> Self {
+        match value {
+            ScheduleIteratorTimeZone::Utc => TaskRecordTimeZone::Utc,
+            ScheduleIteratorTimeZone::Local => TaskRecordTimeZone::Local,
+            ScheduleIteratorTimeZone::FixedOffset(offset) => {
+                TaskRecordTimeZone::FixedOffset(offset.local_minus_utc())
+            }
+        }
+    }
+}
+
+impl From<TaskRecordTimeZone> for ScheduleIteratorTimeZone {
+    fn from(value: TaskRecordTimeZone) -> Self {
+        match value {
+            TaskRecordTimeZone::Utc => ScheduleIteratorTimeZone::Utc,
+            TaskRecordTimeZone::Local => ScheduleIteratorTimeZone::Local,
+            TaskRecordTimeZone::FixedOffset(seconds) => FixedOffset::east_opt(seconds).map_or(
+                ScheduleIteratorTimeZone::Utc,
+                ScheduleIteratorTimeZone::FixedOffset,
+            ),
+        }
+    }
+}
+
+/// The persistent record of a task, the definition of task and its running state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRecord {
+    /// The id of task.
+    pub task_id: u64,
+    /// The cron expression that drives the task.
+    pub cron_expression: String,
+    /// The remaining count of the `CountDown` task, it's `None` for the repeated task.
+    pub remaining_count: Option<u32>,
+    /// Time zone for cron-expression iteration time.
+    pub time_zone: TaskRecordTimeZone,
+    /// Maximum execution time.
+    pub maximum_running_time: Option<u64>,
+    /// Maximum parallel runable num.
+    pub maximun_parallel_runable_num: Option<u64>,
+    /// The name of task.
+    pub name: Option<String>,
+    /// The labels of task.
+    pub labels: HashMap<String, String>,
+    /// The timestamp of the last time the task fired.
+    pub last_fire_time: Option<u64>,
+    /// The name of the registered handler if the task is built from `TaskSpec`.
+    #[serde(default)]
+    pub handler: Option<String>,
+    /// The json payload of the handler.
+    #[serde(default)]
+    pub payload: Option<String>,
+}
+
+impl From<&Task> for TaskRecord {
+    fn from(task: &Task) -> Self {
+        TaskRecord {
+            task_id: task.task_id,
+            cron_expression: task.cron_expression.clone(),
+            remaining_count: task.get_remaining_count(),
+            time_zone: task.schedule_iterator_time_zone.into(),
+            maximum_running_time: task.maximum_running_time,
+            maximun_parallel_runable_num: task.maximun_parallel_runable_num,
+            name: task.name.clone(),
+            labels: task.labels.clone(),
+            last_fire_time: task.last_fire_time,
+            handler: task
+                .handler_spec
+                .as_ref()
+                .map(|handler_spec| handler_spec.handler.clone()),
+            payload: task
+                .handler_spec
+                .as_ref()
+                .map(|handler_spec| handler_spec.payload.to_string()),
+        }
+    }
+}
+
+impl TaskRecord {
+    /// Restore the task from the record with the `body`, the remaining count is kept.
+    pub fn restore<F>(&self, body: F) -> Result<Task, TaskError>
+    where
+        F: Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync,
+    {
+        let frequency = match self.remaining_count {
+            Some(remaining_count) => Frequency::CountDown(remaining_count, &self.cron_expression),
+            None => Frequency::Repeated(&self.cron_expression),
+        };
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency(frequency)
+            .set_task_id(self.task_id)
+            .set_schedule_iterator_time_zone(self.time_zone.into());
+
+        if let Some(maximum_running_time) = self.maximum_running_time {
+            task_builder.set_maximum_running_time(maximum_running_time);
+        }
+
+        if let Some(maximun_parallel_runable_num) = self.maximun_parallel_runable_num {
+            task_builder.set_maximun_parallel_runable_num(maximun_parallel_runable_num);
+        }
+
+        if let Some(name) = self.name.as_deref() {
+            task_builder.set_name(name);
+        }
+
+        for (key, value) in self.labels.iter() {
+            task_builder.add_label(key, value);
+        }
+
+        let mut task = task_builder.spawn(body)?;
+        task.last_fire_time = self.last_fire_time;
+        Ok(task)
+    }
+
+    // Whether the task missed its fire between the last fire and `timestamp`, e.g. when the process was down.
+    pub(crate) fn missed_fire(&self, timestamp: u64) -> bool {
+        let (last_fire_time, schedule) = match (
+            self.last_fire_time,
+            Schedule::from_str(self.cron_expression.trim_matches(' ')),
+        ) {
+            (Some(last_fire_time), Ok(schedule)) => (last_fire_time as i64, schedule),
+            _ => return false,
+        };
+
+        let next_fire_time = match ScheduleIteratorTimeZone::from(self.time_zone) {
+            ScheduleIteratorTimeZone::Utc => Utc
+                .timestamp_opt(last_fire_time, 0)
+                .single()
+                .and_then(|time| schedule.after(&time).next())
+                .map(|time| time.timestamp()),
+            ScheduleIteratorTimeZone::Local => Local
+                .timestamp_opt(last_fire_time, 0)
+                .single()
+                .and_then(|time| schedule.after(&time).next())
+                .map(|time| time.timestamp()),
+            ScheduleIteratorTimeZone::FixedOffset(offset) => offset
+                .timestamp_opt(last_fire_time, 0)
+                .single()
+                .and_then(|time| schedule.after(&time).next())
+                .map(|time| time.timestamp()),
+        };
+
+        matches!(next_fire_time, Some(next_fire_time) if (next_fire_time as u64) < timestamp)
+    }
+
+    // Restore the task built from `TaskSpec`, return `None` if its handler is not registered.
+    pub(crate) fn restore_by_handler(
+        &self,
+        handler_registry: &HandlerRegistry,
+    ) -> Option<Result<Task, TaskError>> {
+        let handler_name = self.handler.as_ref()?;
+        let handler = handler_registry.get(handler_name)?;
+        let payload: serde_json::Value = match self.payload.as_deref() {
+            Some(payload) => serde_json::from_str(payload).ok()?,
+            None => serde_json::Value::Null,
+        };
+
+        let handler_spec = HandlerSpec {
+            handler: handler_name.clone(),
+            payload: payload.clone(),
+        };
+        let restored_task = self.restore(move |context| handler(context, &payload));
+
+        Some(restored_task.map(|mut task| {
+            task.handler_spec = Some(handler_spec);
+            task
+        }))
+    }
+}
+
+// The change of the record of a task, `None` removes the record.
+type RecordChange = (u64, Option<TaskRecord>);
+
+// Write the changes of records to the job-store by a dedicated thread.
+#[derive(Clone)]
+pub(crate) struct JobStoreWriter {
+    pub(crate) job_store: Arc<dyn JobStore>,
+    sender: Sender<RecordChange>,
+}
+
+impl JobStoreWriter {
+    // The thread exits after all the writers are dropped and the pending changes are written.
+    pub(crate) fn new(job_store: Arc<dyn JobStore>) -> Self {
+        let (sender, receiver) = unbounded();
+        let writer_job_store = job_store.clone();
+        Builder::new()
+            .name("job_store_writer".into())
+            .spawn(move || Self::write_changes(writer_job_store.as_ref(), receiver))
+            .expect("job_store_writer can't start.");
+
+        JobStoreWriter { job_store, sender }
+    }
+
+    pub(crate) fn save(&self, record: TaskRecord) {
+        self.send(record.task_id, Some(record));
+    }
+
+    pub(crate) fn remove(&self, task_id: u64) {
+        self.send(task_id, None);
+    }
+
+    fn send(&self, task_id: u64, record: Option<TaskRecord>) {
+        self.sender
+            .try_send((task_id, record))
+            .unwrap_or_else(|e| error!("Can't write the task {} to job-store: {}", task_id, e));
+    }
+
+    fn write_changes(job_store: &dyn JobStore, receiver: Receiver<RecordChange>) {
+        while let Ok((task_id, record)) = block_on(receiver.recv()) {
+            let mut changes = BTreeMap::new();
+            changes.insert(task_id, record);
+            while let Ok((task_id, record)) = receiver.try_recv() {
+                changes.insert(task_id, record);
+            }
+
+            for (task_id, record) in changes {
+                let result = match record {
+                    Some(record) => job_store.save(&record),
+                    None => job_store.remove(task_id),
+                };
+                result.unwrap_or_else(|e| {
+                    error!("Can't write the task {} to job-store: {}", task_id, e)
+                });
+            }
+        }
+    }
+}
+
+type SafeRestoreFn = Arc<dyn Fn(&TaskRecord) -> Option<Task> + 'static + Send + Sync>;
+
+/// Rebuild the task from the record when `DelayTimerBuilder::build`.
+#[derive(Clone)]
+pub(crate) struct TaskRestorer(pub(crate) SafeRestoreFn);
+
+impl fmt::Debug for TaskRestorer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TaskRestorer").finish()
+    }
+}
+
+/// The format of the file that `FileJobStore` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Human-readable json.
+    Json,
+    /// Compact bincode.
+    Bincode,
+}
+
+/// `JobStore` based on a local file, all records are rewritten to the file when any of them changes.
+#[derive(Debug)]
+pub struct FileJobStore {
+    path: PathBuf,
+    format: FileFormat,
+    records: Mutex<BTreeMap<u64, TaskRecord>>,
+}
+
+impl FileJobStore {
+    /// Open the store file, it's created when the first record is saved.
+    pub fn new<P: Into<PathBuf>>(path: P, format: FileFormat) -> Result<Self, JobStoreError> {
+        let path = path.into();
+
+        let records = match fs::read(&path) {
+            Ok(bytes) => Self::decode(format, &bytes)?
+                .into_iter()
+                .map(|record| (record.task_id, record))
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(FileJobStore {
+            path,
+            format,
+            records: Mutex::new(records),
+        })
+    }
+
+    fn decode(format: FileFormat, bytes: &[u8]) -> Result<Vec<TaskRecord>, JobStoreError> {
+        Ok(match format {
+            FileFormat::Json => serde_json::from_slice(bytes)?,
+            FileFormat::Bincode => bincode::deserialize(bytes)?,
+        })
+    }
+
+    // Write to a temporary file first, then rename it, so the store file is never half-written.
+    fn flush(&self, records: &BTreeMap<u64, TaskRecord>) -> Result<(), JobStoreError> {
+        let records: Vec<&TaskRecord> = records.values().collect();
+        let bytes = match self.format {
+            FileFormat::Json => serde_json::to_vec_pretty(&records)?,
+            FileFormat::Bincode => bincode::serialize(&records)?,
+        };
+
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(&temporary_path, bytes)?;
+        fs::rename(&temporary_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl JobStore for FileJobStore {
+    fn save(&self, record: &TaskRecord) -> Result<(), JobStoreError> {
+        let mut records = self.records.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+        records.insert(record.task_id, record.clone());
+        self.flush(&records)
+    }
+
+    fn remove(&self, task_id: u64) -> Result<(), JobStoreError> {
+        let mut records = self.records.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+        if records.remove(&task_id).is_some() {
+            self.flush(&records)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<TaskRecord>, JobStoreError> {
+        let records = self.records.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+        Ok(records.values().cloned().collect())
+    }
+}
+
+cfg_sqlite_store!(
+    /// `JobStore` based on an embedded SQLite database.
+    #[derive(Debug)]
+    pub struct SqliteJobStore {
+        connection: Mutex<Connection>,
+    }
+
+    impl SqliteJobStore {
+        /// Open the SQLite database, the table `delay_timer_task` is created if it doesn't exist.
+        pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, JobStoreError> {
+            Self::init(Connection::open(path)?)
+        }
+
+        /// Open an in-memory SQLite database, it is mainly used for testing.
+        pub fn open_in_memory() -> Result<Self, JobStoreError> {
+            Self::init(Connection::open_in_memory()?)
+        }
+
+        fn init(connection: Connection) -> Result<Self, JobStoreError> {
+            connection.execute(
+                "CREATE TABLE IF NOT EXISTS delay_timer_task (
+                    task_id INTEGER PRIMARY KEY,
+                    cron_expression TEXT NOT NULL,
+                    remaining_count INTEGER,
+                    time_zone TEXT NOT NULL,
+                    maximum_running_time INTEGER,
+                    maximun_parallel_runable_num INTEGER,
+                    name TEXT,
+                    labels TEXT NOT NULL,
+                    last_fire_time INTEGER,
+                    handler TEXT,
+                    payload TEXT
+                )",
+                params![],
+            )?;
+
+            Ok(SqliteJobStore {
+                connection: Mutex::new(connection),
+            })
+        }
+
+        /// Get the record of the task.
+        pub fn get(&self, task_id: u64) -> Result<Option<TaskRecord>, JobStoreError> {
+            let connection = self.connection.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+            let row = connection
+                .query_row(
+                    "SELECT task_id, cron_expression, remaining_count, time_zone, maximum_running_time,
+                        maximun_parallel_runable_num, name, labels, last_fire_time, handler, payload
+                    FROM delay_timer_task WHERE task_id = ?1",
+                    params![task_id as i64],
+                    SqliteTaskRow::from_row,
+                )
+                .optional()?;
+
+            row.map(SqliteTaskRow::into_record).transpose()
+        }
+    }
+
+    // The raw columns of a row, the json columns are decoded after the query.
+    struct SqliteTaskRow {
+        task_id: i64,
+        cron_expression: String,
+        remaining_count: Option<u32>,
+        time_zone: String,
+        maximum_running_time: Option<i64>,
+        maximun_parallel_runable_num: Option<i64>,
+        name: Option<String>,
+        labels: String,
+        last_fire_time: Option<i64>,
+        handler: Option<String>,
+        payload: Option<String>,
+    }
+
+    impl SqliteTaskRow {
+        fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+            Ok(SqliteTaskRow {
+                task_id: row.get(0)?,
+                cron_expression: row.get(1)?,
+                remaining_count: row.get(2)?,
+                time_zone: row.get(3)?,
+                maximum_running_time: row.get(4)?,
+                maximun_parallel_runable_num: row.get(5)?,
+                name: row.get(6)?,
+                labels: row.get(7)?,
+                last_fire_time: row.get(8)?,
+                handler: row.get(9)?,
+                payload: row.get(10)?,
+            })
+        }
+
+        fn into_record(self) -> Result<TaskRecord, JobStoreError> {
+            Ok(TaskRecord {
+                task_id: self.task_id as u64,
+                cron_expression: self.cron_expression,
+                remaining_count: self.remaining_count,
+                time_zone: serde_json::from_str(&self.time_zone)?,
+                maximum_running_time: self.maximum_running_time.map(|t| t as u64),
+                maximun_parallel_runable_num: self.maximun_parallel_runable_num.map(|n| n as u64),
+                name: self.name,
+                labels: serde_json::from_str(&self.labels)?,
+                last_fire_time: self.last_fire_time.map(|t| t as u64),
+                handler: self.handler,
+                payload: self.payload,
+            })
+        }
+    }
+
+    impl JobStore for SqliteJobStore {
+        fn save(&self, record: &TaskRecord) -> Result<(), JobStoreError> {
+            let connection = self.connection.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+            connection.execute(
+                "INSERT OR REPLACE INTO delay_timer_task (task_id, cron_expression, remaining_count,
+                    time_zone, maximum_running_time, maximun_parallel_runable_num, name, labels, last_fire_time, handler, payload)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    record.task_id as i64,
+                    record.cron_expression,
+                    record.remaining_count,
+                    serde_json::to_string(&record.time_zone)?,
+                    record.maximum_running_time.map(|t| t as i64),
+                    record.maximun_parallel_runable_num.map(|n| n as i64),
+                    record.name,
+                    serde_json::to_string(&record.labels)?,
+                    record.last_fire_time.map(|t| t as i64),
+                    record.handler,
+                    record.payload,
+                ],
+            )?;
+            Ok(())
+        }
+
+        fn remove(&self, task_id: u64) -> Result<(), JobStoreError> {
+            let connection = self.connection.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+            connection.execute(
+                "DELETE FROM delay_timer_task WHERE task_id = ?1",
+                params![task_id as i64],
+            )?;
+            Ok(())
+        }
+
+        fn load(&self) -> Result<Vec<TaskRecord>, JobStoreError> {
+            let connection = self.connection.lock().map_err(|_| JobStoreError::Poisoned)?;
+
+            let mut statement = connection.prepare(
+                "SELECT task_id, cron_expression, remaining_count, time_zone, maximum_running_time,
+                    maximun_parallel_runable_num, name, labels, last_fire_time, handler, payload
+                FROM delay_timer_task ORDER BY task_id",
+            )?;
+
+            let rows = statement
+                .query_map(params![], SqliteTaskRow::from_row)?
+                .collect::<rusqlite::Result<Vec<SqliteTaskRow>>>()?;
+
+            rows.into_iter().map(SqliteTaskRow::into_record).collect()
+        }
+    }
+);
+
+mod tests {
+
+    #[test]
+    fn test_file_job_store() -> anyhow::Result<()> {
+        use super::{FileFormat, FileJobStore, JobStore, TaskRecord};
+        use crate::prelude::*;
+
+        for format in [FileFormat::Json, FileFormat::Bincode].iter() {
+            let path = std::env::temp_dir().join(format!(
+                "delay_timer_job_store_{}_{:?}",
+                get_timestamp_micros(),
+                format
+            ));
+
+            let mut task_builder = TaskBuilder::default();
+            task_builder
+                .set_frequency(Frequency::CountDown(3, "@hourly"))
+                .set_task_id(7)
+                .set_name("report")
+                .add_label("team", "billing");
+            let task = task_builder.spawn(|_| create_default_delay_task_handler())?;
+
+            let mut record = TaskRecord::from(&task);
+            record.remaining_count = Some(2);
+            record.last_fire_time = Some(get_timestamp());
+
+            FileJobStore::new(&path, *format)?.save(&record)?;
+
+            let job_store = FileJobStore::new(&path, *format)?;
+            assert_eq!(job_store.load()?, vec![record.clone()]);
+
+            let task = record.restore(|_| create_default_delay_task_handler())?;
+            assert_eq!(task.get_remaining_count(), Some(2));
+            assert_eq!(task.get_name(), Some("report"));
+
+            job_store.remove(7)?;
+            assert!(FileJobStore::new(&path, *format)?.load()?.is_empty());
+
+            std::fs::remove_file(&path)?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missed_fire() -> anyhow::Result<()> {
+        use super::TaskRecord;
+        use crate::prelude::*;
+
+        let task = TaskBuilder::default()
+            .set_frequency(Frequency::Repeated("0 0 * * * * *"))
+            .set_task_id(3)
+            .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Utc)
+            .spawn(|_| create_default_delay_task_handler())?;
+        let mut record = TaskRecord::from(&task);
+
+        // 2021-01-01T00:00:00Z
+        let last_fire_time = 1_609_459_200;
+        assert!(!record.missed_fire(last_fire_time + 7200));
+
+        record.last_fire_time = Some(last_fire_time);
+        assert!(!record.missed_fire(last_fire_time + 1800));
+        assert!(!record.missed_fire(last_fire_time + 3600));
+        assert!(record.missed_fire(last_fire_time + 3601));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite-store")]
+    fn test_sqlite_job_store() -> anyhow::Result<()> {
+        use super::{JobStore, SqliteJobStore, TaskRecord};
+        use crate::prelude::*;
+
+        let task = TaskBuilder::default()
+            .set_frequency(Frequency::Repeated("@daily"))
+            .set_task_id(9)
+            .set_schedule_iterator_time_zone(ScheduleIteratorTimeZone::Utc)
+            .spawn(|_| create_default_delay_task_handler())?;
+        let mut record = TaskRecord::from(&task);
+
+        let job_store = SqliteJobStore::open_in_memory()?;
+        job_store.save(&record)?;
+
+        record.last_fire_time = Some(get_timestamp());
+        job_store.save(&record)?;
+        assert_eq!(job_store.get(9)?, Some(record.clone()));
+        assert_eq!(job_store.load()?, vec![record]);
+
+        job_store.remove(9)?;
+        assert!(job_store.load()?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+//! metrics
+//! It is a module that collects the metrics of `DelayTimer`,
+//! and renders them in the Prometheus text exposition format.
+use crate::prelude::*;
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::Arc;
+
+use crate::entity::SharedTaskFlagMap;
+
+// The upper bounds of histogram buckets, in seconds.
+pub(crate) const HISTOGRAM_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+// The histogram with fixed buckets, the observed values are in microseconds.
+#[derive(Debug, Default)]
+pub(crate) struct Histogram {
+    buckets: [AtomicU64; HISTOGRAM_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    pub(crate) fn observe(&self, micros: u64) {
+        let seconds = micros as f64 / 1_000_000.0;
+        if let Some(index) = HISTOGRAM_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[index].fetch_add(1, Relaxed);
+        }
+        self.count.fetch_add(1, Relaxed);
+        self.sum_micros.fetch_add(micros, Relaxed);
+    }
+
+    // Render the cumulative buckets, the sum and the count.
+    fn render(&self, output: &mut String, name: &str, task_id: u64) {
+        let mut cumulative_count = 0;
+        for (bound, bucket) in HISTOGRAM_BUCKETS.iter().zip(self.buckets.iter()) {
+            cumulative_count += bucket.load(Relaxed);
+            let _ = writeln!(
+                output,
+                "{}_bucket{{task_id=\"{}\",le=\"{}\"}} {}",
+                name, task_id, bound, cumulative_count
+            );
+        }
+
+        let count = self.count.load(Relaxed);
+        let _ = writeln!(
+            output,
+            "{}_bucket{{task_id=\"{}\",le=\"+Inf\"}} {}",
+            name, task_id, count
+        );
+        let _ = writeln!(
+            output,
+            "{}_sum{{task_id=\"{}\"}} {}",
+            name,
+            task_id,
+            self.sum_micros.load(Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(
+            output,
+            "{}_count{{task_id=\"{}\"}} {}",
+            name, task_id, count
+        );
+    }
+}
+
+// Get a counter of `TaskMetrics`.
+type TaskCounter = fn(&TaskMetrics) -> &AtomicU64;
+
+// The metrics of a task.
+#[derive(Debug, Default)]
+pub(crate) struct TaskMetrics {
+    started: AtomicU64,
+    completed: AtomicU64,
+    cancelled: AtomicU64,
+    timed_out: AtomicU64,
+    failed: AtomicU64,
+    run_duration: Histogram,
+    scheduling_lag: Histogram,
+}
+
+// The metrics shared by the workers of `DelayTimer`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Metrics {
+    tasks: Arc<DashMap<u64, Arc<TaskMetrics>>>,
+    // Number of events handled by `EventHandle`.
+    event_num: Arc<AtomicU64>,
+    // Number of events that failed to be handled.
+    event_error_num: Arc<AtomicU64>,
+    // Number of events waiting in the event-channel, when the last event is handled.
+    event_channel_depth: Arc<AtomicU64>,
+    // Number of running instances waiting for their deadline in `RecyclingBins`.
+    recycle_unit_num: Arc<AtomicU64>,
+}
+
+impl Metrics {
+    fn task(&self, task_id: u64) -> Arc<TaskMetrics> {
+        self.tasks.entry(task_id).or_default().value().clone()
+    }
+
+    pub(crate) fn observe_instance_started(&self, task_id: u64, scheduling_lag_micros: u64) {
+        let task_metrics = self.task(task_id);
+        task_metrics.started.fetch_add(1, Relaxed);
+        task_metrics.scheduling_lag.observe(scheduling_lag_micros);
+    }
+
+    pub(crate) fn observe_instance_finished(
+        &self,
+        task_id: u64,
+        state: InstanceState,
+        run_duration_micros: Option<u64>,
+    ) {
+        let task_metrics = self.task(task_id);
+        let counter = match state {
+            state::instance::COMPLETED => &task_metrics.completed,
+            state::instance::CANCELLED => &task_metrics.cancelled,
+            state::instance::TIMEOUT => &task_metrics.timed_out,
+            state::instance::FAILED => &task_metrics.failed,
+            _ => return,
+        };
+        counter.fetch_add(1, Relaxed);
+
+        if let Some(run_duration_micros) = run_duration_micros {
+            task_metrics.run_duration.observe(run_duration_micros);
+        }
+    }
+
+    pub(crate) fn remove_task(&self, task_id: u64) {
+        self.tasks.remove(&task_id);
+    }
+
+    pub(crate) fn observe_event(&self, event_channel_depth: usize, is_error: bool) {
+        self.event_num.fetch_add(1, Relaxed);
+        if is_error {
+            self.event_error_num.fetch_add(1, Relaxed);
+        }
+        self.event_channel_depth
+            .store(event_channel_depth as u64, Relaxed);
+    }
+
+    pub(crate) fn inc_recycle_unit_num(&self) {
+        self.recycle_unit_num.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn dec_recycle_unit_num(&self) {
+        self.recycle_unit_num.fetch_sub(1, Relaxed);
+    }
+
+    // Render all metrics in the Prometheus text exposition format.
+    pub(crate) fn render(&self, task_flag_map: &SharedTaskFlagMap) -> String {
+        let mut output = String::new();
+
+        // The exhausted task has left the wheel, but its last instances may be still running.
+        let mut task_num = 0;
+        let mut running_instances: Vec<(u64, u64)> = Vec::new();
+        for task_mark in task_flag_map.iter() {
+            if !task_mark.value().is_reserved() {
+                running_instances.push((
+                    *task_mark.key(),
+                    task_mark.value().get_parallel_runable_num(),
+                ));
+            }
+            if task_mark.value().is_live() {
+                task_num += 1;
+            }
+        }
+        running_instances.sort_unstable();
+
+        let mut tasks: Vec<(u64, Arc<TaskMetrics>)> = self
+            .tasks
+            .iter()
+            .map(|task_metrics| (*task_metrics.key(), task_metrics.value().clone()))
+            .collect();
+        tasks.sort_unstable_by_key(|(task_id, _)| *task_id);
+
+        render_header(
+            &mut output,
+            "delay_timer_tasks",
+            "gauge",
+            "Number of tasks in the timer wheel.",
+        );
+        let _ = writeln!(output, "delay_timer_tasks {}", task_num);
+
+        render_header(
+            &mut output,
+            "delay_timer_running_instances",
+            "gauge",
+            "Number of running instances of the task.",
+        );
+        for (task_id, running_instance_num) in running_instances.iter() {
+            let _ = writeln!(
+                output,
+                "delay_timer_running_instances{{task_id=\"{}\"}} {}",
+                task_id, running_instance_num
+            );
+        }
+
+        let counters: [(&str, &str, TaskCounter); 5] = [
+            (
+                "delay_timer_instances_started_total",
+                "Number of started instances of the task.",
+                |task_metrics| &task_metrics.started,
+            ),
+            (
+                "delay_timer_instances_completed_total",
+                "Number of completed instances of the task.",
+                |task_metrics| &task_metrics.completed,
+            ),
+            (
+                "delay_timer_instances_cancelled_total",
+                "Number of cancelled instances of the task.",
+                |task_metrics| &task_metrics.cancelled,
+            ),
+            (
+                "delay_timer_instances_timed_out_total",
+                "Number of timed out instances of the task.",
+                |task_metrics| &task_metrics.timed_out,
+            ),
+            (
+                "delay_timer_instances_failed_total",
+                "Number of failed instances of the task.",
+                |task_metrics| &task_metrics.failed,
+            ),
+        ];
+        for (name, help, counter) in counters.iter() {
+            render_header(&mut output, name, "counter", help);
+            for (task_id, task_metrics) in tasks.iter() {
+                let _ = writeln!(
+                    output,
+                    "{}{{task_id=\"{}\"}} {}",
+                    name,
+                    task_id,
+                    counter(task_metrics).load(Relaxed)
+                );
+            }
+        }
+
+        render_header(
+            &mut output,
+            "delay_timer_run_duration_seconds",
+            "histogram",
+            "Run duration of the finished instances of the task.",
+        );
+        for (task_id, task_metrics) in tasks.iter() {
+            task_metrics.run_duration.render(
+                &mut output,
+                "delay_timer_run_duration_seconds",
+                *task_id,
+            );
+        }
+
+        render_header(
+            &mut output,
+            "delay_timer_scheduling_lag_seconds",
+            "histogram",
+            "Actual start time minus intended fire time of the instances of the task.",
+        );
+        for (task_id, task_metrics) in tasks.iter() {
+            task_metrics.scheduling_lag.render(
+                &mut output,
+                "delay_timer_scheduling_lag_seconds",
+                *task_id,
+            );
+        }
+
+        let timer_metrics = [
+            (
+                "delay_timer_events_total",
+                "counter",
+                "Number of events handled by the event handle.",
+                &self.event_num,
+            ),
+            (
+                "delay_timer_event_errors_total",
+                "counter",
+                "Number of events that failed to be handled.",
+                &self.event_error_num,
+            ),
+            (
+                "delay_timer_event_channel_depth",
+                "gauge",
+                "Number of events waiting in the event channel.",
+                &self.event_channel_depth,
+            ),
+            (
+                "delay_timer_recycle_units",
+                "gauge",
+                "Number of running instances waiting for their deadline.",
+                &self.recycle_unit_num,
+            ),
+        ];
+        for (name, kind, help, value) in timer_metrics.iter() {
+            render_header(&mut output, name, kind, help);
+            let _ = writeln!(output, "{} {}", name, value.load(Relaxed));
+        }
+
+        output
+    }
+}
+
+fn render_header(output: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, kind);
+}
+
+mod tests {
+
+    #[test]
+    fn test_metrics_render() {
+        use super::Metrics;
+        use crate::prelude::*;
+        use std::sync::Arc;
+
+        let metrics = Metrics::default();
+        metrics.observe_instance_started(1, 20_000);
+        metrics.observe_instance_finished(1, instance::COMPLETED, Some(2_000_000));
+        metrics.observe_instance_started(1, 300_000);
+        metrics.observe_instance_finished(1, instance::TIMEOUT, None);
+        metrics.observe_instance_finished(1, instance::FAILED, None);
+        metrics.observe_event(3, true);
+
+        let output = metrics.render(&Arc::new(DashMap::new()));
+
+        assert!(output.contains("# TYPE delay_timer_run_duration_seconds histogram\n"));
+        assert!(output.contains("delay_timer_instances_started_total{task_id=\"1\"} 2\n"));
+        assert!(output.contains("delay_timer_instances_failed_total{task_id=\"1\"} 1\n"));
+        assert!(output.contains("delay_timer_instances_completed_total{task_id=\"1\"} 1\n"));
+        assert!(output.contains("delay_timer_instances_timed_out_total{task_id=\"1\"} 1\n"));
+        assert!(
+            output.contains("delay_timer_run_duration_seconds_bucket{task_id=\"1\",le=\"1\"} 0\n")
+        );
+        assert!(output
+            .contains("delay_timer_run_duration_seconds_bucket{task_id=\"1\",le=\"2.5\"} 1\n"));
+        assert!(output.contains("delay_timer_run_duration_seconds_sum{task_id=\"1\"} 2\n"));
+        assert!(output
+            .contains("delay_timer_scheduling_lag_seconds_bucket{task_id=\"1\",le=\"0.025\"} 1\n"));
+        assert!(output.contains("delay_timer_scheduling_lag_seconds_count{task_id=\"1\"} 2\n"));
+        assert!(output.contains("delay_timer_event_errors_total 1\n"));
+        assert!(output.contains("delay_timer_event_channel_depth 3\n"));
+        assert!(output.contains("delay_timer_tasks 0\n"));
+    }
+}
@@ -1,11 +1,36 @@
 //! utils is a tool module that provides easy shell-command parsing,
 //! and functions that generate closures.
 pub mod convenience;
+pub mod crontab;
+pub mod describe;
+pub mod history;
+pub(crate) mod metrics;
+pub mod output;
+pub mod overflow;
 pub mod parse;
+pub mod process;
+pub mod resource;
+pub mod template;
 
 cfg_status_report!(
     pub mod status_report;
 );
 
+cfg_task_spec!(
+    pub mod task_spec;
+);
+
+cfg_job_store!(
+    pub mod job_store;
+);
+
+cfg_config!(
+    pub mod config;
+);
+
+cfg_admin_api!(
+    pub mod admin_api;
+);
+
 pub use convenience::functions;
-pub use parse::shell_command::parse_and_run;
+pub use parse::shell_command::{parse_and_run, wait_pipeline};
@@ -0,0 +1,403 @@
+//! output
+//! It is a module that captures the output of process tasks while they're running.
+//!
+//! Every line of stdout and stderr is streamed to the subscribers of `TaskInstance`,
+//! and written to the log file of the instance if `DelayTimerBuilder::output_log` is set.
+//! Only the tail of output is kept in memory for the `FinishOutput`.
+use crate::prelude::*;
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// The default maximum bytes of stdout and stderr kept in the `FinishOutput`.
+pub const DEFAULT_OUTPUT_TAIL_LIMIT: usize = 1024 * 1024;
+
+/// The default maximum size of a log file before it's rotated.
+pub const DEFAULT_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// The default number of rotated log files kept for an instance.
+pub const DEFAULT_ROTATED_LOG_FILES: usize = 3;
+
+/// The default number of instances of a task whose logs are kept.
+pub const DEFAULT_INSTANCE_LOGS: usize = 10;
+
+/// The number of lines buffered for every subscriber of output,
+/// the subscriber is closed when it can't keep up.
+pub const OUTPUT_SUBSCRIBER_CAPACITY: usize = 1024;
+
+// The line longer than it is split, so a process without newlines can't exhaust the memory.
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// The stream that the output comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputSource {
+    /// The standard output of process.
+    Stdout,
+    /// The standard error of process.
+    Stderr,
+}
+
+/// A line of output of the process task, without the line terminator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    /// The stream that the line comes from.
+    pub source: OutputSource,
+    /// The content of line, the invalid UTF-8 sequences are replaced.
+    pub line: String,
+}
+
+/// The config of the log files that the output of process tasks is written to.
+///
+/// Every task running instance writes to `{dir}/{task_id}/{record_id}.log`,
+/// the file is rotated to `{record_id}.log.1`, `{record_id}.log.2` ... when it exceeds the maximum size,
+/// and only the logs of the latest instances of every task are kept.
+#[derive(Debug, Clone)]
+pub struct OutputLogConfig {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_rotated_files: usize,
+    max_instance_logs: usize,
+}
+
+impl OutputLogConfig {
+    /// Write the logs into the directory, it's created if it doesn't exist.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> OutputLogConfig {
+        OutputLogConfig {
+            dir: dir.into(),
+            max_file_size: DEFAULT_LOG_FILE_SIZE,
+            max_rotated_files: DEFAULT_ROTATED_LOG_FILES,
+            max_instance_logs: DEFAULT_INSTANCE_LOGS,
+        }
+    }
+
+    /// Set the maximum size of a log file before it's rotated.
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Set the number of rotated log files kept for an instance,
+    /// the log file is truncated instead of rotated if it's zero.
+    pub fn max_rotated_files(mut self, max_rotated_files: usize) -> Self {
+        self.max_rotated_files = max_rotated_files;
+        self
+    }
+
+    /// Set the number of instances of a task whose logs are kept.
+    pub fn max_instance_logs(mut self, max_instance_logs: usize) -> Self {
+        self.max_instance_logs = max_instance_logs;
+        self
+    }
+
+    /// The path of the log file of the task running instance.
+    pub fn log_path(&self, task_id: u64, record_id: i64) -> PathBuf {
+        self.dir
+            .join(task_id.to_string())
+            .join(format!("{}.log", record_id))
+    }
+
+    // Remove the logs of the old instances, the record ids increase with time.
+    fn remove_old_logs(&self, task_id: u64) -> io::Result<()> {
+        let task_dir = self.dir.join(task_id.to_string());
+        let mut logs = Vec::new();
+        for entry in fs::read_dir(&task_dir)? {
+            let path = entry?.path();
+            let record_id = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.split('.').next())
+                .and_then(|record_id| record_id.parse::<i64>().ok());
+            if let Some(record_id) = record_id {
+                logs.push((record_id, path));
+            }
+        }
+
+        let mut record_ids: Vec<i64> = logs.iter().map(|(record_id, _)| *record_id).collect();
+        record_ids.sort_unstable_by(|a, b| b.cmp(a));
+        record_ids.dedup();
+        let oldest_kept = match record_ids.get(self.max_instance_logs.max(1) - 1) {
+            Some(record_id) => *record_id,
+            None => return Ok(()),
+        };
+
+        for (record_id, path) in logs {
+            if record_id < oldest_kept {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The log file of a task running instance.
+#[derive(Debug)]
+struct OutputLog {
+    config: Arc<OutputLogConfig>,
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl OutputLog {
+    fn open(config: Arc<OutputLogConfig>, task_id: u64, record_id: i64) -> io::Result<OutputLog> {
+        let path = config.log_path(task_id, record_id);
+        if let Some(task_dir) = path.parent() {
+            fs::create_dir_all(task_dir)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        config.remove_old_logs(task_id)?;
+
+        Ok(OutputLog {
+            config,
+            path,
+            file,
+            size,
+        })
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.config.max_file_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line)?;
+        self.file.write_all(b"\n")?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let max_rotated_files = self.config.max_rotated_files;
+        if max_rotated_files == 0 {
+            self.file.set_len(0)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        for index in (1..max_rotated_files).rev() {
+            match fs::rename(self.rotated_path(index), self.rotated_path(index + 1)) {
+                Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+}
+
+/// The capture of output of a task running instance.
+#[derive(Debug)]
+pub(crate) struct OutputCapture {
+    // The maximum bytes of stdout and stderr kept, the head of output is discarded.
+    tail_limit: usize,
+    // The instance that the lines are published to.
+    instance: Option<Instance>,
+    log: Option<Mutex<OutputLog>>,
+}
+
+impl Default for OutputCapture {
+    fn default() -> Self {
+        OutputCapture {
+            tail_limit: DEFAULT_OUTPUT_TAIL_LIMIT,
+            instance: None,
+            log: None,
+        }
+    }
+}
+
+impl OutputCapture {
+    /// The capture that publishes the output to the instance of context, and writes it to the log.
+    pub(crate) fn new(context: &TaskContext) -> OutputCapture {
+        let log = context.output_log.as_ref().and_then(|config| {
+            OutputLog::open(config.clone(), context.task_id, context.record_id)
+                .map_err(|e| error!(" `OutputCapture` : {}", e))
+                .ok()
+        });
+
+        OutputCapture {
+            instance: context.instance.clone(),
+            log: log.map(Mutex::new),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn set_tail_limit(&mut self, tail_limit: usize) -> &mut Self {
+        self.tail_limit = tail_limit;
+        self
+    }
+
+    /// Read the stream to the end line by line, the tail of it is returned.
+    pub(crate) fn read<R: Read>(&self, source: OutputSource, mut reader: R) -> io::Result<Vec<u8>> {
+        let mut tail = Vec::new();
+        let mut line = Vec::new();
+        let mut buffer = [0; 8192];
+        loop {
+            let size = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(size) => size,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            let mut rest = &buffer[..size];
+            tail.extend_from_slice(rest);
+            if tail.len() > self.tail_limit.saturating_mul(2).max(buffer.len()) {
+                self.truncate_tail(&mut tail);
+            }
+
+            while let Some(index) = rest.iter().position(|&byte| byte == b'\n') {
+                line.extend_from_slice(&rest[..index]);
+                self.capture_line(source, &line);
+                line.clear();
+                rest = &rest[index + 1..];
+            }
+            line.extend_from_slice(rest);
+            if line.len() >= MAX_LINE_LEN {
+                self.capture_line(source, &line);
+                line.clear();
+            }
+        }
+
+        if !line.is_empty() {
+            self.capture_line(source, &line);
+        }
+        self.truncate_tail(&mut tail);
+        Ok(tail)
+    }
+
+    /// Discard the head of output beyond the tail limit.
+    pub(crate) fn truncate_tail(&self, output: &mut Vec<u8>) {
+        if output.len() > self.tail_limit {
+            output.drain(..output.len() - self.tail_limit);
+        }
+    }
+
+    fn capture_line(&self, source: OutputSource, line: &[u8]) {
+        let line = match line.split_last() {
+            Some((b'\r', line)) => line,
+            _ => line,
+        };
+
+        if let Some(log) = self.log.as_ref() {
+            if let Ok(mut log) = log.lock() {
+                log.write_line(line)
+                    .unwrap_or_else(|e| error!(" `OutputCapture` : {}", e));
+            }
+        }
+
+        if let Some(instance) = self.instance.as_ref() {
+            instance.publish_output(OutputLine {
+                source,
+                line: String::from_utf8_lossy(line).into_owned(),
+            });
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_output_capture() -> std::io::Result<()> {
+        use super::{OutputCapture, OutputLine, OutputSource};
+        use crate::prelude::*;
+
+        let instance = Instance::default();
+        let receiver = instance.subscribe_output();
+        let mut context = TaskContext::default();
+        context.instance(instance.clone());
+
+        let mut output_capture = OutputCapture::new(&context);
+        output_capture.set_tail_limit(8);
+        let tail = output_capture.read(OutputSource::Stderr, &b"first\r\nsecond\nthird"[..])?;
+        assert_eq!(tail, b"nd\nthird");
+
+        instance.notify_cancel_finish(state::instance::COMPLETED);
+        let lines: Vec<OutputLine> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        let lines: Vec<&str> = lines
+            .iter()
+            .inspect(|output_line| assert_eq!(output_line.source, OutputSource::Stderr))
+            .map(|output_line| output_line.line.as_str())
+            .collect();
+        assert_eq!(lines, ["first", "second", "third"]);
+        assert!(receiver.is_closed());
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_subscriber_capacity() {
+        use super::{OutputLine, OutputSource, OUTPUT_SUBSCRIBER_CAPACITY};
+        use crate::prelude::*;
+
+        let instance = Instance::default();
+        let receiver = instance.subscribe_output();
+        for index in 0..=OUTPUT_SUBSCRIBER_CAPACITY {
+            instance.publish_output(OutputLine {
+                source: OutputSource::Stdout,
+                line: index.to_string(),
+            });
+        }
+
+        // The lines buffered before the subscriber falls behind are still received.
+        assert!(receiver.is_closed());
+        let lines: Vec<OutputLine> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert_eq!(lines.len(), OUTPUT_SUBSCRIBER_CAPACITY);
+        assert_eq!(lines[0].line, "0");
+    }
+
+    #[test]
+    fn test_output_log() -> std::io::Result<()> {
+        use super::{OutputCapture, OutputLogConfig, OutputSource};
+        use crate::prelude::*;
+        use std::sync::Arc;
+        use std::{env, fs, process};
+
+        let dir = env::temp_dir().join(format!("delay_timer_output_{}", process::id()));
+        let output_log = Arc::new(
+            OutputLogConfig::new(&dir)
+                .max_file_size(8)
+                .max_rotated_files(1)
+                .max_instance_logs(2),
+        );
+
+        for record_id in 1..=3 {
+            let mut context = TaskContext::default();
+            context
+                .task_id(7)
+                .record_id(record_id)
+                .output_log(output_log.clone());
+            OutputCapture::new(&context)
+                .read(OutputSource::Stdout, &b"line1\nline2\nline3\n"[..])?;
+        }
+
+        assert!(!output_log.log_path(7, 1).exists());
+        assert!(output_log.log_path(7, 2).exists());
+        let log_path = output_log.log_path(7, 3);
+        assert_eq!(fs::read_to_string(&log_path)?, "line3\n");
+        assert_eq!(
+            fs::read_to_string(log_path.with_extension("log.1"))?,
+            "line2\n"
+        );
+        assert!(!log_path.with_extension("log.2").exists());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
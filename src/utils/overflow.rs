@@ -0,0 +1,149 @@
+//! overflow
+//! It is a module that configures the capacity of the internal channels,
+//! and how the observational channels deal with overflow.
+use crate::prelude::*;
+
+use smol::channel::{bounded, unbounded, SendError, TrySendError};
+
+/// The policy of an observational channel (status-report, task-instances-chain) when it is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest message in the channel, and send the new one.
+    DropOldest,
+    /// Drop the new message.
+    DropNewest,
+    /// Wait until the channel has space, the sender is blocked.
+    #[default]
+    Block,
+}
+
+/// The capacity and overflow policy of a channel, the channel is unbounded without capacity.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ChannelConfig {
+    pub(crate) capacity: Option<usize>,
+    pub(crate) overflow_policy: OverflowPolicy,
+}
+
+impl ChannelConfig {
+    pub(crate) fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        ChannelConfig {
+            capacity: Some(capacity),
+            overflow_policy,
+        }
+    }
+
+    pub(crate) fn channel<T>(&self) -> (AsyncSender<T>, AsyncReceiver<T>) {
+        match self.capacity {
+            Some(capacity) => bounded(capacity),
+            None => unbounded(),
+        }
+    }
+
+    pub(crate) fn overflow_channel<T>(&self) -> (OverflowSender<T>, AsyncReceiver<T>) {
+        let (sender, receiver) = self.channel();
+
+        (
+            OverflowSender::new(sender, receiver.clone(), self.overflow_policy),
+            receiver,
+        )
+    }
+}
+
+/// The sender of an observational channel, it applies the `OverflowPolicy` when the channel is full.
+#[derive(Debug, Clone)]
+pub(crate) struct OverflowSender<T> {
+    sender: AsyncSender<T>,
+    // Only `DropOldest` needs the receiver to pop the oldest message,
+    // it isn't counted, so the channel is closed after the other receivers are dropped.
+    receiver: Option<AsyncReceiver<T>>,
+    overflow_policy: OverflowPolicy,
+}
+
+impl<T> OverflowSender<T> {
+    pub(crate) fn new(
+        sender: AsyncSender<T>,
+        receiver: AsyncReceiver<T>,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
+        let receiver = if overflow_policy == OverflowPolicy::DropOldest {
+            Some(receiver)
+        } else {
+            None
+        };
+
+        OverflowSender {
+            sender,
+            receiver,
+            overflow_policy,
+        }
+    }
+
+    pub(crate) async fn send(&self, mut value: T) -> Result<(), SendError<T>> {
+        if self.overflow_policy == OverflowPolicy::Block {
+            return self.sender.send(value).await;
+        }
+
+        if self.receiver.is_some() && self.sender.receiver_count() <= 1 {
+            self.sender.close();
+            return Err(SendError(value));
+        }
+
+        loop {
+            match self.sender.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(value)) => return Err(SendError(value)),
+                Err(TrySendError::Full(returned_value)) => {
+                    if let Some(receiver) = self.receiver.as_ref() {
+                        receiver.try_recv().ok();
+                        value = returned_value;
+                        continue;
+                    }
+
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_overflow_policy() {
+        use super::{ChannelConfig, OverflowPolicy};
+        use smol::future::block_on;
+
+        let (sender, receiver) =
+            ChannelConfig::new(2, OverflowPolicy::DropOldest).overflow_channel::<i32>();
+        block_on(async {
+            for i in 0..5 {
+                sender.send(i).await.unwrap();
+            }
+        });
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Ok(4));
+
+        // The channel is closed after the receiver is dropped.
+        drop(receiver);
+        assert!(block_on(sender.send(5)).is_err());
+
+        let (sender, receiver) =
+            ChannelConfig::new(2, OverflowPolicy::DropNewest).overflow_channel::<i32>();
+        block_on(async {
+            for i in 0..5 {
+                sender.send(i).await.unwrap();
+            }
+        });
+        assert_eq!(receiver.try_recv(), Ok(0));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert!(receiver.try_recv().is_err());
+
+        let (sender, receiver) = ChannelConfig::default().overflow_channel::<i32>();
+        block_on(async {
+            for i in 0..5 {
+                sender.send(i).await.unwrap();
+            }
+        });
+        assert_eq!(receiver.len(), 5);
+    }
+}
@@ -4,25 +4,60 @@
 /// Collection of functions related to shell commands and processes.
 pub mod shell_command {
     use crate::prelude::*;
-    use anyhow::*;
+    use crate::utils::output::{OutputCapture, OutputSource};
+    use crate::utils::resource::{wait_resource_usage, ResourceLimits, ResourceUsage};
+    use anyhow::{anyhow, Context, Result};
 
     use async_trait::async_trait;
 
     use smol::process::{Child as SmolChild, Command as SmolCommand};
 
-    use std::collections::LinkedList;
+    use std::collections::{HashMap, LinkedList};
     use std::convert::AsRef;
+    use std::env;
     use std::ffi::OsStr;
     use std::fs::{File, OpenOptions};
+    use std::io;
     use std::iter::Iterator;
     use std::mem;
     use std::ops::{Deref, DerefMut};
-    use std::path::Path;
-    use std::process::{Child as StdChild, Command, Output, Stdio};
+    use std::path::{Path, PathBuf};
+    use std::process::{Child as StdChild, Command, ExitStatus, Output, Stdio};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[cfg(unix)]
+    use smol::process::unix::CommandExt as _;
+    #[cfg(unix)]
+    use std::os::unix::process::CommandExt as _;
+
+    use syntax::{CommandList, Connector, FileMode, Redirect, SimpleCommand, Word, WordPart};
+
+    /// One end of the connected sockets, that's used as the stdin, stdout or stderr of child.
+    #[cfg(unix)]
+    pub(crate) type PipeStream = std::os::unix::net::UnixStream;
+    /// The sockets are only used on unix.
+    #[cfg(not(unix))]
+    pub(crate) type PipeStream = File;
 
     /// The linkedlist of ChildGuard.
     pub type ChildGuardList<T> = LinkedList<ChildGuard<T>>;
 
+    /// The output of pipeline, it's the output of the last process with the exit statuses of all stages.
+    ///
+    /// The single process run by `ProcessTaskBuilder` is a pipeline of one stage.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct PipelineOutput {
+        /// The output of the last process, the status is the status of pipeline.
+        pub output: Output,
+        /// The exit statuses of all stages of the pipeline in order.
+        pub stage_statuses: Vec<ExitStatus>,
+        /// The resources used by all stages of the pipeline in order,
+        /// they're `None` if the usage isn't collected (it's only supported on linux).
+        pub stage_resource_usages: Vec<Option<ResourceUsage>>,
+    }
+
     macro_rules! impl_command_unify{
         ($($command:ty => $child:ty),+) => {
             $(impl CommandUnify<$child> for $command {
@@ -38,6 +73,30 @@ pub mod shell_command {
                     self
                 }
 
+                fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+                where
+                    K: AsRef<OsStr>,
+                    V: AsRef<OsStr>,
+                {
+                    self.env(key, val);
+                    self
+                }
+
+                fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+                    self.env_remove(key);
+                    self
+                }
+
+                fn env_clear(&mut self) -> &mut Self {
+                    self.env_clear();
+                    self
+                }
+
+                fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+                    self.current_dir(dir);
+                    self
+                }
+
                 fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
                     self.stdin(cfg);
                     self
@@ -53,6 +112,29 @@ pub mod shell_command {
                     self
                 }
 
+                fn set_resource_limits(&mut self, resource_limits: ResourceLimits) -> &mut Self {
+                    #[cfg(target_os = "linux")]
+                    // Safety: the closure only calls async-signal-safe functions.
+                    unsafe {
+                        self.pre_exec(move || resource_limits.apply());
+                    }
+                    #[cfg(not(target_os = "linux"))]
+                    let _ = resource_limits;
+                    self
+                }
+
+                fn isolate_process_group(&mut self) -> &mut Self {
+                    #[cfg(unix)]
+                    {
+                        let parent_id = std::process::id();
+                        // Safety: the closure only calls async-signal-safe functions.
+                        unsafe {
+                            self.pre_exec(move || isolate_process_group(parent_id));
+                        }
+                    }
+                    self
+                }
+
                 fn spawn(&mut self) -> AnyResult<$child> {
                     Ok(self.spawn()?)
                 }
@@ -73,6 +155,21 @@ pub mod shell_command {
             I: IntoIterator<Item = S>,
             S: AsRef<OsStr>;
 
+        /// Inserts or updates an environment variable mapping.
+        fn env<K, V>(&mut self, key: K, val: V) -> &mut Self
+        where
+            K: AsRef<OsStr>,
+            V: AsRef<OsStr>;
+
+        /// Removes an environment variable mapping.
+        fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self;
+
+        /// Clears the entire environment map for the child process.
+        fn env_clear(&mut self) -> &mut Self;
+
+        /// Sets the working directory for the child process.
+        fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self;
+
         /// Configuration for the child process's standard input (stdin) handle.
         fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self;
 
@@ -82,10 +179,43 @@ pub mod shell_command {
         /// Configuration for the child process's standard error (stderr) handle.
         fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self;
 
+        /// Applies the limits of resources to the child (linux only).
+        fn set_resource_limits(&mut self, resource_limits: ResourceLimits) -> &mut Self;
+
+        /// Spawns the child as the leader of a new process group, so the whole group can be killed (unix only),
+        /// and the child is killed if the thread that spawns it exits (linux only).
+        fn isolate_process_group(&mut self) -> &mut Self;
+
         /// Executes the command as a child process, returning a handle to it.
         fn spawn(&mut self) -> AnyResult<Child>;
     }
 
+    // It runs in the child after `fork`, only the async-signal-safe functions can be called.
+    #[cfg(unix)]
+    fn isolate_process_group(parent_id: u32) -> io::Result<()> {
+        // Safety: these functions have no memory effects.
+        unsafe {
+            if libc::setpgid(0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            #[cfg(target_os = "linux")]
+            {
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                // The parent has exited before the signal is set.
+                if libc::getppid() as u32 != parent_id {
+                    return Err(io::Error::from(io::ErrorKind::BrokenPipe));
+                }
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        let _ = parent_id;
+        Ok(())
+    }
+
     impl_command_unify!(Command => StdChild,SmolCommand => SmolChild);
 
     cfg_tokio_support!(
@@ -98,12 +228,20 @@ pub mod shell_command {
 
     #[async_trait]
     /// Trait abstraction of multiple library process handles.
-    pub trait ChildUnify: Send + Sync {
+    pub trait ChildUnify: Send + Sync + 'static {
         /// Executes the command as a child process, waiting for it to finish and collecting all of its output.
         async fn wait_with_output(self) -> AnyResult<Output>;
+        /// Waits for the child to exit completely, returning the status that it exited with.
+        async fn wait(&mut self) -> AnyResult<ExitStatus>;
         /// Convert stdout to stdio.
         async fn stdout_to_stdio(&mut self) -> Option<Stdio>;
 
+        /// Attempts to collect the exit status of the child if it has already exited.
+        fn try_wait(&mut self) -> AnyResult<Option<ExitStatus>>;
+
+        /// Returns the OS-assigned process identifier of the child.
+        fn id(&self) -> Option<u32>;
+
         /// Kill the process child.
         fn kill(&mut self) -> AnyResult<()>;
     }
@@ -113,10 +251,21 @@ pub mod shell_command {
         async fn wait_with_output(self) -> AnyResult<Output> {
             Ok(self.wait_with_output()?)
         }
+        async fn wait(&mut self) -> AnyResult<ExitStatus> {
+            Ok(self.wait()?)
+        }
         async fn stdout_to_stdio(&mut self) -> Option<Stdio> {
             self.stdout.take().map(Stdio::from)
         }
 
+        fn try_wait(&mut self) -> AnyResult<Option<ExitStatus>> {
+            Ok(self.try_wait()?)
+        }
+
+        fn id(&self) -> Option<u32> {
+            Some(self.id())
+        }
+
         fn kill(&mut self) -> AnyResult<()> {
             Ok(self.kill()?)
         }
@@ -128,6 +277,10 @@ pub mod shell_command {
             Ok(self.output().await?)
         }
 
+        async fn wait(&mut self) -> AnyResult<ExitStatus> {
+            Ok(self.status().await?)
+        }
+
         async fn stdout_to_stdio(&mut self) -> Option<Stdio> {
             if let Some(stdout) = self.stdout.take() {
                 return stdout.into_stdio().await.ok();
@@ -135,6 +288,14 @@ pub mod shell_command {
             None
         }
 
+        fn try_wait(&mut self) -> AnyResult<Option<ExitStatus>> {
+            Ok(self.try_status()?)
+        }
+
+        fn id(&self) -> Option<u32> {
+            Some(self.id())
+        }
+
         fn kill(&mut self) -> AnyResult<()> {
             Ok(self.kill()?)
         }
@@ -147,10 +308,22 @@ pub mod shell_command {
                 Ok(self.wait_with_output().await?)
             }
 
+            async fn wait(&mut self) -> AnyResult<ExitStatus> {
+                Ok(self.wait().await?)
+            }
+
             async fn stdout_to_stdio(&mut self) -> Option<Stdio> {
                 self.stdout.take().map(|s| s.try_into().ok()).flatten()
             }
 
+            fn try_wait(&mut self) -> AnyResult<Option<ExitStatus>> {
+                Ok(self.try_wait()?)
+            }
+
+            fn id(&self) -> Option<u32> {
+                self.id()
+            }
+
             // Attempts to force the child to exit, but does not wait for the request to take effect.
             // On Unix platforms, this is the equivalent to sending a SIGKILL.
             // Note that on Unix platforms it is possible for a zombie process to remain after a kill is sent;
@@ -164,33 +337,237 @@ pub mod shell_command {
     /// Guarding of process handles.
     pub struct ChildGuard<Child: ChildUnify> {
         pub(crate) child: Option<Child>,
+        // The output of the commands that ran before the child in the sequence,
+        // it's the whole output if the child isn't spawned (e.g. `false && echo skipped`).
+        pub(crate) preceding_output: Option<Output>,
+        // The read ends of the stdout and stderr of child, they're read while waiting for the child.
+        // They're `None` on the platforms without unix sockets, the outputs are piped by child itself.
+        pub(crate) stdout_reader: Option<PipeStream>,
+        pub(crate) stderr_reader: Option<PipeStream>,
+        // The outputs are read line by line by the capture, and only the tail is kept.
+        pub(crate) output_capture: Arc<OutputCapture>,
+        // The process group of child receives SIGTERM first,
+        // and it's killed after the period if it's still running.
+        pub(crate) kill_grace_period: Option<Duration>,
+        // The status of pipeline is the last non-zero status of stages, it's set on the last stage.
+        pub(crate) pipefail: bool,
     }
 
     impl<Child: ChildUnify> ChildGuard<Child> {
         pub(crate) fn new(child: Child) -> Self {
             let child = Some(child);
-            Self { child }
+            Self {
+                child,
+                preceding_output: None,
+                stdout_reader: None,
+                stderr_reader: None,
+                output_capture: Arc::default(),
+                kill_grace_period: None,
+                pipefail: false,
+            }
         }
 
-        pub(crate) async fn wait_with_output(mut self) -> AnyResult<Output> {
-            if let Some(child) = self.child.take() {
-                return child.wait_with_output().await;
+        // The guard of commands that have finished.
+        fn finished(output: Output) -> Self {
+            Self {
+                child: None,
+                preceding_output: Some(output),
+                stdout_reader: None,
+                stderr_reader: None,
+                output_capture: Arc::default(),
+                kill_grace_period: None,
+                pipefail: false,
+            }
+        }
+
+        // The usage of resources is only for the child,
+        // it's `None` for the commands that ran before the child in the sequence.
+        pub(crate) async fn wait_with_usage(
+            mut self,
+        ) -> AnyResult<(Output, Option<ResourceUsage>)> {
+            let preceding_output = self.preceding_output.take();
+            if self.child.is_none() {
+                let output =
+                    preceding_output.ok_or_else(|| anyhow!("Without child for waiting."))?;
+                return Ok((output, None));
+            }
+
+            let (mut output, resource_usage) = self.wait_child().await?;
+            if let Some(mut preceding_output) = preceding_output {
+                preceding_output.stdout.append(&mut output.stdout);
+                preceding_output.stderr.append(&mut output.stderr);
+                output.stdout = preceding_output.stdout;
+                output.stderr = preceding_output.stderr;
+                self.output_capture.truncate_tail(&mut output.stdout);
+                self.output_capture.truncate_tail(&mut output.stderr);
+            }
+            Ok((output, resource_usage))
+        }
+
+        // Wait for the child without reading the outputs, they're connected to the next stage.
+        async fn wait_status(&mut self) -> AnyResult<(ExitStatus, Option<ResourceUsage>)> {
+            let child = self
+                .child
+                .as_mut()
+                .ok_or_else(|| anyhow!("Without child for waiting."))?;
+            let exited = wait_exited(child).await?;
+            self.child = None;
+            Ok(exited)
+        }
+
+        // The child is kept by the guard while waiting,
+        // so it's terminated if the waiting is cancelled (e.g. the task is cancelled or timeout).
+        async fn wait_child(&mut self) -> AnyResult<(Output, Option<ResourceUsage>)> {
+            let stdout_reader = self.stdout_reader.take();
+            let stderr_reader = self.stderr_reader.take();
+            if stdout_reader.is_none() && stderr_reader.is_none() {
+                let child = self
+                    .child
+                    .take()
+                    .ok_or_else(|| anyhow!("Without child for waiting."))?;
+                let mut output = child.wait_with_output().await?;
+
+                // The outputs piped by child are captured after it exits.
+                output.stdout = self
+                    .output_capture
+                    .read(OutputSource::Stdout, output.stdout.as_slice())?;
+                output.stderr = self
+                    .output_capture
+                    .read(OutputSource::Stderr, output.stderr.as_slice())?;
+                return Ok((output, None));
             }
 
-            Err(anyhow!("Without child for waiting."))
+            let output_capture = self.output_capture.clone();
+            let child = self
+                .child
+                .as_mut()
+                .ok_or_else(|| anyhow!("Without child for waiting."))?;
+            let (exited, stdout, stderr) = futures::join!(
+                wait_exited(child),
+                read_pipe_stream(stdout_reader, output_capture.clone(), OutputSource::Stdout),
+                read_pipe_stream(stderr_reader, output_capture, OutputSource::Stderr)
+            );
+
+            let (status, resource_usage) = exited?;
+            // The child has exited, it needn't be killed by dropping.
+            self.child = None;
+            let output = Output {
+                status,
+                stdout: stdout?,
+                stderr: stderr?,
+            };
+            Ok((output, resource_usage))
         }
     }
 
+    // Wait for the child to exit, the usage of resources is collected before the child is reaped.
+    async fn wait_exited<Child: ChildUnify>(
+        child: &mut Child,
+    ) -> AnyResult<(ExitStatus, Option<ResourceUsage>)> {
+        let resource_usage = match child.id() {
+            Some(id) if cfg!(target_os = "linux") => unblock_spawn(move || wait_resource_usage(id))
+                .await
+                .unwrap_or_else(|e| {
+                    error!(" `ChildGuard` : {}", e);
+                    None
+                }),
+            _ => None,
+        };
+
+        let status = child.wait().await?;
+        Ok((status, resource_usage))
+    }
+
     impl<Child: ChildUnify> Drop for ChildGuard<Child> {
         fn drop(&mut self) {
-            if let Some(child) = self.child.as_mut() {
-                child
-                    .kill()
-                    .unwrap_or_else(|e| error!(" `ChildGuard` : {}", e));
+            if let Some(child) = self.child.take() {
+                if let Some(mut child) = terminate(child, self.kill_grace_period) {
+                    child
+                        .kill()
+                        .unwrap_or_else(|e| error!(" `ChildGuard` : {}", e));
+                }
             }
         }
     }
 
+    // Terminate the process group of the child, so the descendants of the child are terminated as well.
+    // The group is killed immediately without the grace period, otherwise it receives SIGTERM first,
+    // and it's killed in a new thread after the grace period if it's still running.
+    // The child is returned if it should be killed by the caller.
+    #[cfg(unix)]
+    fn terminate<Child: ChildUnify>(
+        child: Child,
+        kill_grace_period: Option<Duration>,
+    ) -> Option<Child> {
+        // The child is the leader of its process group, the id isn't reused since the child isn't reaped.
+        let process_group = match child.id() {
+            Some(id) => id as libc::pid_t,
+            None => return Some(child),
+        };
+
+        let kill_grace_period = match kill_grace_period {
+            Some(kill_grace_period) => kill_grace_period,
+            None => {
+                signal_process_group(process_group, libc::SIGKILL);
+                return Some(child);
+            }
+        };
+        if !signal_process_group(process_group, libc::SIGTERM) {
+            return Some(child);
+        }
+
+        let deadline = Instant::now() + kill_grace_period;
+        let terminate_result = thread::Builder::new()
+            .name("child_terminator".into())
+            .spawn(move || {
+                let mut child = Some(child);
+                loop {
+                    // The exited child is reaped,
+                    // and the id isn't reused while there are other processes in the group.
+                    if let Some(running_child) = child.as_mut() {
+                        if !matches!(running_child.try_wait(), Ok(None)) {
+                            child = None;
+                        }
+                    }
+                    if !signal_process_group(process_group, 0) {
+                        return;
+                    }
+
+                    let now = Instant::now();
+                    if now >= deadline {
+                        signal_process_group(process_group, libc::SIGKILL);
+                        if let Some(mut child) = child {
+                            child
+                                .kill()
+                                .unwrap_or_else(|e| error!(" `ChildGuard` : {}", e));
+                        }
+                        return;
+                    }
+                    thread::sleep((deadline - now).min(Duration::from_millis(50)));
+                }
+            });
+
+        if let Err(e) = terminate_result {
+            error!(" `ChildGuard` : {}", e);
+        }
+        None
+    }
+
+    #[cfg(not(unix))]
+    fn terminate<Child: ChildUnify>(
+        child: Child,
+        _kill_grace_period: Option<Duration>,
+    ) -> Option<Child> {
+        Some(child)
+    }
+
+    // Send the signal to the process group, it returns false if there's no process in the group.
+    #[cfg(unix)]
+    fn signal_process_group(process_group: libc::pid_t, signal: libc::c_int) -> bool {
+        // Safety: `killpg` has no memory effects.
+        unsafe { libc::killpg(process_group, signal) == 0 }
+    }
+
     impl<Child: ChildUnify> Deref for ChildGuard<Child> {
         type Target = Option<Child>;
         fn deref(&self) -> &Self::Target {
@@ -204,145 +581,1299 @@ pub mod shell_command {
         }
     }
 
-    //that code base on 'build-your-own-shell-rust'. Thanks you Josh Mcguigan.
+    /// Wait for all processes of the pipeline that's returned by `parse_and_run`.
+    ///
+    /// The status of pipeline is the status of the last stage, or the last non-zero status of stages
+    /// if `set -o pipefail` is run before it.
+    pub async fn wait_pipeline<Child: ChildUnify>(
+        mut child_guard_list: ChildGuardList<Child>,
+    ) -> Result<PipelineOutput> {
+        let last_child_guard = child_guard_list
+            .pop_back()
+            .ok_or_else(|| anyhow!("Without child."))?;
+        let pipefail = last_child_guard.pipefail;
+
+        let (stages_exited, last_stage_output) = futures::join!(
+            futures::future::join_all(child_guard_list.iter_mut().map(ChildGuard::wait_status)),
+            last_child_guard.wait_with_usage()
+        );
+        let (mut output, last_resource_usage) = last_stage_output?;
+        let (mut stage_statuses, mut stage_resource_usages): (Vec<_>, Vec<_>) = stages_exited
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+        stage_statuses.push(output.status);
+        stage_resource_usages.push(last_resource_usage);
+
+        if pipefail {
+            if let Some(status) = stage_statuses.iter().rev().find(|status| !status.success()) {
+                output.status = *status;
+            }
+        }
+        Ok(PipelineOutput {
+            output,
+            stage_statuses,
+            stage_resource_usages,
+        })
+    }
 
     /// Generate a list of processes from a string of shell commands.
+    ///
+    /// The input is parsed by a POSIX-style grammar:
+    ///
+    /// * Quoting: `'literal'`, `"with $VAR"` and escaped characters by `\`.
+    /// * Sequences and conditional chains: `a; b`, `a && b`, `a || b`, and newlines as `;`.
+    /// * Pipelines: `a | b`.
+    /// * Redirections: `< file`, `> file`, `>> file`, `2> file`, `2>> file`, `2>&1` and `>&2`.
+    /// * Assignments: `KEY=value command` sets the environment of the command,
+    ///   and the standalone `KEY=value` sets a variable for the following commands.
+    /// * Expansion: `$VAR`, `${VAR}` and `$?`, the expanded text isn't split into fields.
+    /// * Options: `set -o pipefail` and `set +o pipefail`.
+    /// * Working directory: `cd dir` changes the directory of the following commands and redirections,
+    ///   and `cd` without argument changes it to `$HOME`.
+    ///
+    /// Subshells, background jobs, command substitution and globbing are not supported.
+    ///
+    /// Every pipeline before the last one is run to completion before this function returns,
+    /// so the returned future doesn't complete until they exit. Their outputs are
+    /// prepended to the output of the returned processes. Only the last pipeline is returned
+    /// without waiting, and its processes are killed when the list is dropped.
+    ///
+    /// The stderr of every command in pipeline is captured (unix only),
+    /// and only the tail of outputs is kept when the processes are waited.
+    ///
+    /// Every process is the leader of a new process group (unix only).
+    /// On linux the process is killed as well if the thread that spawns it exits,
+    /// so the future should be polled by a long-lived thread (e.g. a worker thread of the async runtime),
+    /// not by a thread that exits before the processes.
     //  There are a lot of system calls that happen when this method is called,
     //  the speed of execution depends on the parsing of the command and the speed of the process fork,
     //  after which it should be split into unblock().
     pub async fn parse_and_run<Child: ChildUnify, Command: CommandUnify<Child>>(
         input: &str,
     ) -> Result<ChildGuardList<Child>> {
-        // Check to see if process_linked_list is also automatically dropped out of scope
-        // by ERROR's early return and an internal kill method is executed.
+        run_command_list::<Child, Command>(syntax::parse(input)?, Arc::default()).await
+    }
+
+    /// Same as `parse_and_run`, the placeholders in the commands are rendered from the context
+    /// (see `render_template`), and the outputs are captured for the task running instance.
+    pub(crate) async fn parse_and_run_in_context<
+        Child: ChildUnify,
+        Command: CommandUnify<Child>,
+    >(
+        input: &str,
+        context: &TaskContext,
+    ) -> Result<ChildGuardList<Child>> {
+        let mut command_list = syntax::parse(input)?;
+        // The commands are rendered after parsing, so the values can't inject shell syntax.
+        let render = |text: &str| render_template(text, context);
+        for (_, pipeline) in command_list.iter_mut() {
+            for simple_command in pipeline.iter_mut() {
+                simple_command.render(&render)?;
+            }
+        }
 
-        let mut process_linked_list: ChildGuardList<Child> = LinkedList::new();
-        // must be peekable so we know when we are on the last command
-        let commands = input.trim().split(" | ").peekable();
-        //if str has >> | > ,after spawn return.
+        let output_capture = Arc::new(OutputCapture::new(context));
+        run_command_list::<Child, Command>(command_list, output_capture).await
+    }
+
+    async fn run_command_list<Child: ChildUnify, Command: CommandUnify<Child>>(
+        command_list: CommandList,
+        output_capture: Arc<OutputCapture>,
+    ) -> Result<ChildGuardList<Child>> {
+        let last_index = command_list.len() - 1;
 
-        let mut _stdio: Stdio;
+        let mut shell = Shell {
+            output_capture,
+            ..Default::default()
+        };
+        let mut preceding_output: Option<Output> = None;
 
-        for mut command in commands {
-            let check_redirect_result = _has_redirect_file(command);
-            if check_redirect_result.is_some() {
-                command = _remove_angle_bracket_command(command)?;
+        for (index, (connector, pipeline)) in command_list.into_iter().enumerate() {
+            let should_run = match connector {
+                Connector::Always => true,
+                Connector::And => shell.last_status == 0,
+                Connector::Or => shell.last_status != 0,
+            };
+            if !should_run {
+                continue;
             }
 
-            let mut parts = command.trim().split_whitespace();
-            let command = parts.next().ok_or_else(|| anyhow!("Without next part"))?;
-            let args = parts;
+            let execution = shell
+                .execute::<Child, Command>(pipeline)
+                .await
+                .or_else(|e| {
+                    // The error of the last pipeline is returned as it is.
+                    if index == last_index {
+                        return Err(e);
+                    }
+
+                    let code = match e.downcast_ref::<io::Error>() {
+                        Some(io_error) if io_error.kind() == io::ErrorKind::NotFound => 127,
+                        _ => 1,
+                    };
+                    Ok(Execution::Finished(Output {
+                        status: exit_status(code),
+                        stdout: Vec::new(),
+                        stderr: format!("{:#}\n", e).into_bytes(),
+                    }))
+                })?;
+
+            let output = match execution {
+                Execution::Spawned(mut process_linked_list) => {
+                    if index == last_index {
+                        let mut last_process = process_linked_list
+                            .pop_back()
+                            .ok_or_else(|| anyhow!("Without child."))?;
+                        last_process.preceding_output = preceding_output.take();
+                        process_linked_list.push_back(last_process);
+                        return Ok(process_linked_list);
+                    }
+                    wait_pipeline(process_linked_list).await?.output
+                }
+                Execution::Finished(output) => output,
+            };
+
+            shell.last_status = output.status.code().unwrap_or(1);
+            preceding_output = Some(match preceding_output.take() {
+                Some(mut preceding_output) => {
+                    preceding_output.status = output.status;
+                    preceding_output.stdout.extend(output.stdout);
+                    preceding_output.stderr.extend(output.stderr);
+                    shell
+                        .output_capture
+                        .truncate_tail(&mut preceding_output.stdout);
+                    shell
+                        .output_capture
+                        .truncate_tail(&mut preceding_output.stderr);
+                    preceding_output
+                }
+                None => output,
+            });
+        }
 
+        // The last pipeline is skipped by the conditional chain.
+        let output = preceding_output.unwrap_or_else(|| Output {
+            status: exit_status(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+        let mut process_linked_list = LinkedList::new();
+        process_linked_list.push_back(ChildGuard::finished(output));
+        Ok(process_linked_list)
+    }
+
+    // The result of executing a pipeline.
+    enum Execution<Child: ChildUnify> {
+        Spawned(ChildGuardList<Child>),
+        // The pipeline that doesn't spawn any process, e.g. `KEY=value`.
+        Finished(Output),
+    }
+
+    // The destination of the stdout or stderr of a command.
+    #[derive(Debug)]
+    enum Sink {
+        // The standard output of the command, that's the next command or the output of pipeline.
+        Stdout,
+        // The standard error of the command, that's the output of pipeline,
+        // or the stderr of current process on the platforms without unix sockets.
+        Stderr,
+        File(File),
+    }
+
+    impl Sink {
+        fn try_clone(&self) -> Result<Sink> {
+            Ok(match self {
+                Sink::Stdout => Sink::Stdout,
+                Sink::Stderr => Sink::Stderr,
+                Sink::File(file) => Sink::File(file.try_clone()?),
+            })
+        }
+    }
+
+    // The state of shell that is shared by the commands in sequence.
+    #[derive(Debug, Default)]
+    struct Shell {
+        variables: HashMap<String, String>,
+        last_status: i32,
+        pipefail: bool,
+        // The working directory that is changed by `cd`, it's the one of current process if it's `None`.
+        current_dir: Option<PathBuf>,
+        output_capture: Arc<OutputCapture>,
+    }
+
+    impl Shell {
+        async fn execute<Child: ChildUnify, Command: CommandUnify<Child>>(
+            &mut self,
+            pipeline: Vec<SimpleCommand>,
+        ) -> Result<Execution<Child>> {
+            if let [simple_command] = pipeline.as_slice() {
+                let words: Vec<String> = simple_command
+                    .words
+                    .iter()
+                    .filter_map(|word| self.expand(word))
+                    .collect();
+                match words.split_first() {
+                    None => return self.assign(simple_command).map(Execution::Finished),
+                    Some((program, args)) if program == "set" => {
+                        return self.set(args).map(Execution::Finished)
+                    }
+                    Some((program, args)) if program == "cd" => {
+                        return self.cd(args).map(Execution::Finished)
+                    }
+                    _ => {}
+                }
+            }
+
+            let stage_num = pipeline.len();
+            let mut process_linked_list: ChildGuardList<Child> = LinkedList::new();
             // Standard input to the current process.
-            // If previous_command is present, it inherits the standard output of the previous command.
-            // If not, it inherits the current parent process.
-            let previous_command = process_linked_list.back_mut();
+            // It inherits the standard output of the previous command, or the current parent process.
             let mut stdin = Stdio::inherit();
+            // The stderr of all commands in the pipeline is read by the guard of the last one.
+            let (mut stderr_reader, stderr_writer) = match pipe_stream_pair()? {
+                Some((reader, writer)) => (Some(reader), Some(writer)),
+                None => (None, None),
+            };
+
+            for (index, simple_command) in pipeline.iter().enumerate() {
+                let is_last = index + 1 == stage_num;
+                let mut words = simple_command
+                    .words
+                    .iter()
+                    .filter_map(|word| self.expand(word));
+                let program = words
+                    .next()
+                    .ok_or_else(|| anyhow!("Without command in the pipeline."))?;
+
+                let mut stdout_sink = Sink::Stdout;
+                let mut stderr_sink = Sink::Stderr;
+                for redirect in simple_command.redirects.iter() {
+                    match redirect {
+                        Redirect::File { fd: 0, mode, path } => {
+                            stdin = Stdio::from(self.open(*mode, path)?);
+                        }
+                        Redirect::File { fd, mode, path } => {
+                            let file = Sink::File(self.open(*mode, path)?);
+                            match fd {
+                                1 => stdout_sink = file,
+                                _ => stderr_sink = file,
+                            }
+                        }
+                        Redirect::Duplicate {
+                            fd: 1,
+                            target_fd: 2,
+                        } => {
+                            stdout_sink = stderr_sink.try_clone()?;
+                        }
+                        Redirect::Duplicate {
+                            fd: 2,
+                            target_fd: 1,
+                        } => {
+                            stderr_sink = stdout_sink.try_clone()?;
+                        }
+                        Redirect::Duplicate { .. } => {}
+                    }
+                }
+
+                // The stdout of the last command is read by the guard,
+                // and the stdout is connected to sockets as well if it's shared with stderr.
+                let stdout_pair = if is_last || matches!(stderr_sink, Sink::Stdout) {
+                    pipe_stream_pair()?
+                } else {
+                    None
+                };
+                if (matches!(stderr_sink, Sink::Stdout) && stdout_pair.is_none())
+                    || (matches!(stdout_sink, Sink::Stderr) && stderr_writer.is_none())
+                {
+                    return Err(anyhow!(
+                        "`{}` can't redirect between stdout and stderr in the middle of pipeline or on this platform.",
+                        program
+                    ));
+                }
+
+                let to_stdio = |sink: Sink| -> Result<Stdio> {
+                    Ok(match (sink, &stdout_pair, &stderr_writer) {
+                        (Sink::File(file), _, _) => Stdio::from(file),
+                        (Sink::Stdout, Some((_, writer)), _) | (Sink::Stderr, _, Some(writer)) => {
+                            pipe_stdio(writer.try_clone()?)
+                        }
+                        (Sink::Stdout, None, _) => Stdio::piped(),
+                        (Sink::Stderr, _, None) if is_last => Stdio::piped(),
+                        (Sink::Stderr, _, None) => Stdio::inherit(),
+                    })
+                };
+                let pipes_stdout = matches!(stdout_sink, Sink::Stdout);
+                let stdout = to_stdio(stdout_sink)?;
+                let stderr = to_stdio(stderr_sink)?;
+
+                let mut process = {
+                    let mut command = Command::new(&program);
+                    command
+                        .args(words)
+                        .stdin(mem::replace(&mut stdin, Stdio::null()))
+                        .stdout(stdout)
+                        .stderr(stderr)
+                        .isolate_process_group();
+                    if let Some(current_dir) = self.current_dir.as_ref() {
+                        command.current_dir(current_dir);
+                    }
+
+                    for (key, value) in simple_command.assignments.iter() {
+                        command.env(key, self.expand(value).unwrap_or_default());
+                    }
+
+                    command
+                        .spawn()
+                        .with_context(|| format!("Can't spawn `{}`", program))?
+                };
+
+                if !is_last {
+                    stdin = match stdout_pair {
+                        Some((reader, _)) => pipe_stdio(reader),
+                        None if pipes_stdout => {
+                            process.stdout_to_stdio().await.unwrap_or_else(Stdio::null)
+                        }
+                        None => Stdio::null(),
+                    };
+                    process_linked_list.push_back(ChildGuard::<Child>::new(process));
+                    continue;
+                }
+
+                let mut child_guard = ChildGuard::<Child>::new(process);
+                child_guard.stdout_reader = stdout_pair.map(|(reader, _)| reader);
+                child_guard.stderr_reader = stderr_reader.take();
+                child_guard.output_capture = self.output_capture.clone();
+                child_guard.pipefail = self.pipefail;
+                process_linked_list.push_back(child_guard);
+            }
 
-            if let Some(previous_command_ref) = previous_command {
-                let mut t = None;
+            Ok(Execution::Spawned(process_linked_list))
+        }
 
-                if let Some(child_mut) = previous_command_ref.child.as_mut() {
-                    mem::swap(&mut child_mut.stdout_to_stdio().await, &mut t);
+        // Set the variables of the command without program, the files of redirections are created as well.
+        fn assign(&mut self, simple_command: &SimpleCommand) -> Result<Output> {
+            for redirect in simple_command.redirects.iter() {
+                if let Redirect::File { mode, path, .. } = redirect {
+                    self.open(*mode, path)?;
                 }
+            }
+
+            for (key, value) in simple_command.assignments.iter() {
+                let value = self.expand(value).unwrap_or_default();
+                self.variables.insert(key.clone(), value);
+            }
+
+            Ok(Output {
+                status: exit_status(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
 
-                if let Some(child_stdio) = t {
-                    stdin = child_stdio;
+        // The builtin `set`, only the option `pipefail` is supported.
+        fn set(&mut self, args: &[String]) -> Result<Output> {
+            match args {
+                [flag, option] if option == "pipefail" && (flag == "-o" || flag == "+o") => {
+                    self.pipefail = flag == "-o";
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "Only `set -o pipefail` and `set +o pipefail` are supported."
+                    ))
                 }
             }
 
-            let mut output = Command::new(command);
-            output.args(args).stdin(stdin).stderr(Stdio::piped());
+            Ok(Output {
+                status: exit_status(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
 
-            let process: Child;
-            let end_flag = if let Some(stdout_result) = check_redirect_result {
-                let stdout = stdout_result?;
-                process = output.stdout(stdout).spawn()?;
-                true
-            } else {
-                let stdout;
-                // if commands.peek().is_some() {
-                //     // there is another command piped behind this one
-                //     // prepare to send output to the next command
-                stdout = Stdio::piped();
-                // } else {
-                //     // there are no more commands piped behind this one
-                //     // send output to shell stdout
-                //     // TODO:It shouldn't be the standard output of the parent process in the context,
-                //     // there should be a default file to record it.
-                //     stdout = Stdio::inherit();
-                // };
-
-                process = output.stdout(stdout).spawn()?;
-                false
+        // The builtin `cd`, the directory is resolved from the current one.
+        fn cd(&mut self, args: &[String]) -> Result<Output> {
+            let dir = match args {
+                [] => env::var_os("HOME")
+                    .map(PathBuf::from)
+                    .ok_or_else(|| anyhow!("`cd` without argument requires `HOME`."))?,
+                [dir] => self.resolve(dir),
+                _ => return Err(anyhow!("`cd` accepts only one directory.")),
             };
+            if !dir.is_dir() {
+                return Err(anyhow!("`cd` can't change to `{}`", dir.display()));
+            }
+            self.current_dir = Some(dir);
 
-            process_linked_list.push_back(ChildGuard::<Child>::new(process));
+            Ok(Output {
+                status: exit_status(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
 
-            if end_flag {
-                break;
+        // The relative path is joined to the directory changed by `cd`.
+        fn resolve(&self, path: &str) -> PathBuf {
+            match self.current_dir.as_ref() {
+                Some(current_dir) => current_dir.join(path),
+                None => PathBuf::from(path),
             }
         }
-        Ok(process_linked_list)
+
+        // The unquoted word that is expanded to empty is removed.
+        fn expand(&self, word: &Word) -> Option<String> {
+            let mut text = String::new();
+            for part in word.parts.iter() {
+                match part {
+                    WordPart::Literal { text: literal, .. } => text.push_str(literal),
+                    WordPart::Variable(name) => {
+                        if let Some(value) = self.variables.get(name) {
+                            text.push_str(value);
+                        } else if let Some(value) = env::var_os(name) {
+                            text.push_str(&value.to_string_lossy());
+                        }
+                    }
+                    WordPart::LastStatus => text.push_str(&self.last_status.to_string()),
+                }
+            }
+
+            if text.is_empty() && !word.quoted {
+                None
+            } else {
+                Some(text)
+            }
+        }
+
+        fn open(&self, mode: FileMode, path: &Word) -> Result<File> {
+            let path = self
+                .expand(path)
+                .ok_or_else(|| anyhow!("The file of redirection is empty."))?;
+
+            let mut open_options = OpenOptions::new();
+            match mode {
+                FileMode::Read => open_options.read(true),
+                FileMode::Write => open_options.write(true).create(true).truncate(true),
+                FileMode::Append => open_options.append(true).create(true),
+            };
+
+            open_options
+                .open(self.resolve(&path))
+                .with_context(|| format!("Can't open `{}`", path))
+        }
     }
 
-    // I should give Option<Result<File>>
-    //By Option(Some(Result<T>)), determine if there is an output stdio..
-    //By Result<T>(OK(t)), determine if there is success open file.
-    #[cfg(not(SPLIT_INCLUSIVE_COMPATIBLE))]
-    fn _has_redirect_file(command: &str) -> Option<Result<File>> {
-        let angle_bracket = if command.contains(">>") {
-            ">>"
-        } else if command.contains('>') {
-            ">"
-        } else {
-            return None;
+    // Read the stream to the end by the capture, the tail of it is returned.
+    async fn read_pipe_stream(
+        stream: Option<PipeStream>,
+        output_capture: Arc<OutputCapture>,
+        source: OutputSource,
+    ) -> Result<Vec<u8>> {
+        let stream = match stream {
+            Some(stream) => stream,
+            None => return Ok(Vec::new()),
         };
 
-        if let Some(filename) = command.trim().rsplit(angle_bracket).next() {
-            Some(create_stdio_file(angle_bracket, filename))
-        } else {
-            None
+        let output = smol::unblock(move || output_capture.read(source, stream)).await?;
+        Ok(output)
+    }
+
+    /// The (reader, writer) pair of connected sockets, it's `None` on the platforms without unix sockets.
+    #[cfg(unix)]
+    pub(crate) fn pipe_stream_pair() -> io::Result<Option<(PipeStream, PipeStream)>> {
+        PipeStream::pair().map(Some)
+    }
+
+    /// The (reader, writer) pair of connected sockets, it's `None` on the platforms without unix sockets.
+    #[cfg(not(unix))]
+    pub(crate) fn pipe_stream_pair() -> io::Result<Option<(PipeStream, PipeStream)>> {
+        Ok(None)
+    }
+
+    /// Convert the end of sockets to the stdio of child.
+    #[cfg(unix)]
+    pub(crate) fn pipe_stdio(stream: PipeStream) -> Stdio {
+        use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+        // Safety: the file descriptor is owned by the stream, which is consumed here.
+        unsafe { Stdio::from_raw_fd(stream.into_raw_fd()) }
+    }
+
+    /// Convert the end of sockets to the stdio of child.
+    #[cfg(not(unix))]
+    pub(crate) fn pipe_stdio(stream: PipeStream) -> Stdio {
+        Stdio::from(stream)
+    }
+
+    #[cfg(unix)]
+    fn exit_status(code: i32) -> ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+
+    #[cfg(windows)]
+    fn exit_status(code: i32) -> ExitStatus {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+
+    //that code base on 'build-your-own-shell-rust'. Thanks you Josh Mcguigan.
+    /// The grammar of shell commands.
+    pub(crate) mod syntax {
+        use anyhow::{anyhow, Result};
+
+        use std::fmt;
+        use std::iter::Peekable;
+        use std::mem;
+        use std::str::Chars;
+
+        /// A part of word.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub(crate) enum WordPart {
+            Literal {
+                text: String,
+                quoted: bool,
+            },
+            /// `$NAME` or `${NAME}`.
+            Variable(String),
+            /// `$?`.
+            LastStatus,
+        }
+
+        /// A word, e.g. `"$HOME"/bin`.
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub(crate) struct Word {
+            pub(crate) parts: Vec<WordPart>,
+            // The word has any quoted part, it's kept even if it's expanded to empty.
+            pub(crate) quoted: bool,
+        }
+
+        impl Word {
+            fn push_char(&mut self, c: char, quoted: bool) {
+                if let Some(WordPart::Literal {
+                    text,
+                    quoted: last_quoted,
+                }) = self.parts.last_mut()
+                {
+                    if *last_quoted == quoted {
+                        text.push(c);
+                        return;
+                    }
+                }
+
+                self.parts.push(WordPart::Literal {
+                    text: c.to_string(),
+                    quoted,
+                });
+            }
+
+            // The file descriptor before `<` or `>`, e.g. `2` of `2>`.
+            fn file_descriptor(&self) -> Option<u8> {
+                match self.parts.as_slice() {
+                    [WordPart::Literal {
+                        text,
+                        quoted: false,
+                    }] => match text.as_str() {
+                        "0" => Some(0),
+                        "1" => Some(1),
+                        "2" => Some(2),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+
+            // Split `KEY=value` into the name and value.
+            fn assignment(&self) -> Option<(String, Word)> {
+                let (first_text, rest_parts) = match self.parts.split_first() {
+                    Some((
+                        WordPart::Literal {
+                            text,
+                            quoted: false,
+                        },
+                        rest_parts,
+                    )) => (text, rest_parts),
+                    _ => return None,
+                };
+
+                let index = first_text.find('=')?;
+                let name = &first_text[..index];
+                if !is_name(name) {
+                    return None;
+                }
+
+                let mut value = Word {
+                    parts: Vec::new(),
+                    quoted: true,
+                };
+                if index + 1 < first_text.len() {
+                    value.parts.push(WordPart::Literal {
+                        text: first_text[index + 1..].to_string(),
+                        quoted: false,
+                    });
+                }
+                value.parts.extend(rest_parts.iter().cloned());
+                Some((name.to_string(), value))
+            }
+
+            // Render the literal text, the adjacent literal parts are joined,
+            // so a placeholder isn't split by quoting (e.g. `{scheduled_time:"%Y %m"}`).
+            pub(crate) fn render<F>(&mut self, render: &F) -> Result<()>
+            where
+                F: Fn(&str) -> Result<String>,
+            {
+                let mut parts: Vec<WordPart> = Vec::with_capacity(self.parts.len());
+                for part in mem::take(&mut self.parts) {
+                    if let (
+                        Some(WordPart::Literal { text, .. }),
+                        WordPart::Literal { text: literal, .. },
+                    ) = (parts.last_mut(), &part)
+                    {
+                        text.push_str(literal);
+                        continue;
+                    }
+                    parts.push(part);
+                }
+
+                for part in parts.iter_mut() {
+                    if let WordPart::Literal { text, .. } = part {
+                        *text = render(text)?;
+                    }
+                }
+                self.parts = parts;
+                Ok(())
+            }
+        }
+
+        /// The way to open file of redirection.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub(crate) enum FileMode {
+            /// `<`
+            Read,
+            /// `>`
+            Write,
+            /// `>>`
+            Append,
+        }
+
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub(crate) enum Token {
+            Word(Word),
+            /// `|`
+            Pipe,
+            /// `&&`
+            And,
+            /// `||`
+            Or,
+            /// `;`
+            Semicolon,
+            Newline,
+            /// e.g. `2>> file`, the file is the next word.
+            FileRedirect(u8, FileMode),
+            /// e.g. `2>&1`.
+            Duplicate(u8, u8),
+        }
+
+        impl fmt::Display for Token {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    Token::Word(_) => write!(f, "word"),
+                    Token::Pipe => write!(f, "|"),
+                    Token::And => write!(f, "&&"),
+                    Token::Or => write!(f, "||"),
+                    Token::Semicolon => write!(f, ";"),
+                    Token::Newline => write!(f, "newline"),
+                    Token::FileRedirect(fd, FileMode::Read) => write!(f, "{}<", fd),
+                    Token::FileRedirect(fd, FileMode::Write) => write!(f, "{}>", fd),
+                    Token::FileRedirect(fd, FileMode::Append) => write!(f, "{}>>", fd),
+                    Token::Duplicate(fd, target_fd) => write!(f, "{}>&{}", fd, target_fd),
+                }
+            }
+        }
+
+        /// The redirection of a command.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub(crate) enum Redirect {
+            File { fd: u8, mode: FileMode, path: Word },
+            Duplicate { fd: u8, target_fd: u8 },
+        }
+
+        /// A command with the assignments and redirections, e.g. `KEY=value grep key < file`.
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub(crate) struct SimpleCommand {
+            pub(crate) assignments: Vec<(String, Word)>,
+            pub(crate) words: Vec<Word>,
+            pub(crate) redirects: Vec<Redirect>,
+        }
+
+        impl SimpleCommand {
+            fn push_word(&mut self, word: Word) {
+                if self.words.is_empty() {
+                    if let Some(assignment) = word.assignment() {
+                        self.assignments.push(assignment);
+                        return;
+                    }
+                }
+                self.words.push(word);
+            }
+
+            fn is_empty(&self) -> bool {
+                self.assignments.is_empty() && self.words.is_empty() && self.redirects.is_empty()
+            }
+
+            // Render the literal text of the values of assignments, the words and the paths of redirections.
+            pub(crate) fn render<F>(&mut self, render: &F) -> Result<()>
+            where
+                F: Fn(&str) -> Result<String>,
+            {
+                for (_, value) in self.assignments.iter_mut() {
+                    value.render(render)?;
+                }
+                for word in self.words.iter_mut() {
+                    word.render(render)?;
+                }
+                for redirect in self.redirects.iter_mut() {
+                    if let Redirect::File { path, .. } = redirect {
+                        path.render(render)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        /// The condition to run a pipeline, by the status of the previous one.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub(crate) enum Connector {
+            /// The first pipeline, or after `;`.
+            Always,
+            /// After `&&`.
+            And,
+            /// After `||`.
+            Or,
+        }
+
+        /// The pipelines in sequence.
+        pub(crate) type CommandList = Vec<(Connector, Vec<SimpleCommand>)>;
+
+        fn is_name(name: &str) -> bool {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                    chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+                }
+                _ => false,
+            }
+        }
+
+        struct Lexer<'a> {
+            chars: Peekable<Chars<'a>>,
+            tokens: Vec<Token>,
+            word: Word,
+            in_word: bool,
+        }
+
+        impl<'a> Lexer<'a> {
+            fn finish_word(&mut self) {
+                if mem::replace(&mut self.in_word, false) {
+                    self.tokens.push(Token::Word(mem::take(&mut self.word)));
+                }
+            }
+
+            fn push_char(&mut self, c: char, quoted: bool) {
+                self.in_word = true;
+                self.word.push_char(c, quoted);
+            }
+
+            fn push_part(&mut self, part: WordPart) {
+                self.in_word = true;
+                self.word.parts.push(part);
+            }
+
+            // After `$`.
+            fn read_variable(&mut self, quoted: bool) -> Result<()> {
+                match self.chars.peek() {
+                    Some('?') => {
+                        self.chars.next();
+                        self.push_part(WordPart::LastStatus);
+                    }
+                    Some('{') => {
+                        self.chars.next();
+                        let mut name = String::new();
+                        loop {
+                            match self.chars.next() {
+                                Some('}') => break,
+                                Some(c) => name.push(c),
+                                None => return Err(anyhow!("Unterminated `${{`.")),
+                            }
+                        }
+
+                        if name == "?" {
+                            self.push_part(WordPart::LastStatus);
+                        } else if is_name(&name) {
+                            self.push_part(WordPart::Variable(name));
+                        } else {
+                            return Err(anyhow!("Bad substitution `${{{}}}`.", name));
+                        }
+                    }
+                    Some('(') => return Err(anyhow!("Command substitution is not supported.")),
+                    Some(&c) if c == '_' || c.is_ascii_alphabetic() => {
+                        let mut name = String::new();
+                        while let Some(&c) = self.chars.peek() {
+                            if c != '_' && !c.is_ascii_alphanumeric() {
+                                break;
+                            }
+                            name.push(c);
+                            self.chars.next();
+                        }
+                        self.push_part(WordPart::Variable(name));
+                    }
+                    _ => self.push_char('$', quoted),
+                }
+                Ok(())
+            }
+
+            fn read_single_quoted(&mut self) -> Result<()> {
+                self.in_word = true;
+                self.word.quoted = true;
+                loop {
+                    match self.chars.next() {
+                        Some('\'') => return Ok(()),
+                        Some(c) => self.push_char(c, true),
+                        None => return Err(anyhow!("Unterminated single quote.")),
+                    }
+                }
+            }
+
+            fn read_double_quoted(&mut self) -> Result<()> {
+                self.in_word = true;
+                self.word.quoted = true;
+                loop {
+                    match self.chars.next() {
+                        Some('"') => return Ok(()),
+                        Some('\\') => match self.chars.peek() {
+                            Some(&c) if c == '$' || c == '`' || c == '"' || c == '\\' => {
+                                self.chars.next();
+                                self.push_char(c, true);
+                            }
+                            Some('\n') => {
+                                self.chars.next();
+                            }
+                            _ => self.push_char('\\', true),
+                        },
+                        Some('$') => self.read_variable(true)?,
+                        Some('`') => return Err(anyhow!("Command substitution is not supported.")),
+                        Some(c) => self.push_char(c, true),
+                        None => return Err(anyhow!("Unterminated double quote.")),
+                    }
+                }
+            }
+
+            // After `<` or `>`.
+            fn read_redirect(&mut self, c: char) -> Result<()> {
+                let fd = if self.in_word {
+                    self.word.file_descriptor()
+                } else {
+                    None
+                };
+                if fd.is_some() {
+                    self.in_word = false;
+                    self.word = Word::default();
+                }
+                self.finish_word();
+
+                let token = match (c, self.chars.peek()) {
+                    ('<', _) => Token::FileRedirect(fd.unwrap_or(0), FileMode::Read),
+                    (_, Some('>')) => {
+                        self.chars.next();
+                        Token::FileRedirect(fd.unwrap_or(1), FileMode::Append)
+                    }
+                    (_, Some('&')) => {
+                        self.chars.next();
+                        match self.chars.next() {
+                            Some('1') => Token::Duplicate(fd.unwrap_or(1), 1),
+                            Some('2') => Token::Duplicate(fd.unwrap_or(1), 2),
+                            _ => return Err(anyhow!("Only `>&1` and `>&2` are supported.")),
+                        }
+                    }
+                    _ => Token::FileRedirect(fd.unwrap_or(1), FileMode::Write),
+                };
+
+                match token {
+                    Token::FileRedirect(0, FileMode::Read)
+                    | Token::FileRedirect(1, FileMode::Write)
+                    | Token::FileRedirect(1, FileMode::Append)
+                    | Token::FileRedirect(2, FileMode::Write)
+                    | Token::FileRedirect(2, FileMode::Append)
+                    | Token::Duplicate(1, _)
+                    | Token::Duplicate(2, _) => {
+                        self.tokens.push(token);
+                        Ok(())
+                    }
+                    _ => Err(anyhow!("The redirection `{}` is not supported.", token)),
+                }
+            }
+        }
+
+        /// Split the input into tokens.
+        pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>> {
+            let mut lexer = Lexer {
+                chars: input.chars().peekable(),
+                tokens: Vec::new(),
+                word: Word::default(),
+                in_word: false,
+            };
+
+            while let Some(c) = lexer.chars.next() {
+                match c {
+                    ' ' | '\t' | '\r' => lexer.finish_word(),
+                    '\n' => {
+                        lexer.finish_word();
+                        lexer.tokens.push(Token::Newline);
+                    }
+                    ';' => {
+                        lexer.finish_word();
+                        lexer.tokens.push(Token::Semicolon);
+                    }
+                    '#' if !lexer.in_word => {
+                        while let Some(&c) = lexer.chars.peek() {
+                            if c == '\n' {
+                                break;
+                            }
+                            lexer.chars.next();
+                        }
+                    }
+                    '|' => {
+                        lexer.finish_word();
+                        if lexer.chars.peek() == Some(&'|') {
+                            lexer.chars.next();
+                            lexer.tokens.push(Token::Or);
+                        } else {
+                            lexer.tokens.push(Token::Pipe);
+                        }
+                    }
+                    '&' => {
+                        lexer.finish_word();
+                        if lexer.chars.next() != Some('&') {
+                            return Err(anyhow!("Background job `&` is not supported."));
+                        }
+                        lexer.tokens.push(Token::And);
+                    }
+                    '<' | '>' => lexer.read_redirect(c)?,
+                    '(' | ')' => return Err(anyhow!("Subshell `{}` is not supported.", c)),
+                    '`' => return Err(anyhow!("Command substitution is not supported.")),
+                    '\'' => lexer.read_single_quoted()?,
+                    '"' => lexer.read_double_quoted()?,
+                    '\\' => match lexer.chars.next() {
+                        // The line continuation.
+                        Some('\n') => {}
+                        Some(c) => lexer.push_char(c, true),
+                        None => lexer.push_char('\\', false),
+                    },
+                    '$' => lexer.read_variable(false)?,
+                    c => lexer.push_char(c, false),
+                }
+            }
+            lexer.finish_word();
+
+            Ok(lexer.tokens)
+        }
+
+        /// Parse the input into the pipelines in sequence.
+        pub(crate) fn parse(input: &str) -> Result<CommandList> {
+            let mut command_list = Vec::new();
+            let mut connector = Connector::Always;
+            let mut pipeline = Vec::new();
+            let mut simple_command = SimpleCommand::default();
+
+            let mut tokens = tokenize(input)?.into_iter();
+            while let Some(token) = tokens.next() {
+                match token {
+                    Token::Word(word) => simple_command.push_word(word),
+                    Token::FileRedirect(fd, mode) => {
+                        match tokens.next() {
+                            Some(Token::Word(path)) => simple_command
+                                .redirects
+                                .push(Redirect::File { fd, mode, path }),
+                            _ => return Err(anyhow!("Missing the file after `{}`.", token)),
+                        }
+                    }
+                    Token::Duplicate(fd, target_fd) => simple_command
+                        .redirects
+                        .push(Redirect::Duplicate { fd, target_fd }),
+                    Token::Pipe => {
+                        if simple_command.is_empty() {
+                            return Err(anyhow!("Missing command before `{}`.", token));
+                        }
+                        pipeline.push(mem::take(&mut simple_command));
+                    }
+                    // Blank lines and the lines after `&&`, `||` or `|` are skipped.
+                    Token::Newline if simple_command.is_empty() => {}
+                    Token::And | Token::Or | Token::Semicolon | Token::Newline => {
+                        if simple_command.is_empty() {
+                            return Err(anyhow!("Missing command before `{}`.", token));
+                        }
+                        pipeline.push(mem::take(&mut simple_command));
+                        command_list.push((connector, mem::take(&mut pipeline)));
+
+                        connector = match token {
+                            Token::And => Connector::And,
+                            Token::Or => Connector::Or,
+                            _ => Connector::Always,
+                        };
+                    }
+                }
+            }
+
+            if !simple_command.is_empty() {
+                pipeline.push(simple_command);
+                command_list.push((connector, pipeline));
+            } else if !pipeline.is_empty() || connector != Connector::Always {
+                return Err(anyhow!("Unexpected end of command."));
+            }
+
+            if command_list.is_empty() {
+                return Err(anyhow!("Without command."));
+            }
+            Ok(command_list)
         }
     }
+}
+
+mod tests {
 
-    #[cfg(SPLIT_INCLUSIVE_COMPATIBLE)]
-    fn _has_redirect_file(command: &str) -> Option<Result<File>> {
-        let angle_bracket = if command.contains(">>") {
-            ">>"
-        } else if command.contains('>') {
-            ">"
-        } else {
-            return None;
+    #[test]
+    fn test_parse() {
+        use super::shell_command::syntax::{parse, Connector, Redirect, Word, WordPart};
+
+        let literal = |text: &str, quoted: bool| WordPart::Literal {
+            text: text.to_string(),
+            quoted,
         };
 
-        let mut sub_command_inner = command.trim().split_inclusive(angle_bracket).rev();
+        let command_list =
+            parse("KEY='a b' grep -e \"x $HOME\" < in.txt 2>&1 | wc -l >> out.txt && echo $? || true; date\n")
+                .unwrap();
+        let connectors: Vec<Connector> = command_list.iter().map(|(c, _)| *c).collect();
+        assert_eq!(
+            connectors,
+            vec![
+                Connector::Always,
+                Connector::And,
+                Connector::Or,
+                Connector::Always
+            ]
+        );
+
+        let pipeline = &command_list[0].1;
+        assert_eq!(pipeline.len(), 2);
+        assert_eq!(
+            pipeline[0].assignments,
+            vec![(
+                String::from("KEY"),
+                Word {
+                    parts: vec![literal("a b", true)],
+                    quoted: true
+                }
+            )]
+        );
+        assert_eq!(pipeline[0].words.len(), 3);
+        assert_eq!(
+            pipeline[0].words[2].parts,
+            vec![
+                literal("x ", true),
+                WordPart::Variable(String::from("HOME"))
+            ]
+        );
+        assert_eq!(
+            pipeline[0].redirects[1],
+            Redirect::Duplicate {
+                fd: 2,
+                target_fd: 1
+            }
+        );
+        assert_eq!(
+            command_list[1].1[0].words[1].parts,
+            vec![WordPart::LastStatus]
+        );
+
+        // Escaped characters.
+        let command_list = parse(r#"echo a\ b\;c 'it'\''s' "\"q\" \$x""#).unwrap();
+        let words: Vec<String> = command_list[0].1[0]
+            .words
+            .iter()
+            .map(|word| {
+                word.parts
+                    .iter()
+                    .map(|part| match part {
+                        WordPart::Literal { text, .. } => text.clone(),
+                        _ => String::new(),
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(words, vec!["echo", "a b;c", "it's", "\"q\" $x"]);
 
-        sub_command_inner
-            .next()
-            .map(|filename| create_stdio_file(angle_bracket, filename))
+        for input in [
+            "",
+            "echo 'a",
+            "echo a |",
+            "echo a && && echo b",
+            "| echo a",
+            "echo a > ",
+            "echo a &",
+            "echo $(date)",
+            "(echo a)",
+        ]
+        .iter()
+        {
+            assert!(parse(input).is_err(), "{}", input);
+        }
     }
 
-    //After confirming that there is a redirect file, parse the command before the command '>'.
-    fn _remove_angle_bracket_command(command: &str) -> Result<&str> {
-        let mut sub_command_inner = command.trim().split('>');
-        sub_command_inner
-            .next()
-            .ok_or_else(|| anyhow!("can't parse ...."))
+    #[test]
+    fn test_parse_and_run() -> anyhow::Result<()> {
+        use super::shell_command::parse_and_run;
+        use smol::process::{Child, Command};
+        use std::fs;
+
+        let run = |input: &str| {
+            smol::block_on(async {
+                let mut childs = parse_and_run::<Child, Command>(input).await?;
+                let last_child = childs
+                    .pop_back()
+                    .ok_or_else(|| anyhow::anyhow!("Without child."))?;
+                let (output, _) = last_child.wait_with_usage().await?;
+                Ok::<_, anyhow::Error>(output)
+            })
+        };
+
+        let output = run("echo 'a  b' \"$NOT_EXISTING\" | tr a-z A-Z")?;
+        assert_eq!(output.stdout, b"A  B \n");
+
+        let output = run("NAME=world; GREETING=hi sh -c 'echo $GREETING $0' \"$NAME\"")?;
+        assert_eq!(output.stdout, b"hi world\n");
+
+        let output = run("false && echo skipped || echo $?; echo done")?;
+        assert_eq!(output.stdout, b"1\ndone\n");
+        assert!(output.status.success());
+
+        let output = run("echo ok; false && echo skipped")?;
+        assert_eq!(output.stdout, b"ok\n");
+        assert_eq!(output.status.code(), Some(1));
+
+        let output = run("not-existing-command-of-delay-timer || echo $?")?;
+        assert_eq!(output.stdout, b"127\n");
+
+        let output = run("sh -c 'echo out; echo err >&2' 2>&1 | cat")?;
+        assert_eq!(output.stdout, b"out\nerr\n");
+
+        let output = run("sh -c 'echo err >&2' 2>&1")?;
+        assert_eq!(output.stdout, b"err\n");
+        assert!(output.stderr.is_empty());
+
+        let output = run("echo moved >&2")?;
+        assert_eq!(output.stderr, b"moved\n");
+
+        let output = run("sh -c 'echo first >&2; echo out' | sh -c 'cat; echo last >&2'")?;
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"first\nlast\n");
+
+        let dir = std::env::temp_dir().join(format!("delay_timer_parse_{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let file = dir.join("out.txt");
+        let input = format!(
+            "echo a > {file:?}; echo b >> {file:?}; sort -r < {file:?}",
+            file = file
+        );
+        let output = run(&input)?;
+        assert_eq!(output.stdout, b"b\na\n");
+
+        let input = format!(
+            "cd {dir:?} && echo c > cd.txt; cat cd.txt; pwd; cd not-existing-dir || pwd",
+            dir = dir
+        );
+        let output = run(&input)?;
+        let dir = dir.canonicalize()?;
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            format!("c\n{dir}\n{dir}\n", dir = dir.display())
+        );
+        assert_eq!(fs::read_to_string(dir.join("cd.txt"))?, "c\n");
+        fs::remove_dir_all(&dir)?;
+
+        Ok(())
     }
 
-    fn create_stdio_file(angle_bracket: &str, filename: &str) -> Result<File> {
-        let mut file_tmp = OpenOptions::new();
-        file_tmp.write(true).create(true);
+    #[test]
+    fn test_wait_pipeline() -> anyhow::Result<()> {
+        use super::shell_command::{parse_and_run, wait_pipeline, PipelineOutput};
+        use smol::process::{Child, Command};
+
+        let run = |input: &str| -> anyhow::Result<PipelineOutput> {
+            smol::block_on(async {
+                wait_pipeline(parse_and_run::<Child, Command>(input).await?).await
+            })
+        };
+        let codes = |pipeline_output: &PipelineOutput| -> Vec<Option<i32>> {
+            pipeline_output
+                .stage_statuses
+                .iter()
+                .map(|status| status.code())
+                .collect()
+        };
+
+        let pipeline_output = run("sh -c 'exit 2' | false | true")?;
+        assert_eq!(codes(&pipeline_output), [Some(2), Some(1), Some(0)]);
+        assert!(pipeline_output.output.status.success());
 
-        if angle_bracket == ">>" {
-            file_tmp.append(true);
+        let pipeline_output = run("set -o pipefail; sh -c 'exit 2' | false | true")?;
+        assert_eq!(codes(&pipeline_output), [Some(2), Some(1), Some(0)]);
+        assert_eq!(pipeline_output.output.status.code(), Some(1));
+        if cfg!(target_os = "linux") {
+            assert!(pipeline_output
+                .stage_resource_usages
+                .iter()
+                .all(|resource_usage| matches!(resource_usage, Some(usage) if usage.max_rss > 0)));
         }
 
-        //TODO:I need record that open file error because filename has a whitespace i don't trim.
-        let os_filename = Path::new(filename.trim()).as_os_str();
+        let pipeline_output =
+            run("set -o pipefail; false | true || echo $?; set +o pipefail; false | true")?;
+        assert_eq!(pipeline_output.output.stdout, b"1\n");
+        assert!(pipeline_output.output.status.success());
+
+        assert!(run("set -e").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_and_run_in_context() -> anyhow::Result<()> {
+        use super::shell_command::{parse_and_run_in_context, wait_pipeline};
+        use crate::prelude::*;
+        use smol::process::{Child, Command};
+
+        let mut context = TaskContext::default();
+        context
+            .task_id(7)
+            .record_id(9)
+            .time_zone(ScheduleIteratorTimeZone::Utc);
+        let run = |input: &str| {
+            smol::block_on(async {
+                let childs = parse_and_run_in_context::<Child, Command>(input, &context).await?;
+                wait_pipeline(childs)
+                    .await
+                    .map(|pipeline_output| pipeline_output.output)
+            })
+        };
+
+        let output = run("ID={task_id} sh -c 'echo $ID-$0' \"{record_id}\"")?;
+        assert_eq!(output.stdout, b"7-9\n");
+
+        // The values are kept in the words, even if they contain the separators of shell.
+        let output = run("printf '%s|' {scheduled_time:%Y%t%m} {scheduled_time:\"%Y; %m\"}")?;
+        assert_eq!(output.stdout, b"1970\t01|1970; 01|");
 
-        let stdio_file = file_tmp.open(os_filename)?;
-        Ok(stdio_file)
+        assert!(run("echo {scheduled_time:%Q}").is_err());
+        Ok(())
     }
 }
@@ -0,0 +1,520 @@
+//! process
+//! It is a module that builds the bodies of tasks which run a program directly,
+//! the arguments are passed as they are, without parsing shell commands.
+//!
+//! ```
+//! use delay_timer::prelude::*;
+//! use std::time::Duration;
+//!
+//! let mut process_task_builder = ProcessTaskBuilder::new("grep");
+//! process_task_builder
+//!     .add_args(&["-c", "disk full"])
+//!     .add_env("LC_ALL", "C")
+//!     .set_current_dir("/tmp")
+//!     .set_stdin("error: disk full\n")
+//!     .set_kill_grace_period(Duration::from_secs(5))
+//!     .set_output_limit(64 * 1024)
+//!     .set_resource_limits(
+//!         ResourceLimits::default()
+//!             .cpu_time(Duration::from_secs(10))
+//!             .open_files(256)
+//!             .nice(10)
+//!             .io_priority(IoPriority::Idle),
+//!     );
+//!
+//! let task = TaskBuilder::default()
+//!     .set_frequency(Frequency::Repeated("0 * * * * * *"))
+//!     .set_task_id(1)
+//!     .set_maximum_running_time(30)
+//!     .spawn(process_task_builder.build());
+//! assert!(task.is_ok());
+//! ```
+use crate::prelude::*;
+use crate::utils::output::OutputCapture;
+use crate::utils::parse::shell_command::{pipe_stdio, pipe_stream_pair, CommandUnify, PipeStream};
+use crate::utils::resource::ResourceLimits;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use smol::process::{Child as SmolChild, Command as SmolCommand};
+
+/// The builder of task bodies that run a program with explicit arguments.
+///
+/// The environment is inherited from the current process by default,
+/// it can be cleared by `set_env_clear`, and the variables added or removed are overlaid on it.
+///
+/// The process is spawned as the leader of a new process group (unix only),
+/// when the task is cancelled or runs out of the maximum running time, the whole group is killed,
+/// or it receives SIGTERM first if the kill grace period is set.
+/// On linux the process is killed as well if the thread that spawns it exits,
+/// it's spawned by a worker thread of the runtime, so it's killed when the current process exits
+/// or the tokio runtime shuts down.
+///
+/// The placeholders in the arguments and the values of environment variables (e.g. `{record_id}`)
+/// are rendered from the `TaskContext`, see `render_template`.
+///
+/// The task finishes with `FinishOutput::PipelineOutput` of one stage,
+/// it has the usage of resources of the process on linux.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTaskBuilder {
+    program: String,
+    args: Vec<String>,
+    env_clear: bool,
+    // The variables overlaid on the environment, `None` removes the variable.
+    envs: Vec<(String, Option<String>)>,
+    current_dir: Option<PathBuf>,
+    stdin: Option<Vec<u8>>,
+    kill_grace_period: Option<Duration>,
+    output_limit: Option<usize>,
+    resource_limits: Option<ResourceLimits>,
+}
+
+impl ProcessTaskBuilder {
+    /// Constructs a new builder of task bodies that run the program,
+    /// the program is searched in `PATH` if it isn't a path.
+    pub fn new<S: Into<String>>(program: S) -> Self {
+        ProcessTaskBuilder {
+            program: program.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    pub fn add_arg<S: Into<String>>(&mut self, arg: S) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    pub fn add_args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_string()));
+        self
+    }
+
+    /// Set whether to clear the environment inherited from the current process (optional).
+    pub fn set_env_clear(&mut self, env_clear: bool) -> &mut Self {
+        self.env_clear = env_clear;
+        self
+    }
+
+    /// Add an environment variable, it overrides the inherited one.
+    pub fn add_env<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) -> &mut Self {
+        self.envs.push((key.into(), Some(value.into())));
+        self
+    }
+
+    /// Remove an environment variable from the inherited ones.
+    pub fn remove_env<K: Into<String>>(&mut self, key: K) -> &mut Self {
+        self.envs.push((key.into(), None));
+        self
+    }
+
+    /// Set the working directory of the process (optional).
+    pub fn set_current_dir<P: Into<PathBuf>>(&mut self, current_dir: P) -> &mut Self {
+        self.current_dir = Some(current_dir.into());
+        self
+    }
+
+    /// Set the data written to the stdin of the process (optional, unix only),
+    /// the stdin is null without data.
+    pub fn set_stdin<D: Into<Vec<u8>>>(&mut self, stdin: D) -> &mut Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+
+    /// Set the period between SIGTERM and SIGKILL when the process group is killed (optional, unix only).
+    pub fn set_kill_grace_period(&mut self, kill_grace_period: Duration) -> &mut Self {
+        self.kill_grace_period = Some(kill_grace_period);
+        self
+    }
+
+    /// Set the maximum bytes of stdout and stderr kept in the `FinishOutput` (optional),
+    /// the head of output is discarded, the default is `DEFAULT_OUTPUT_TAIL_LIMIT`.
+    pub fn set_output_limit(&mut self, output_limit: usize) -> &mut Self {
+        self.output_limit = Some(output_limit);
+        self
+    }
+
+    /// Set the limits of resources of the process (optional, linux only).
+    pub fn set_resource_limits(&mut self, resource_limits: ResourceLimits) -> &mut Self {
+        self.resource_limits = Some(resource_limits);
+        self
+    }
+
+    /// Build the task body that runs the process by smol.
+    pub fn build(
+        &self,
+    ) -> impl Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync {
+        let process_task_builder = Arc::new(self.clone());
+        move |context: TaskContext| {
+            let process_task_builder = process_task_builder.clone();
+            create_delay_task_handler(async_spawn(context.instrument(|context| async move {
+                process_task_builder
+                    .run::<SmolChild, SmolCommand>(context)
+                    .await
+            })))
+        }
+    }
+
+    async fn run<Child: ChildUnify, Command: CommandUnify<Child>>(
+        &self,
+        context: TaskContext,
+    ) -> AnyResult<()> {
+        let (child_guard, stdin_writer) = match self.spawn::<Child, Command>(&context) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                context
+                    .finishe_task(Some(FinishOutput::ExceptionOutput(format!("{:#}", e))))
+                    .await;
+                return Err(e);
+            }
+        };
+
+        let pipeline_output = self.wait_with_output(child_guard, stdin_writer).await?;
+        context
+            .finishe_task(Some(FinishOutput::PipelineOutput(Box::new(
+                pipeline_output,
+            ))))
+            .await;
+        Ok(())
+    }
+
+    // Spawn the process, the writer of stdin is returned if there's data to write.
+    fn spawn<Child: ChildUnify, Command: CommandUnify<Child>>(
+        &self,
+        context: &TaskContext,
+    ) -> AnyResult<(ChildGuard<Child>, Option<PipeStream>)> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| render_template(arg, context))
+            .collect::<AnyResult<Vec<String>>>()?;
+        let mut command = Command::new(&self.program);
+        command.args(&args).isolate_process_group();
+
+        if self.env_clear {
+            command.env_clear();
+        }
+        for (key, value) in self.envs.iter() {
+            match value {
+                Some(value) => command.env(key, render_template(value, context)?),
+                None => command.env_remove(key),
+            };
+        }
+        if let Some(current_dir) = self.current_dir.as_ref() {
+            command.current_dir(current_dir);
+        }
+        if let Some(resource_limits) = self.resource_limits {
+            command.set_resource_limits(resource_limits);
+        }
+
+        let mut stdin_writer = None;
+        if self.stdin.is_some() {
+            let (reader, writer) = pipe_stream_pair()?
+                .ok_or_else(|| anyhow!("The stdin of process task is only supported on unix."))?;
+            command.stdin(pipe_stdio(reader));
+            stdin_writer = Some(writer);
+        } else {
+            command.stdin(Stdio::null());
+        }
+
+        let mut stdout_reader = None;
+        match pipe_stream_pair()? {
+            Some((reader, writer)) => {
+                command.stdout(pipe_stdio(writer));
+                stdout_reader = Some(reader);
+            }
+            None => {
+                command.stdout(Stdio::piped());
+            }
+        }
+
+        let mut stderr_reader = None;
+        match pipe_stream_pair()? {
+            Some((reader, writer)) => {
+                command.stderr(pipe_stdio(writer));
+                stderr_reader = Some(reader);
+            }
+            None => {
+                command.stderr(Stdio::piped());
+            }
+        }
+
+        let child = command
+            .spawn()
+            .with_context(|| format!("Can't spawn `{}`", self.program))?;
+
+        let mut output_capture = OutputCapture::new(context);
+        if let Some(output_limit) = self.output_limit {
+            output_capture.set_tail_limit(output_limit);
+        }
+
+        let mut child_guard = ChildGuard::new(child);
+        child_guard.stdout_reader = stdout_reader;
+        child_guard.stderr_reader = stderr_reader;
+        child_guard.output_capture = Arc::new(output_capture);
+        child_guard.kill_grace_period = self.kill_grace_period;
+        Ok((child_guard, stdin_writer))
+    }
+
+    // Write the stdin while waiting for the process, the child may exit without reading all of it.
+    async fn wait_with_output<Child: ChildUnify>(
+        &self,
+        child_guard: ChildGuard<Child>,
+        stdin_writer: Option<PipeStream>,
+    ) -> AnyResult<PipelineOutput> {
+        let stdin = self.stdin.clone();
+        let write_stdin = async move {
+            if let (Some(mut stdin_writer), Some(stdin)) = (stdin_writer, stdin) {
+                unblock_spawn(move || stdin_writer.write_all(&stdin))
+                    .await
+                    .unwrap_or_else(|e| info!("The stdin of process isn't written fully: {}", e));
+            }
+        };
+
+        let ((), output) = futures::join!(write_stdin, child_guard.wait_with_usage());
+        let (output, resource_usage) = output?;
+        Ok(PipelineOutput {
+            stage_statuses: vec![output.status],
+            stage_resource_usages: vec![resource_usage],
+            output,
+        })
+    }
+}
+
+cfg_tokio_support!(
+    use tokio::process::{Child as TokioChild, Command as TokioCommand};
+
+    impl ProcessTaskBuilder {
+        /// Build the task body that runs the process in delay-timer `Runtime` based on tokio.
+        pub fn build_by_tokio(
+            &self,
+        ) -> impl Fn(TaskContext) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync {
+            let process_task_builder = Arc::new(self.clone());
+            move |context: TaskContext| {
+                let process_task_builder = process_task_builder.clone();
+                create_delay_task_handler(async_spawn_by_tokio(context.instrument(
+                    |context| async move {
+                        process_task_builder
+                            .run::<TokioChild, TokioCommand>(context)
+                            .await
+                    },
+                )))
+            }
+        }
+    }
+);
+
+mod tests {
+
+    #[test]
+    fn test_process_task() -> anyhow::Result<()> {
+        use super::ProcessTaskBuilder;
+        use crate::prelude::*;
+        use smol::process::{Child, Command};
+        use std::env;
+
+        let run = |process_task_builder: &ProcessTaskBuilder| {
+            smol::block_on(async {
+                let (child_guard, stdin_writer) =
+                    process_task_builder.spawn::<Child, Command>(&TaskContext::default())?;
+                process_task_builder
+                    .wait_with_output(child_guard, stdin_writer)
+                    .await
+                    .map(|pipeline_output| pipeline_output.output)
+            })
+        };
+
+        let current_dir = env::temp_dir().canonicalize()?;
+        let mut process_task_builder = ProcessTaskBuilder::new("sh");
+        process_task_builder
+            .add_args([
+                "-c",
+                "printf '%s|%s|%s|' \"$GREETING\" \"$HOME\" \"$(pwd)\"; cat; echo error >&2",
+            ])
+            .set_env_clear(true)
+            .add_env("PATH", env::var("PATH").unwrap_or_default())
+            .add_env("GREETING", "hello world")
+            .set_current_dir(&current_dir)
+            .set_stdin("input");
+
+        let output = run(&process_task_builder)?;
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout)?,
+            format!("hello world||{}|input", current_dir.display())
+        );
+        assert_eq!(output.stderr, b"error\n");
+
+        let mut context = TaskContext::default();
+        context.task_id(7).record_id(9);
+        let mut process_task_builder = ProcessTaskBuilder::new("sh");
+        process_task_builder
+            .add_args(["-c", "echo \"$0 $RECORD\"", "{task_id}; {attempt}"])
+            .add_env("RECORD", "record-{record_id}");
+        let output = smol::block_on(async {
+            let (child_guard, stdin_writer) =
+                process_task_builder.spawn::<Child, Command>(&context)?;
+            process_task_builder
+                .wait_with_output(child_guard, stdin_writer)
+                .await
+        })?;
+        assert_eq!(output.output.stdout, b"7; 1 record-9\n");
+
+        let mut process_task_builder = ProcessTaskBuilder::new("printf");
+        process_task_builder
+            .add_arg("0123456789")
+            .remove_env("HOME")
+            .set_output_limit(4);
+        assert_eq!(run(&process_task_builder)?.stdout, b"6789");
+
+        let process_task_builder = ProcessTaskBuilder::new("not-existing-program-of-delay-timer");
+        assert!(run(&process_task_builder).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_kill_grace_period() -> anyhow::Result<()> {
+        use super::ProcessTaskBuilder;
+        use crate::prelude::*;
+        use smol::process::{Child, Command};
+        use std::time::{Duration, Instant};
+        use std::{env, fs, process, thread};
+
+        let wait_until = |condition: &dyn Fn() -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while !condition() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(50));
+            }
+        };
+
+        let file = env::temp_dir().join(format!("delay_timer_process_{}", process::id()));
+        let _ = fs::remove_file(&file);
+        let script = format!(
+            "exec 2> /dev/null; trap 'echo terminated > {file}; exit 0' TERM; echo ready > {file}; while true; do sleep 0.1; done",
+            file = file.display()
+        );
+
+        let mut process_task_builder = ProcessTaskBuilder::new("sh");
+        process_task_builder
+            .add_args(["-c", &script])
+            .set_kill_grace_period(Duration::from_secs(3));
+
+        let (child_guard, _) =
+            process_task_builder.spawn::<Child, Command>(&TaskContext::default())?;
+        // The marker is written after the trap is set. The stderr is discarded,
+        // otherwise reporting the terminated `sleep` to the pipe closed with the guard raises SIGPIPE.
+        wait_until(&|| fs::read_to_string(&file).unwrap_or_default() == "ready\n");
+        assert_eq!(fs::read_to_string(&file)?, "ready\n");
+        drop(child_guard);
+
+        wait_until(&|| fs::read_to_string(&file).unwrap_or_default() == "terminated\n");
+        assert_eq!(fs::read_to_string(&file)?, "terminated\n");
+        fs::remove_file(&file)?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_process_group() -> anyhow::Result<()> {
+        use super::ProcessTaskBuilder;
+        use crate::prelude::*;
+        use smol::process::{Child, Command};
+        use std::time::{Duration, Instant};
+        use std::{env, fs, process, thread};
+
+        let wait_until = |condition: &dyn Fn() -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(3);
+            while !condition() && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(50));
+            }
+        };
+        // The exited process may be a zombie that isn't reaped by the init process.
+        let is_running = |id: &str| {
+            fs::read_to_string(format!("/proc/{}/stat", id))
+                .map(|stat| !stat.rsplit(") ").next().unwrap_or("").starts_with('Z'))
+                .unwrap_or(false)
+        };
+
+        let file = env::temp_dir().join(format!("delay_timer_process_group_{}", process::id()));
+        let script = format!("sleep 30 & echo $! > {}; wait", file.display());
+
+        let mut process_task_builder = ProcessTaskBuilder::new("sh");
+        process_task_builder.add_args(["-c", &script]);
+
+        let (child_guard, _) =
+            process_task_builder.spawn::<Child, Command>(&TaskContext::default())?;
+        wait_until(&|| {
+            fs::read_to_string(&file)
+                .unwrap_or_default()
+                .ends_with('\n')
+        });
+        let grandchild_id = fs::read_to_string(&file)?.trim().to_string();
+        assert!(is_running(&grandchild_id));
+
+        // The grandchild is killed with the process group.
+        drop(child_guard);
+        wait_until(&|| !is_running(&grandchild_id));
+        assert!(!is_running(&grandchild_id));
+
+        fs::remove_file(&file)?;
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resource_limits() -> anyhow::Result<()> {
+        use super::ProcessTaskBuilder;
+        use crate::prelude::*;
+        use smol::process::{Child, Command};
+        use std::time::Duration;
+
+        let mut process_task_builder = ProcessTaskBuilder::new("sh");
+        process_task_builder
+            .add_args([
+                "-c",
+                "ulimit -t; ulimit -n; ulimit -v; nice; grep 'Max processes' /proc/self/limits",
+            ])
+            .set_resource_limits(
+                ResourceLimits::default()
+                    .cpu_time(Duration::from_millis(9500))
+                    .open_files(64)
+                    .address_space(1024 * 1024 * 1024)
+                    .processes(4096)
+                    .nice(5)
+                    .io_priority(IoPriority::BestEffort(7)),
+            );
+
+        let pipeline_output = smol::block_on(async {
+            let (child_guard, stdin_writer) =
+                process_task_builder.spawn::<Child, Command>(&TaskContext::default())?;
+            process_task_builder
+                .wait_with_output(child_guard, stdin_writer)
+                .await
+        })?;
+        let stdout = String::from_utf8(pipeline_output.output.stdout)?;
+        let lines: Vec<Vec<&str>> = stdout
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(lines[..4], [["10"], ["64"], ["1048576"], ["5"]]);
+        assert_eq!(lines[4][2..4], ["4096", "4096"]);
+
+        // The usage includes the waited-for descendants of the process.
+        assert_eq!(pipeline_output.stage_resource_usages.len(), 1);
+        let resource_usage = pipeline_output.stage_resource_usages[0]
+            .ok_or_else(|| anyhow::anyhow!("Without resource usage."))?;
+        assert!(resource_usage.max_rss > 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+//! resource
+//! It is a module that bounds the resources of process tasks, and accounts the usage of them.
+//!
+//! The limits are applied in the child before it executes the program,
+//! and the usage is collected when the child exits, both are only supported on linux.
+use std::io;
+use std::time::Duration;
+
+/// The scheduling class and priority of I/O, like `ionice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoPriority {
+    /// The process gets the first access to the disk, the level is 0 (highest) to 7 (lowest).
+    RealTime(u8),
+    /// The default class, the level is 0 (highest) to 7 (lowest).
+    BestEffort(u8),
+    /// The process only gets the disk when no other process needs it.
+    Idle,
+}
+
+/// The limits of resources of the process task (linux only).
+///
+/// The rlimits are set as both the soft and hard limits, they're inherited by the descendants of process.
+/// The child fails to spawn if a limit is higher than the hard limit of the current process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    cpu_time: Option<u64>,
+    address_space: Option<u64>,
+    open_files: Option<u64>,
+    processes: Option<u64>,
+    nice: Option<i32>,
+    io_priority: Option<IoPriority>,
+}
+
+impl ResourceLimits {
+    /// Set the CPU time that the process can consume (`RLIMIT_CPU`), it's rounded up to seconds.
+    pub fn cpu_time(mut self, cpu_time: Duration) -> Self {
+        let seconds = cpu_time.as_secs() + u64::from(cpu_time.subsec_nanos() > 0);
+        self.cpu_time = Some(seconds);
+        self
+    }
+
+    /// Set the maximum bytes of the virtual memory of process (`RLIMIT_AS`).
+    pub fn address_space(mut self, address_space: u64) -> Self {
+        self.address_space = Some(address_space);
+        self
+    }
+
+    /// Set the maximum number of file descriptors that the process can open (`RLIMIT_NOFILE`).
+    pub fn open_files(mut self, open_files: u64) -> Self {
+        self.open_files = Some(open_files);
+        self
+    }
+
+    /// Set the maximum number of processes of the user that the process can create (`RLIMIT_NPROC`).
+    pub fn processes(mut self, processes: u64) -> Self {
+        self.processes = Some(processes);
+        self
+    }
+
+    /// Set the niceness of process, it's from -20 (highest priority) to 19 (lowest priority).
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Set the scheduling class and priority of I/O of the process.
+    pub fn io_priority(mut self, io_priority: IoPriority) -> Self {
+        self.io_priority = Some(io_priority);
+        self
+    }
+
+    // It runs in the child after `fork`, only the async-signal-safe functions can be called.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        let rlimits = [
+            (libc::RLIMIT_CPU, self.cpu_time),
+            (libc::RLIMIT_AS, self.address_space),
+            (libc::RLIMIT_NOFILE, self.open_files),
+            (libc::RLIMIT_NPROC, self.processes),
+        ];
+
+        // Safety: these functions only read the arguments.
+        unsafe {
+            for (resource, limit) in rlimits.iter() {
+                if let Some(limit) = limit {
+                    let rlimit = libc::rlimit {
+                        rlim_cur: *limit as libc::rlim_t,
+                        rlim_max: *limit as libc::rlim_t,
+                    };
+                    if libc::setrlimit(*resource, &rlimit) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+            }
+
+            if let Some(nice) = self.nice {
+                if libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            if let Some(io_priority) = self.io_priority {
+                // The constants are from `linux/ioprio.h`.
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+                let (class, level) = match io_priority {
+                    IoPriority::RealTime(level) => (1, level),
+                    IoPriority::BestEffort(level) => (2, level),
+                    IoPriority::Idle => (3, 0),
+                };
+                let ioprio = (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level.min(7));
+                if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The resources used by the process and its waited-for descendants.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// The CPU time spent in user mode.
+    pub user_time: Duration,
+    /// The CPU time spent in kernel mode.
+    pub system_time: Duration,
+    /// The maximum resident set size in bytes.
+    pub max_rss: u64,
+    /// The number of blocks read from the file system.
+    pub block_input: u64,
+    /// The number of blocks written to the file system.
+    pub block_output: u64,
+}
+
+/// Wait for the child to exit without reaping it, and get the resources it used (linux only),
+/// the child should be reaped by its handle after that.
+///
+/// It blocks the current thread until the child exits.
+#[cfg(target_os = "linux")]
+pub(crate) fn wait_resource_usage(pid: u32) -> io::Result<Option<ResourceUsage>> {
+    // Safety: the zeroed structs are valid, and they're only written by the kernel.
+    // The raw syscall is used since the wrapper of libc doesn't expose the usage.
+    let rusage = unsafe {
+        let mut siginfo: libc::siginfo_t = std::mem::zeroed();
+        let mut rusage: libc::rusage = std::mem::zeroed();
+        loop {
+            let result = libc::syscall(
+                libc::SYS_waitid,
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut siginfo as *mut libc::siginfo_t,
+                libc::WEXITED | libc::WNOWAIT,
+                &mut rusage as *mut libc::rusage,
+            );
+            if result == 0 {
+                break rusage;
+            }
+
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    };
+
+    let duration = |time: libc::timeval| {
+        Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+    };
+    Ok(Some(ResourceUsage {
+        user_time: duration(rusage.ru_utime),
+        system_time: duration(rusage.ru_stime),
+        // It's in kilobytes on linux.
+        max_rss: rusage.ru_maxrss as u64 * 1024,
+        block_input: rusage.ru_inblock as u64,
+        block_output: rusage.ru_oublock as u64,
+    }))
+}
+
+/// The usage isn't collected on the other platforms.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn wait_resource_usage(_pid: u32) -> io::Result<Option<ResourceUsage>> {
+    Ok(None)
+}
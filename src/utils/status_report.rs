@@ -53,6 +53,8 @@ pub struct PublicFinishTaskBody {
 pub enum PublicFinishOutput {
     /// The output generated when the process task is completed.
     ProcessOutput(std::process::Output),
+    /// The output generated when the pipeline of process task is completed.
+    PipelineOutput(PipelineOutput),
     /// Exception output for a task that did not run successfully.
     ExceptionOutput(String),
 }
@@ -101,6 +103,7 @@ impl From<FinishOutput> for PublicFinishOutput{
     fn from(value:FinishOutput) -> Self{
         match value{
             FinishOutput::ProcessOutput(o) => PublicFinishOutput::ProcessOutput(o),
+            FinishOutput::PipelineOutput(o) => PublicFinishOutput::PipelineOutput(*o),
             FinishOutput::ExceptionOutput(o) => PublicFinishOutput::ExceptionOutput(o) 
         }
     }
@@ -115,8 +118,16 @@ pub enum PublicEvent {
     RunningTask(u64, i64),
     /// Describe which task instance completed.
     FinishTask(PublicFinishTaskBody),
+    /// Describe which task instance failed.
+    FailTask(PublicFinishTaskBody),
     /// Describe which task instance timeout .
     TimeoutTask(u64, i64),
+    /// Describe which task instance is cancelled.
+    CancelTask(u64, i64),
+    /// Describes which task is paused.
+    PauseTask(u64),
+    /// Describes which task is resumed.
+    ResumeTask(u64),
 }
 
 impl TryFrom<&TimerEvent> for PublicEvent {
@@ -128,6 +139,9 @@ impl TryFrom<&TimerEvent> for PublicEvent {
             TimerEvent::AppendTaskHandle(_, delay_task_handler_box) => {
                 Ok(PublicEvent::RunningTask(delay_task_handler_box.get_task_id(), delay_task_handler_box.get_record_id()))
             }
+            TimerEvent::FinishTask(finish_task_body) if finish_task_body.state == state::instance::FAILED => {
+                Ok(PublicEvent::FailTask(finish_task_body.clone().into()))
+            }
             TimerEvent::FinishTask(finish_task_body) => {
                 // TODO: Be wary, clone can involve a lot of memory and consume performance.
                 Ok(PublicEvent::FinishTask(finish_task_body.clone().into()))
@@ -137,7 +151,15 @@ impl TryFrom<&TimerEvent> for PublicEvent {
                 Ok(PublicEvent::TimeoutTask(*task_id, *record_id))
             }
 
-            _ => Err("PublicEvent only accepts timer_event some variant( RemoveTask, CancelTask ,FinishTask )!"),
+            TimerEvent::CancelTask(task_id, record_id) => {
+                Ok(PublicEvent::CancelTask(*task_id, *record_id))
+            }
+
+            TimerEvent::Acknowledged(timer_event, _) => PublicEvent::try_from(timer_event.as_ref()),
+            TimerEvent::PauseTask(task_id) => Ok(PublicEvent::PauseTask(*task_id)),
+            TimerEvent::ResumeTask(task_id) => Ok(PublicEvent::ResumeTask(*task_id)),
+
+            _ => Err("PublicEvent only accepts timer_event some variant( RemoveTask, CancelTask ,FinishTask, TimeoutTask, PauseTask, ResumeTask )!"),
         }
     }
 }
@@ -148,8 +170,11 @@ impl PublicEvent {
         match self {
             PublicEvent::RemoveTask(ref task_id) => *task_id,
             PublicEvent::RunningTask(ref task_id, _) => *task_id,
-            PublicEvent::FinishTask(PublicFinishTaskBody{task_id,..}) => *task_id,
+            PublicEvent::FinishTask(PublicFinishTaskBody{task_id,..}) | PublicEvent::FailTask(PublicFinishTaskBody{task_id,..}) => *task_id,
             PublicEvent::TimeoutTask(ref task_id, _) => *task_id,
+            PublicEvent::CancelTask(ref task_id, _) => *task_id,
+            PublicEvent::PauseTask(ref task_id) => *task_id,
+            PublicEvent::ResumeTask(ref task_id) => *task_id,
         }
     }
 
@@ -158,8 +183,10 @@ impl PublicEvent {
         match self {
             PublicEvent::RemoveTask(_) => None,
             PublicEvent::RunningTask(_,ref record_id) => Some(*record_id),
-            PublicEvent::FinishTask(PublicFinishTaskBody{record_id,..}) => Some(*record_id),
+            PublicEvent::FinishTask(PublicFinishTaskBody{record_id,..}) | PublicEvent::FailTask(PublicFinishTaskBody{record_id,..}) => Some(*record_id),
             PublicEvent::TimeoutTask(_,ref record_id) => Some(*record_id),
+            PublicEvent::CancelTask(_,ref record_id) => Some(*record_id),
+            PublicEvent::PauseTask(_) | PublicEvent::ResumeTask(_) => None,
       
         }
     }
@@ -0,0 +1,180 @@
+//! task_spec
+//! It is a module that defines tasks by data,
+//! the task body is looked up by the handler name in the registry of `DelayTimer`.
+//!
+//! # Required features
+//!
+//! This module requires the `task-spec` feature of the `delay_timer` crate to be enabled.
+use crate::prelude::*;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub(crate) type SafeHandlerFn =
+    Arc<dyn Fn(TaskContext, &Value) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync>;
+
+/// The frequency of `TaskSpec`, it can be serialized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrequencySpec {
+    /// Repeat once.
+    Once(String),
+    /// Repeat ad infinitum.
+    Repeated(String),
+    /// Type of countdown.
+    CountDown(u32, String),
+}
+
+/// The serializable definition of a task, the task body is the handler registered by `handler`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSpec {
+    /// The id of task.
+    #[serde(default)]
+    pub task_id: u64,
+    /// The name of the registered handler.
+    pub handler: String,
+    /// The payload passed to the handler every time the task runs.
+    #[serde(default)]
+    pub payload: Value,
+    /// The frequency of task.
+    pub frequency: FrequencySpec,
+    /// Maximum execution time.
+    #[serde(default)]
+    pub maximum_running_time: Option<u64>,
+    /// Maximum parallel runable num.
+    #[serde(default)]
+    pub maximun_parallel_runable_num: Option<u64>,
+    /// The name of task.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The labels of task.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+/// The handler name and payload that the task is built from.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HandlerSpec {
+    pub(crate) handler: String,
+    pub(crate) payload: Value,
+}
+
+/// The registry of named handlers, it's shared by `DelayTimerBuilder` and `DelayTimer`.
+#[derive(Clone, Default)]
+pub(crate) struct HandlerRegistry {
+    handlers: Arc<DashMap<String, SafeHandlerFn>>,
+}
+
+impl fmt::Debug for HandlerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|handler| handler.key().clone()))
+            .finish()
+    }
+}
+
+impl HandlerRegistry {
+    pub(crate) fn register<F>(&self, name: String, handler: F)
+    where
+        F: Fn(TaskContext, &Value) -> Box<dyn DelayTaskHandler> + 'static + Send + Sync,
+    {
+        self.handlers.insert(name, Arc::new(handler));
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<SafeHandlerFn> {
+        self.handlers
+            .get(name)
+            .map(|handler| handler.value().clone())
+    }
+
+    pub(crate) fn spawn_task(&self, task_spec: &TaskSpec) -> Result<Task, TaskError> {
+        let handler = self
+            .get(&task_spec.handler)
+            .ok_or_else(|| TaskError::HandlerNotFound(task_spec.handler.clone()))?;
+
+        let frequency = match &task_spec.frequency {
+            FrequencySpec::Once(cron_expression) => Frequency::Once(cron_expression),
+            FrequencySpec::Repeated(cron_expression) => Frequency::Repeated(cron_expression),
+            FrequencySpec::CountDown(count, cron_expression) => {
+                Frequency::CountDown(*count, cron_expression)
+            }
+        };
+
+        let mut task_builder = TaskBuilder::default();
+        task_builder
+            .set_frequency(frequency)
+            .set_task_id(task_spec.task_id);
+
+        if let Some(maximum_running_time) = task_spec.maximum_running_time {
+            task_builder.set_maximum_running_time(maximum_running_time);
+        }
+
+        if let Some(maximun_parallel_runable_num) = task_spec.maximun_parallel_runable_num {
+            task_builder.set_maximun_parallel_runable_num(maximun_parallel_runable_num);
+        }
+
+        if let Some(name) = task_spec.name.as_deref() {
+            task_builder.set_name(name);
+        }
+
+        for (key, value) in task_spec.labels.iter() {
+            task_builder.add_label(key, value);
+        }
+
+        let payload = task_spec.payload.clone();
+        let mut task = task_builder.spawn(move |context| handler(context, &payload))?;
+        task.handler_spec = Some(HandlerSpec {
+            handler: task_spec.handler.clone(),
+            payload: task_spec.payload.clone(),
+        });
+        Ok(task)
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_handler_registry() -> anyhow::Result<()> {
+        use super::{FrequencySpec, HandlerRegistry, TaskSpec};
+        use crate::prelude::*;
+
+        let task_spec: TaskSpec = serde_json::from_str(
+            r#"{
+                "task_id": 3,
+                "handler": "send_report",
+                "payload": {"to": "ops"},
+                "frequency": {"CountDown": [2, "@hourly"]},
+                "labels": {"team": "billing"}
+            }"#,
+        )?;
+        assert_eq!(
+            task_spec.frequency,
+            FrequencySpec::CountDown(2, String::from("@hourly"))
+        );
+
+        let handler_registry = HandlerRegistry::default();
+        assert!(matches!(
+            handler_registry.spawn_task(&task_spec),
+            Err(TaskError::HandlerNotFound(_))
+        ));
+
+        handler_registry.register(String::from("send_report"), |_, payload| {
+            assert_eq!(payload["to"], "ops");
+            create_default_delay_task_handler()
+        });
+        let task = handler_registry.spawn_task(&task_spec)?;
+        assert_eq!(task.task_id, 3);
+        assert_eq!(task.get_remaining_count(), Some(2));
+        assert_eq!(
+            task.get_labels().get("team").map(String::as_str),
+            Some("billing")
+        );
+
+        (task.get_body())(TaskContext::default());
+
+        Ok(())
+    }
+}
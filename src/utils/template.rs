@@ -0,0 +1,147 @@
+//! template
+//! It is a module that renders the placeholders in the commands of process tasks
+//! from the `TaskContext` of the task running instance.
+//!
+//! | Placeholder | Value |
+//! | --- | --- |
+//! | `{task_id}` | The id of task. |
+//! | `{record_id}` | The id of the task running instance. |
+//! | `{attempt}` | The attempt of the task running instance, it starts from 1. |
+//! | `{scheduled_time}` | The time the instance is scheduled to run, in RFC 3339 format. |
+//! | `{scheduled_time:FORMAT}` | The time the instance is scheduled to run, in the `strftime` FORMAT, e.g. `{scheduled_time:%Y-%m-%d}`. |
+//!
+//! The time is in the time zone of task. The other text in braces is kept as it is, e.g. `awk '{print $1}'`.
+//!
+//! The shell commands of `unblock_process_task_fn` are rendered after they're parsed,
+//! so the values are always a part of the word that they're in, they're never parsed as shell syntax.
+//! The arguments and the values of environment variables of `ProcessTaskBuilder` are rendered one by one.
+use crate::prelude::*;
+
+use std::fmt::Write;
+
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+/// Render the placeholders in the template from the context of the task running instance.
+///
+/// ```
+/// use delay_timer::prelude::*;
+///
+/// let mut context = TaskContext::default();
+/// context.task_id(7).record_id(9);
+///
+/// let rendered = render_template("backup-{task_id}-{record_id}.tar", &context);
+/// assert_eq!(rendered.unwrap(), "backup-7-9.tar");
+/// ```
+pub fn render_template(template: &str, context: &TaskContext) -> AnyResult<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let placeholder = rest[1..].find('}').map(|end| &rest[1..end + 1]);
+        match placeholder {
+            Some(placeholder) if render_placeholder(&mut rendered, placeholder, context)? => {
+                rest = &rest[placeholder.len() + 2..];
+            }
+            _ => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+// It returns false if the placeholder isn't known.
+fn render_placeholder(
+    rendered: &mut String,
+    placeholder: &str,
+    context: &TaskContext,
+) -> AnyResult<bool> {
+    let mut parts = placeholder.splitn(2, ':');
+    let format = match (parts.next(), parts.next()) {
+        (Some("task_id"), None) => return Ok(push_value(rendered, context.task_id)),
+        (Some("record_id"), None) => return Ok(push_value(rendered, context.record_id)),
+        (Some("attempt"), None) => return Ok(push_value(rendered, context.get_attempt())),
+        (Some("scheduled_time"), format) => format.unwrap_or(DEFAULT_TIME_FORMAT),
+        _ => return Ok(false),
+    };
+
+    let scheduled_time = Utc
+        .timestamp_opt(context.get_scheduled_time() as i64, 0)
+        .single()
+        .ok_or_else(|| anyhow!("Invalid scheduled time {}.", context.get_scheduled_time()))?;
+
+    // The invalid format is reported by the error of formatting.
+    let formatted = match context.time_zone {
+        ScheduleIteratorTimeZone::Utc => write!(rendered, "{}", scheduled_time.format(format)),
+        ScheduleIteratorTimeZone::Local => write!(
+            rendered,
+            "{}",
+            scheduled_time.with_timezone(&Local).format(format)
+        ),
+        ScheduleIteratorTimeZone::FixedOffset(offset) => write!(
+            rendered,
+            "{}",
+            scheduled_time.with_timezone(&offset).format(format)
+        ),
+    };
+    formatted.map_err(|_| anyhow!("Invalid time format `{}` of `{{{}}}`.", format, placeholder))?;
+    Ok(true)
+}
+
+fn push_value<T: ToString>(rendered: &mut String, value: T) -> bool {
+    rendered.push_str(&value.to_string());
+    true
+}
+
+mod tests {
+
+    #[test]
+    fn test_render_template() -> anyhow::Result<()> {
+        use super::render_template;
+        use crate::prelude::*;
+
+        let mut context = TaskContext::default();
+        context
+            .task_id(7)
+            .record_id(9)
+            .attempt(1)
+            .scheduled_time(86_400)
+            .time_zone(ScheduleIteratorTimeZone::Utc);
+
+        assert_eq!(
+            render_template(
+                "{task_id}/{record_id}/{attempt}/{scheduled_time:%Y-%m-%d}",
+                &context
+            )?,
+            "7/9/1/1970-01-02"
+        );
+        assert_eq!(
+            render_template("{scheduled_time}", &context)?,
+            "1970-01-02T00:00:00+00:00"
+        );
+
+        // The unknown placeholders are kept.
+        let template = "awk '{print $1}' {} {task_id {{task_id}}";
+        assert_eq!(
+            render_template(template, &context)?,
+            "awk '{print $1}' {} {task_id {7}"
+        );
+
+        context.time_zone(ScheduleIteratorTimeZone::FixedOffset(
+            FixedOffset::east_opt(3600).unwrap(),
+        ));
+        assert_eq!(
+            render_template("{scheduled_time:%H:%M}", &context)?,
+            "01:00"
+        );
+
+        assert!(render_template("{scheduled_time:%Q}", &context).is_err());
+        Ok(())
+    }
+}
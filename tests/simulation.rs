@@ -190,6 +190,296 @@ fn go_works() -> AnyResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_async_fn_body_macros() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    #[cfg(feature = "tokio-support")]
+    let tokio_delay_timer = DelayTimerBuilder::default().tokio_runtime(None).build();
+    let share_num = Arc::new(AtomicUsize::new(0));
+
+    // Both macros accept the same syntax, so every body is built by both of them.
+    macro_rules! add_tasks {
+        ($task_id:expr, $(($($capture_variable:ident),+))? $body:block) => {
+            {
+                $($(let $capture_variable = $capture_variable.clone();)+)?
+                let task = TaskBuilder::default()
+                    .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+                    .set_task_id($task_id)
+                    .spawn(create_async_fn_body!($(($($capture_variable),+))? $body))?;
+                delay_timer.add_task(task)?;
+            }
+
+            #[cfg(feature = "tokio-support")]
+            {
+                $($(let $capture_variable = $capture_variable.clone();)+)?
+                let task = TaskBuilder::default()
+                    .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+                    .set_task_id($task_id)
+                    .spawn(create_async_fn_tokio_body!($(($($capture_variable),+))? $body))?;
+                tokio_delay_timer.add_task(task)?;
+            }
+        };
+    }
+
+    add_tasks!(1, {
+        Timer::after(Duration::from_millis(100)).await;
+    });
+    add_tasks!(2, (share_num) {
+        Timer::after(Duration::from_millis(100)).await;
+        share_num_ref.fetch_add(1, Release);
+    });
+
+    park_timeout(Duration::from_secs(3));
+    let bodies_num = if cfg!(feature = "tokio-support") {
+        2
+    } else {
+        1
+    };
+    assert_eq!(share_num.load(Acquire), bodies_num);
+    Ok(())
+}
+
+#[test]
+fn test_spawn_async_and_blocking() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    let share_num = Arc::new(AtomicUsize::new(0));
+
+    let share_num_bunshin = share_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn_async(move |_| {
+            let share_num = share_num_bunshin.clone();
+            async move {
+                Timer::after(Duration::from_millis(100)).await;
+                share_num.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(2)
+        .spawn_async(|context| async move {
+            Err::<(), _>(anyhow!("The instance {} failed.", context.record_id))
+        })?;
+    delay_timer.add_task(task)?;
+
+    let share_num_bunshin = share_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(3)
+        .spawn_blocking(move |_| {
+            thread::sleep(Duration::from_millis(100));
+            share_num_bunshin.fetch_add(1, Release);
+        })?;
+    delay_timer.add_task(task)?;
+
+    // The panic of closure fails the instance instead of leaving it running.
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(6)
+        .spawn_blocking(|_| -> () { panic!("boom") })?;
+    delay_timer.add_task(task)?;
+
+    // The instance of task added without `TaskInstancesChain` is notified as well,
+    // the closure stops when the instance is timeout.
+    let timeout_num = Arc::new(AtomicUsize::new(0));
+    let timeout_num_bunshin = timeout_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(5)
+        .set_maximum_running_time(1)
+        .spawn_blocking(move |context| {
+            while !context.is_cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            timeout_num_bunshin.fetch_add(1, Release);
+        })?;
+    delay_timer.add_task(task)?;
+
+    // The blocking closure stops early when the instance is cancelled,
+    // it's counted when it starts and when it stops.
+    let blocking_num = Arc::new(AtomicUsize::new(0));
+    let blocking_num_bunshin = blocking_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(4)
+        .spawn_blocking(move |context| {
+            blocking_num_bunshin.fetch_add(1, Release);
+            while !context.is_cancelled() {
+                thread::sleep(Duration::from_millis(10));
+            }
+            blocking_num_bunshin.fetch_add(1, Release);
+        })?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // The closure never runs if the instance is cancelled before it starts.
+    let instance = task_instance_chain.next_with_wait()?;
+    while blocking_num.load(Acquire) == 0 {
+        park_timeout(Duration::from_millis(10));
+    }
+    instance.cancel_with_wait()?;
+
+    park_timeout(Duration::from_millis(500));
+    assert_eq!(share_num.load(Acquire), 2);
+    assert_eq!(blocking_num.load(Acquire), 2);
+
+    let records = delay_timer.history(1, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::COMPLETED);
+    let records = delay_timer.history(2, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::FAILED);
+    let error = format!("The instance {} failed.", records[0].record_id);
+    assert_eq!(records[0].error.as_deref(), Some(error.as_str()));
+    let records = delay_timer.history(4, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::CANCELLED);
+    let records = delay_timer.history(6, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::FAILED);
+    assert!(records[0]
+        .error
+        .as_deref()
+        .unwrap_or_default()
+        .contains("panicked"));
+
+    park_timeout(Duration::from_secs(2));
+    assert_eq!(timeout_num.load(Acquire), 1);
+    let records = delay_timer.history(5, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::TIMEOUT);
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio-support")]
+#[test]
+fn test_spawn_async_by_tokio() -> AnyResult<()> {
+    let delay_timer = DelayTimerBuilder::default().tokio_runtime(None).build();
+    let share_num = Arc::new(AtomicUsize::new(0));
+
+    // The future is spawned on tokio, so the timer of tokio works in it.
+    let share_num_bunshin = share_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn_async(move |_| {
+            let share_num = share_num_bunshin.clone();
+            async move {
+                sleep_by_tokio(Duration::from_millis(100)).await;
+                share_num.fetch_add(1, Release);
+            }
+        })?;
+    delay_timer.add_task(task)?;
+
+    let share_num_bunshin = share_num.clone();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(2)
+        .spawn_blocking(move |_| {
+            share_num_bunshin.fetch_add(1, Release);
+        })?;
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_secs(3));
+    assert_eq!(share_num.load(Acquire), 2);
+    Ok(())
+}
+
+#[test]
+fn test_spawn_job() -> AnyResult<()> {
+    // The job counts the calls of `run` and hooks, in the order of `run`, success, failure, timeout and cancel.
+    #[derive(Default)]
+    struct TestJob {
+        running_time: Duration,
+        fail: bool,
+        calls: [AtomicUsize; 5],
+    }
+
+    impl TestJob {
+        fn calls(&self) -> Vec<usize> {
+            self.calls.iter().map(|call| call.load(Acquire)).collect()
+        }
+    }
+
+    #[async_trait]
+    impl Job for TestJob {
+        type Output = ();
+
+        async fn run(&self, _context: TaskContext) -> AnyResult<()> {
+            self.calls[0].fetch_add(1, Release);
+            Timer::after(self.running_time).await;
+            if self.fail {
+                return Err(anyhow!("The job failed."));
+            }
+            Ok(())
+        }
+
+        async fn on_success(&self, _context: &TaskContext, _output: &()) {
+            self.calls[1].fetch_add(1, Release);
+        }
+
+        async fn on_failure(&self, _context: &TaskContext, _error: &anyhow::Error) {
+            self.calls[2].fetch_add(1, Release);
+        }
+
+        async fn on_timeout(&self, _context: &TaskContext) {
+            self.calls[3].fetch_add(1, Release);
+        }
+
+        async fn on_cancel(&self, _context: &TaskContext) {
+            self.calls[4].fetch_add(1, Release);
+        }
+    }
+
+    let delay_timer = DelayTimer::new();
+    let success_job = Arc::new(TestJob::default());
+    let failure_job = Arc::new(TestJob {
+        fail: true,
+        ..Default::default()
+    });
+    let timeout_job = Arc::new(TestJob {
+        running_time: Duration::from_secs(5),
+        ..Default::default()
+    });
+    let cancel_job = Arc::new(TestJob {
+        running_time: Duration::from_secs(5),
+        ..Default::default()
+    });
+
+    let jobs = [&success_job, &failure_job, &timeout_job];
+    for (task_id, job) in (1..).zip(jobs.iter()) {
+        let task = TaskBuilder::default()
+            .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+            .set_task_id(task_id)
+            .set_maximum_running_time(1)
+            .spawn_job((*job).clone())?;
+        delay_timer.add_task(task)?;
+    }
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(4)
+        .spawn_job(cancel_job.clone())?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // The instance is cancelled after `run` is called.
+    let instance = task_instance_chain.next_with_wait()?;
+    while cancel_job.calls()[0] == 0 {
+        park_timeout(Duration::from_millis(10));
+    }
+    instance.cancel_with_wait()?;
+
+    park_timeout(Duration::from_secs(3));
+    assert_eq!(success_job.calls(), [1, 1, 0, 0, 0]);
+    assert_eq!(failure_job.calls(), [1, 0, 1, 0, 0]);
+    assert_eq!(timeout_job.calls(), [1, 0, 0, 1, 0]);
+    assert_eq!(cancel_job.calls(), [1, 0, 0, 0, 1]);
+
+    let records = delay_timer.history(2, HistoryFilter::default())?;
+    assert_eq!(records[0].state, instance::FAILED);
+    assert_eq!(records[0].error.as_deref(), Some("The job failed."));
+
+    Ok(())
+}
+
 #[test]
 fn test_advance() -> AnyResult<()> {
     // The task is executed in the next hour.
@@ -223,6 +513,980 @@ fn test_advance() -> AnyResult<()> {
     Ok(())
 }
 
+#[test]
+fn test_pause_tasks_by_label() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    let share_num = Arc::new(AtomicUsize::new(0));
+    let share_num_bunshin = share_num.clone();
+
+    let body = move |_| {
+        share_num_bunshin.fetch_add(1, Release);
+        create_default_delay_task_handler()
+    };
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Secondly))
+        .set_task_id(1)
+        .set_name("counter")
+        .add_label("team", "billing")
+        .spawn(body)?;
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_secs_f32(2.5));
+    delay_timer.pause_tasks(TaskSelector::default().label("team", "billing"))?;
+    park_timeout(Duration::from_secs_f32(1.1));
+
+    // The paused task doesn't derive new running instances.
+    let paused_num = share_num.load(Acquire);
+    assert!(paused_num > 0);
+    assert!(delay_timer.get_task_info(1).unwrap().paused);
+
+    park_timeout(Duration::from_secs(2));
+    assert_eq!(share_num.load(Acquire), paused_num);
+
+    delay_timer.resume_tasks(TaskSelector::default().name("counter"))?;
+    park_timeout(Duration::from_secs_f32(2.1));
+    assert!(share_num.load(Acquire) > paused_num);
+
+    delay_timer.remove_tasks(TaskSelector::default().label("team", "billing"))?;
+    park_timeout(Duration::from_secs_f32(1.1));
+    assert!(delay_timer.get_task_info(1).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_task_id() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    let mut task_builder = TaskBuilder::default();
+    task_builder
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Hourly))
+        .set_task_id(1);
+
+    assert_eq!(
+        delay_timer.add_task(task_builder.spawn(|_| create_default_delay_task_handler())?)?,
+        1
+    );
+    assert!(matches!(
+        delay_timer.add_task(task_builder.spawn(|_| create_default_delay_task_handler())?),
+        Err(TaskError::DuplicateTaskId(1))
+    ));
+
+    // The id of the task that has run out is released, its mark is removed after the instance finishes.
+    let mut once_task_builder = TaskBuilder::default();
+    once_task_builder
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(2);
+    delay_timer.add_task(once_task_builder.spawn(create_async_fn_body!({}))?)?;
+    let released = (0..50).any(|_| {
+        park_timeout(Duration::from_millis(100));
+        delay_timer.get_task_info(2).is_none()
+            && !delay_timer
+                .render_metrics()
+                .contains("delay_timer_running_instances{task_id=\"2\"}")
+    });
+    assert!(released);
+    assert_eq!(
+        delay_timer.add_task(once_task_builder.spawn(create_async_fn_body!({}))?)?,
+        2
+    );
+
+    let delay_timer = DelayTimerBuilder::default()
+        .duplicate_task_policy(DuplicateTaskPolicy::Replace)
+        .build();
+    delay_timer.add_task(task_builder.spawn(|_| create_default_delay_task_handler())?)?;
+    delay_timer.pause_task(1)?;
+
+    task_builder.set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Daily));
+    delay_timer.add_task(task_builder.spawn(|_| create_default_delay_task_handler())?)?;
+    park_timeout(Duration::from_millis(100));
+    let task_info = delay_timer.get_task_info(1).unwrap();
+    assert_eq!(task_info.cron_expression, "@daily");
+    assert!(task_info.paused);
+
+    // The replaced task keeps the chain of task instances.
+    let mut secondly_task_builder = TaskBuilder::default();
+    secondly_task_builder
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Secondly))
+        .set_task_id(3);
+    let task_instances_chain =
+        delay_timer.insert_task(secondly_task_builder.spawn(create_async_fn_body!({}))?)?;
+    delay_timer.add_task(secondly_task_builder.spawn(create_async_fn_body!({}))?)?;
+    park_timeout(Duration::from_secs(2));
+    assert!(task_instances_chain.next().is_ok());
+    delay_timer.remove_task(3)?;
+
+    let delay_timer = DelayTimerBuilder::default()
+        .enable_task_id_allocation()
+        .build();
+    let first_task_id =
+        delay_timer.add_task(task_builder.spawn(|_| create_default_delay_task_handler())?)?;
+    let task_instances_chain =
+        delay_timer.insert_task(task_builder.spawn(|_| create_default_delay_task_handler())?)?;
+    assert_ne!(first_task_id, task_instances_chain.get_task_id());
+
+    Ok(())
+}
+
+#[test]
+fn test_acknowledged_operations() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Hourly))
+        .set_task_id(1)
+        .spawn(|_| create_default_delay_task_handler())?;
+    assert_eq!(delay_timer.add_task_with_ack(task)?, 1);
+
+    delay_timer.advance_task_with_ack(1)?;
+    assert!(matches!(
+        delay_timer.cancel_task_with_ack(1, 0),
+        Err(TaskError::InstanceNotFound(1, 0))
+    ));
+    delay_timer.remove_task_with_ack(1)?;
+
+    assert!(matches!(
+        delay_timer.remove_task_with_ack(1),
+        Err(TaskError::TaskNotFound(1))
+    ));
+    assert!(matches!(
+        future_lite::block_on(delay_timer.advance_task_with_async_ack(2)),
+        Err(TaskError::TaskNotFound(2))
+    ));
+
+    // The year 2000 has passed.
+    let task = TaskBuilder::default()
+        .set_frequency(Frequency::Once("0 0 0 1 1 * 2000"))
+        .set_task_id(3)
+        .spawn(|_| create_default_delay_task_handler())?;
+    assert!(matches!(
+        delay_timer.add_task_with_ack(task),
+        Err(TaskError::NoFutureFireTime(3))
+    ));
+    assert!(delay_timer.get_task_info(3).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_update_task() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    let build_task = |task_id: u64, expression: &'static str, share_num: &Arc<AtomicUsize>| {
+        let share_num = share_num.clone();
+        TaskBuilder::default()
+            .set_frequency(Frequency::Repeated(expression))
+            .set_task_id(task_id)
+            .spawn(move |_| {
+                share_num.fetch_add(1, Release);
+                create_default_delay_task_handler()
+            })
+    };
+
+    let secondly_num = Arc::new(AtomicUsize::new(0));
+    let yearly_num = Arc::new(AtomicUsize::new(0));
+
+    // The task is moved to the slot of its new schedule, it doesn't fire in the old slot.
+    delay_timer.add_task_with_ack(build_task(1, "@secondly", &secondly_num)?)?;
+    delay_timer.update_task_with_ack(build_task(1, "@yearly", &yearly_num)?)?;
+
+    // The task fires by its new schedule instead of waiting for the old slot.
+    delay_timer.add_task_with_ack(build_task(2, "@yearly", &yearly_num)?)?;
+    delay_timer.update_task_with_ack(build_task(2, "@secondly", &secondly_num)?)?;
+
+    park_timeout(Duration::from_secs_f32(3.1));
+    assert_eq!(yearly_num.load(Acquire), 0);
+    assert!(secondly_num.load(Acquire) >= 2);
+    assert_eq!(
+        delay_timer.get_task_info(1).unwrap().cron_expression,
+        "@yearly"
+    );
+
+    assert!(matches!(
+        delay_timer.update_task_with_ack(build_task(3, "@yearly", &yearly_num)?),
+        Err(TaskError::TaskNotFound(3))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_reconcile() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+    let build_task = |task_id: u64, frequency: Frequency| -> AnyResult<Task> {
+        Ok(TaskBuilder::default()
+            .set_frequency(frequency)
+            .set_task_id(task_id)
+            .spawn(|_| create_default_delay_task_handler())?)
+    };
+
+    delay_timer.add_task_with_ack(build_task(1, Frequency::Repeated("@hourly"))?)?;
+    delay_timer.add_task_with_ack(build_task(2, Frequency::Repeated("@hourly"))?)?;
+    delay_timer.add_task_with_ack(build_task(3, Frequency::Repeated("@hourly"))?)?;
+
+    // The batch with duplicate task ids is rejected as a whole.
+    assert!(matches!(
+        delay_timer.reconcile(vec![
+            build_task(2, Frequency::Repeated("@daily"))?,
+            build_task(4, Frequency::Repeated("@hourly"))?,
+            build_task(4, Frequency::Repeated("@daily"))?,
+        ]),
+        Err(TaskError::DuplicateTaskId(4))
+    ));
+    assert_eq!(
+        delay_timer.get_task_info(2).unwrap().cron_expression,
+        "@hourly"
+    );
+    assert!(delay_timer.get_task_info(3).is_some());
+    assert!(delay_timer.get_task_info(4).is_none());
+
+    let reconcile_report = delay_timer.reconcile(vec![
+        build_task(1, Frequency::Repeated("@hourly"))?,
+        build_task(2, Frequency::Repeated("@daily"))?,
+        build_task(4, Frequency::Repeated("@hourly"))?,
+    ])?;
+
+    assert_eq!(reconcile_report.unchanged, vec![1]);
+    assert_eq!(reconcile_report.updated, vec![2]);
+    assert_eq!(reconcile_report.added, vec![4]);
+    assert_eq!(reconcile_report.removed, vec![3]);
+    assert!(reconcile_report.failed.is_empty());
+
+    assert_eq!(
+        delay_timer.get_task_info(2).unwrap().cron_expression,
+        "@daily"
+    );
+    assert!(delay_timer.get_task_info(3).is_none());
+    assert!(delay_timer.get_task_info(4).is_some());
+
+    // The configured count is compared, but the body that can't be compared is ignored.
+    delay_timer.add_task_with_ack(build_task(5, Frequency::CountDown(3, "@hourly"))?)?;
+    let reconcile_report = delay_timer.reconcile(vec![
+        TaskBuilder::default()
+            .set_frequency(Frequency::Repeated("@hourly"))
+            .set_task_id(1)
+            .spawn(unblock_process_task_fn("echo changed".into()))?,
+        build_task(2, Frequency::Repeated("@daily"))?,
+        build_task(4, Frequency::Repeated("@hourly"))?,
+        build_task(5, Frequency::CountDown(3, "@hourly"))?,
+    ])?;
+    assert_eq!(reconcile_report.unchanged, vec![1, 2, 4, 5]);
+
+    let reconcile_report = delay_timer.reconcile(vec![
+        build_task(1, Frequency::Repeated("@hourly"))?,
+        build_task(2, Frequency::Repeated("@daily"))?,
+        build_task(4, Frequency::Repeated("@hourly"))?,
+        build_task(5, Frequency::CountDown(10, "@hourly"))?,
+    ])?;
+    assert_eq!(reconcile_report.unchanged, vec![1, 2, 4]);
+    assert_eq!(reconcile_report.updated, vec![5]);
+
+    let reconcile_report = delay_timer.reconcile(vec![])?;
+    reconcile_report.removed.iter().for_each(|task_id| {
+        assert!(delay_timer.get_task_info(*task_id).is_none());
+    });
+    assert_eq!(reconcile_report.removed.len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_process_output() -> AnyResult<()> {
+    let log_dir = std::env::temp_dir().join(format!("delay_timer_logs_{}", std::process::id()));
+    let delay_timer = DelayTimerBuilder::default()
+        .output_log(OutputLogConfig::new(&log_dir))
+        .build();
+
+    let body = unblock_process_task_fn("sleep 1; echo out; echo err >&2".into());
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn(body)?;
+    let task_instance_chain = delay_timer.insert_task(task)?;
+
+    // The receiver is closed after the instance finishes.
+    let output_receiver = task_instance_chain.next_with_wait()?.subscribe_output();
+    let mut output_lines = Vec::new();
+    while let Ok(output_line) = future_lite::block_on(output_receiver.recv()) {
+        output_lines.push(output_line);
+    }
+    assert_eq!(
+        output_lines,
+        [
+            OutputLine {
+                source: OutputSource::Stdout,
+                line: "out".into()
+            },
+            OutputLine {
+                source: OutputSource::Stderr,
+                line: "err".into()
+            }
+        ]
+    );
+
+    let log_files = std::fs::read_dir(log_dir.join("1"))?.collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(log_files.len(), 1);
+    assert_eq!(std::fs::read_to_string(log_files[0].path())?, "out\nerr\n");
+
+    std::fs::remove_dir_all(&log_dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_crontab_entry() -> AnyResult<()> {
+    let file = std::env::temp_dir().join(format!("delay_timer_crontab_{}", std::process::id()));
+    let content = format!(
+        "GREETING = \"hello | world;\"\n@reboot echo \"$GREETING\" > {0}; cat >> {0}%line one%line two\n",
+        file.display()
+    );
+    let crontab = Crontab::parse(&content, CrontabFormat::User)
+        .map_err(|errors| anyhow::anyhow!("{:?}", errors))?;
+
+    let delay_timer = DelayTimer::new();
+    for task in crontab
+        .spawn_tasks(1)
+        .map_err(|errors| anyhow::anyhow!("{:?}", errors))?
+    {
+        delay_timer.add_task(task)?;
+    }
+
+    // The `@reboot` entry runs two seconds later.
+    park_timeout(Duration::from_secs_f32(4.5));
+    assert_eq!(
+        std::fs::read_to_string(&file)?,
+        "hello | world;\nline one\nline two"
+    );
+
+    std::fs::remove_file(&file)?;
+    Ok(())
+}
+
+#[cfg(feature = "status-report")]
+#[test]
+fn test_success_exit_codes() -> AnyResult<()> {
+    use delay_timer::utils::status_report;
+
+    let mut delay_timer = DelayTimerBuilder::default().enable_status_report().build();
+    let status_reporter = delay_timer
+        .take_status_reporter()
+        .ok_or_else(|| anyhow!("Without `status_reporter`."))?;
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(1)
+        .set_success_exit_codes(&[0, 3])
+        .spawn(unblock_process_task_fn("sh -c 'exit 3'".into()))?;
+    delay_timer.add_task(task)?;
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(2)
+        .spawn(unblock_process_task_fn(
+            "set -o pipefail; false | true".into(),
+        ))?;
+    delay_timer.add_task(task)?;
+
+    let mut finish_events = Vec::new();
+    while finish_events.len() < 2 {
+        match status_reporter.next_public_event_with_wait()? {
+            PublicEvent::FinishTask(body) => finish_events.push((body.get_task_id(), false)),
+            PublicEvent::FailTask(mut body) => {
+                if let Some(status_report::PublicFinishOutput::PipelineOutput(pipeline_output)) =
+                    body.get_finish_output()
+                {
+                    assert_eq!(pipeline_output.stage_statuses.len(), 2);
+                    assert_eq!(pipeline_output.output.status.code(), Some(1));
+                }
+                finish_events.push((body.get_task_id(), true))
+            }
+            _ => {}
+        }
+    }
+    finish_events.sort_unstable();
+    assert_eq!(finish_events, [(1, false), (2, true)]);
+
+    // The event is reported before the record is kept.
+    park_timeout(Duration::from_millis(100));
+    let records = delay_timer.history(2, HistoryFilter::default().state(instance::FAILED))?;
+    assert_eq!(records.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_execution_history() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+
+    let body = create_async_fn_body!({
+        Timer::after(Duration::from_millis(100)).await;
+    });
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::CountDown(3, CandyCron::Secondly))
+        .set_task_id(1)
+        .set_history_capacity(2)
+        .spawn(body)?;
+    delay_timer.add_task(task)?;
+
+    let body = create_async_fn_body!({
+        Timer::after(Duration::from_secs(10)).await;
+    });
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(2)
+        .set_maximum_running_time(1)
+        .spawn(body)?;
+    delay_timer.add_task(task)?;
+
+    let task = TaskBuilder::default()
+        .set_frequency(Frequency::CountDown(2, "*/2 * * * * * *"))
+        .set_task_id(3)
+        .spawn(create_async_fn_body!({}))?;
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_secs_f32(4.5));
+
+    // Only the latest 2 records are kept.
+    let records = delay_timer.history(1, HistoryFilter::default())?;
+    assert_eq!(records.len(), 2);
+    assert!(records[0].record_id < records[1].record_id);
+    assert!(records.iter().all(
+        |record| record.state == instance::COMPLETED && record.start_time <= record.finish_time
+    ));
+
+    let records = delay_timer.history(2, HistoryFilter::default().state(instance::TIMEOUT))?;
+    assert_eq!(records.len(), 1);
+
+    // The instance is scheduled at the fire time of the cron expression.
+    let records = delay_timer.history(3, HistoryFilter::default())?;
+    assert!(!records.is_empty());
+    assert!(
+        records
+            .iter()
+            .all(|record| record.scheduled_time % 2 == 0
+                && record.scheduled_time <= record.start_time)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_render_metrics() -> AnyResult<()> {
+    let delay_timer = DelayTimer::new();
+
+    let body = create_async_fn_body!({
+        Timer::after(Duration::from_millis(100)).await;
+    });
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::CountDown(2, CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn(body)?;
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_secs_f32(3.5));
+
+    let metrics = delay_timer.render_metrics();
+    assert!(metrics.contains("delay_timer_instances_started_total{task_id=\"1\"} 2\n"));
+    assert!(metrics.contains("delay_timer_instances_completed_total{task_id=\"1\"} 2\n"));
+    assert!(metrics.contains("delay_timer_run_duration_seconds_count{task_id=\"1\"} 2\n"));
+    // The task that has run out is removed after its last instance finishes.
+    assert!(metrics.contains("delay_timer_tasks 0\n"));
+    assert!(!metrics.contains("delay_timer_running_instances{task_id=\"1\"}"));
+
+    Ok(())
+}
+
+#[cfg(feature = "admin-api")]
+#[test]
+fn test_admin_api() -> AnyResult<()> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+
+    // Send a request to the admin api, return the status code and body.
+    fn request(addr: SocketAddr, method: &str, path: &str) -> AnyResult<(u16, String)> {
+        let mut stream = TcpStream::connect(addr)?;
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            method, path
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let status = response[9..12].parse()?;
+        let body = response
+            .find("\r\n\r\n")
+            .map(|index| response[index + 4..].to_string())
+            .unwrap_or_default();
+        Ok((status, body))
+    }
+
+    let delay_timer = DelayTimerBuilder::default()
+        .admin_api(([127, 0, 0, 1], 0))
+        .build();
+    let addr = delay_timer.admin_api_addr().unwrap();
+
+    let task = TaskBuilder::default()
+        .set_frequency(Frequency::Repeated("@hourly"))
+        .set_task_id(1)
+        .set_name("report")
+        .add_label("team", "billing")
+        .spawn(|_| create_default_delay_task_handler())?;
+    delay_timer.add_task(task)?;
+    park_timeout(Duration::from_millis(100));
+
+    let (status, body) = request(addr, "GET", "/tasks?label=team:billing&count=2")?;
+    assert_eq!(status, 200);
+    assert!(body.contains("\"task_id\":1"));
+    assert!(body.contains("\"name\":\"report\""));
+    assert_eq!(body.matches("next_fire_times").count(), 1);
+
+    let (status, body) = request(addr, "GET", "/tasks?label=team:ops")?;
+    assert_eq!((status, body.as_str()), (200, "[]"));
+
+    let (status, body) = request(addr, "GET", "/tasks/2")?;
+    assert_eq!(status, 404);
+    assert!(body.contains("Task 2 is not found."));
+
+    let (status, _) = request(addr, "GET", "/tasks/1/history?state=completed&limit=3")?;
+    assert_eq!(status, 200);
+    let (status, _) = request(addr, "GET", "/tasks/1/history?state=unknown")?;
+    assert_eq!(status, 400);
+
+    // Subscribe the public events of task 1 before pausing it.
+    let mut event_stream = TcpStream::connect(addr)?;
+    event_stream.set_read_timeout(Some(Duration::from_secs(3)))?;
+    write!(event_stream, "GET /events?task_id=1 HTTP/1.1\r\n\r\n")?;
+    let mut event_stream = BufReader::new(event_stream);
+    let mut line = String::new();
+    event_stream.read_line(&mut line)?;
+    assert_eq!(line, "HTTP/1.1 200 OK\r\n");
+    while line != "\r\n" {
+        line.clear();
+        event_stream.read_line(&mut line)?;
+    }
+
+    let (status, _) = request(addr, "POST", "/tasks/1/pause")?;
+    assert_eq!(status, 204);
+    park_timeout(Duration::from_millis(100));
+    assert!(delay_timer.get_task_info(1).unwrap().paused);
+
+    line.clear();
+    event_stream.read_line(&mut line)?;
+    assert_eq!(line, "event: pause_task\n");
+    line.clear();
+    event_stream.read_line(&mut line)?;
+    assert!(line.starts_with("data: ") && line.contains("\"task_id\":1"));
+
+    let (status, _) = request(addr, "POST", "/tasks/1/resume")?;
+    assert_eq!(status, 204);
+
+    let (status, _) = request(addr, "DELETE", "/tasks/1")?;
+    assert_eq!(status, 204);
+    let (status, body) = request(addr, "GET", "/tasks")?;
+    assert_eq!((status, body.as_str()), (200, "[]"));
+
+    let (status, _) = request(addr, "POST", "/tasks/1/advance")?;
+    assert_eq!(status, 404);
+
+    // The listener is closed after the timer is stopped.
+    delay_timer.stop_delay_timer()?;
+    park_timeout(Duration::from_secs(1));
+    assert!(TcpStream::connect(addr).is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "tracing-support")]
+#[test]
+fn test_tracing_instance_span() -> AnyResult<()> {
+    use std::sync::Mutex;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Collect the span names and the messages of events with their task_id.
+    #[derive(Default)]
+    struct Collector {
+        next_id: AtomicU64,
+        records: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct MessageVisitor(String);
+
+    impl Visit for MessageVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!("{}={:?};", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut visitor = MessageVisitor(format!("span {}:", span.metadata().name()));
+            span.record(&mut visitor);
+            self.records.lock().unwrap().push(visitor.0);
+            Id::from_u64(self.next_id.fetch_add(1, Release) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut visitor = MessageVisitor(String::from("event:"));
+            event.record(&mut visitor);
+            self.records.lock().unwrap().push(visitor.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    let collector = Collector::default();
+    let records = collector.records.clone();
+    tracing::subscriber::set_global_default(collector)?;
+
+    let delay_timer = DelayTimer::new();
+    let body = create_async_fn_body!({
+        tracing::info!("Inside the instance.");
+    });
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Once(CandyCron::Secondly))
+        .set_task_id(1)
+        .set_name("traced")
+        .spawn(body)?;
+    delay_timer.add_task(task)?;
+
+    park_timeout(Duration::from_secs_f32(2.5));
+
+    let records = records.lock().unwrap();
+    let contains = |pattern: &str| records.iter().any(|record| record.contains(pattern));
+    assert!(contains("span task_instance:task_id=1;"));
+    assert!(contains("name=\"traced\";"));
+    assert!(contains("message=Instance dispatched.;task_id=1;"));
+    assert!(contains("message=Inside the instance.;"));
+    assert!(contains("message=Instance completed.;task_id=1;"));
+
+    Ok(())
+}
+
+#[test]
+fn test_bounded_task_instances_chain() -> AnyResult<()> {
+    let delay_timer = DelayTimerBuilder::default()
+        .timer_event_channel_capacity(64)
+        .task_instances_chain_capacity(1, OverflowPolicy::DropOldest)
+        .build();
+
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::CountDown(3, CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn(|_| create_default_delay_task_handler())?;
+    let task_instances_chain = delay_timer.insert_task_with_ack(task)?;
+
+    park_timeout(Duration::from_secs_f32(3.5));
+
+    // Only the newest instance is kept in the chain.
+    assert!(task_instances_chain.next().is_ok());
+    assert!(task_instances_chain.next().is_err());
+
+    let delay_timer = DelayTimerBuilder::default()
+        .task_instances_chain_capacity(1, OverflowPolicy::Block)
+        .build();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::CountDown(3, CandyCron::Secondly))
+        .set_task_id(1)
+        .spawn(|_| create_default_delay_task_handler())?;
+    let task_instances_chain = delay_timer.insert_task_with_ack(task)?;
+
+    park_timeout(Duration::from_secs_f32(3.5));
+
+    // The timer is blocked by the full chain, but the task can still be accessed.
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let inspector = delay_timer.clone();
+    thread::spawn(move || sender.send(inspector.get_task_info(1).is_some()));
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(true));
+    assert!(task_instances_chain.next().is_ok());
+
+    Ok(())
+}
+
+#[cfg(feature = "job-store")]
+#[test]
+fn test_restore_tasks_from_job_store() -> AnyResult<()> {
+    let path = std::env::temp_dir().join(format!(
+        "delay_timer_restore_tasks_{}.json",
+        get_timestamp_micros()
+    ));
+    let restorer =
+        |record: &TaskRecord| record.restore(|_| create_default_delay_task_handler()).ok();
+
+    let delay_timer = DelayTimerBuilder::default()
+        .job_store(FileJobStore::new(&path, FileFormat::Json)?, restorer)
+        .build();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::CountDown(5, CandyCron::Secondly))
+        .set_task_id(1)
+        .set_name("restored")
+        .spawn(|_| create_default_delay_task_handler())?;
+    delay_timer.add_task_with_ack(task)?;
+
+    park_timeout(Duration::from_secs_f32(2.5));
+    delay_timer.stop_delay_timer()?;
+    park_timeout(Duration::from_secs_f32(1.2));
+
+    let records = FileJobStore::new(&path, FileFormat::Json)?.load()?;
+    assert_eq!(records.len(), 1);
+    assert!(records[0].remaining_count < Some(5));
+    assert!(records[0].last_fire_time.is_some());
+
+    let delay_timer = DelayTimerBuilder::default()
+        .enable_task_id_allocation()
+        .job_store(FileJobStore::new(&path, FileFormat::Json)?, restorer)
+        .build();
+    park_timeout(Duration::from_secs_f32(0.5));
+    assert_eq!(
+        delay_timer
+            .get_task_info(1)
+            .and_then(|task_info| task_info.name),
+        Some(String::from("restored"))
+    );
+
+    // The restored id is not allocated again.
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Hourly))
+        .spawn(|_| create_default_delay_task_handler())?;
+    assert_eq!(delay_timer.add_task(task)?, 2);
+
+    delay_timer.stop_delay_timer()?;
+    park_timeout(Duration::from_secs_f32(1.2));
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "job-store")]
+#[test]
+fn test_restore_missed_task() -> AnyResult<()> {
+    let path = std::env::temp_dir().join(format!(
+        "delay_timer_restore_missed_task_{}.json",
+        get_timestamp_micros()
+    ));
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Hourly))
+        .set_task_id(1)
+        .spawn(|_| create_default_delay_task_handler())?;
+    let mut record = TaskRecord::from(&task);
+    record.last_fire_time = Some(get_timestamp() - 7200);
+    FileJobStore::new(&path, FileFormat::Json)?.save(&record)?;
+
+    let share_num = Arc::new(AtomicUsize::new(0));
+    let share_num_bunshin = share_num.clone();
+    let restorer = move |record: &TaskRecord| {
+        let share_num = share_num_bunshin.clone();
+        record
+            .restore(move |_| {
+                share_num.fetch_add(1, Release);
+                create_default_delay_task_handler()
+            })
+            .ok()
+    };
+
+    // The task missed its fire while the process was down, so it runs once after it's restored.
+    let delay_timer = DelayTimerBuilder::default()
+        .job_store(FileJobStore::new(&path, FileFormat::Json)?, restorer)
+        .build();
+    park_timeout(Duration::from_secs_f32(2.5));
+    assert_eq!(share_num.load(Acquire), 1);
+
+    delay_timer.stop_delay_timer()?;
+    park_timeout(Duration::from_secs_f32(1.2));
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "task-spec")]
+#[test]
+fn test_task_spec() -> AnyResult<()> {
+    let shared_num = Arc::new(AtomicU64::new(0));
+    let shared_num_ref = shared_num.clone();
+
+    let delay_timer = DelayTimerBuilder::default()
+        .register_handler("add", move |_, payload| {
+            let step = payload["step"].as_u64().unwrap_or(1);
+            shared_num_ref.fetch_add(step, Release);
+            create_default_delay_task_handler()
+        })
+        .build();
+
+    let task_spec: TaskSpec = serde_json::from_str(
+        r#"{
+            "task_id": 1,
+            "handler": "add",
+            "payload": {"step": 2},
+            "frequency": {"CountDown": [3, "@secondly"]}
+        }"#,
+    )?;
+    assert_eq!(delay_timer.add_task_spec(&task_spec)?, 1);
+
+    let unknown_task_spec = TaskSpec {
+        handler: String::from("unknown"),
+        ..task_spec
+    };
+    assert!(matches!(
+        delay_timer.add_task_spec(&unknown_task_spec),
+        Err(TaskError::HandlerNotFound(_))
+    ));
+
+    park_timeout(Duration::from_secs_f32(4.5));
+    assert_eq!(shared_num.load(Acquire), 6);
+
+    Ok(())
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_reload_config() -> AnyResult<()> {
+    let path = std::env::temp_dir().join(format!(
+        "delay_timer_reload_config_{}.toml",
+        get_timestamp_micros()
+    ));
+    std::fs::write(
+        &path,
+        r#"
+        [[tasks]]
+        id = 1
+        name = "first"
+        expression = "@hourly"
+        handler = "noop"
+
+        [[tasks]]
+        id = 2
+        expression = "@daily"
+        shell = "echo hello"
+        "#,
+    )?;
+
+    let delay_timer = DelayTimerBuilder::default()
+        .register_handler("noop", |_, _| create_default_delay_task_handler())
+        .config_file(&path)
+        .config_reload_interval(Duration::from_secs(60))
+        .build();
+    park_timeout(Duration::from_secs_f32(0.5));
+    assert!(delay_timer.get_task_info(1).is_some());
+    assert!(delay_timer.get_task_info(2).is_some());
+
+    std::fs::write(
+        &path,
+        r#"
+        [[tasks]]
+        id = 1
+        name = "renamed"
+        expression = "@hourly"
+        handler = "noop"
+
+        [[tasks]]
+        id = 3
+        expression = "@daily"
+        shell = "echo hello"
+        "#,
+    )?;
+    let report = delay_timer.reload_config()?;
+    assert_eq!(report.added, vec![3]);
+    assert_eq!(report.updated, vec![1]);
+    assert_eq!(report.removed, vec![2]);
+
+    park_timeout(Duration::from_secs_f32(0.5));
+    assert!(delay_timer.get_task_info(2).is_none());
+    assert_eq!(
+        delay_timer
+            .get_task_info(1)
+            .and_then(|task_info| task_info.name),
+        Some(String::from("renamed"))
+    );
+
+    // An invalid file changes nothing.
+    std::fs::write(&path, "[[tasks]]\nid = 4\nexpression = \"@daily\"\n")?;
+    assert!(matches!(
+        delay_timer.reload_config(),
+        Err(ConfigError::InvalidBody(4))
+    ));
+    assert!(delay_timer.get_task_info(3).is_some());
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn test_retry_failed_config_reload() -> AnyResult<()> {
+    let path = std::env::temp_dir().join(format!(
+        "delay_timer_retry_config_{}.toml",
+        get_timestamp_micros()
+    ));
+    let task_config = r#"
+        [[tasks]]
+        id = 1
+        expression = "@hourly"
+        handler = "noop"
+        "#;
+    std::fs::write(&path, task_config)?;
+
+    let delay_timer = DelayTimerBuilder::default()
+        .register_handler("noop", |_, _| create_default_delay_task_handler())
+        .config_file(&path)
+        .config_reload_interval(Duration::from_millis(100))
+        .build();
+    let task = TaskBuilder::default()
+        .set_frequency_by_candy(CandyFrequency::Repeated(CandyCron::Hourly))
+        .set_task_id(2)
+        .spawn(|_| create_default_delay_task_handler())?;
+    delay_timer.add_task_with_ack(task)?;
+
+    // The task 2 can't be added until the task with the same id is removed.
+    std::fs::write(
+        &path,
+        format!(
+            "{}\n[[tasks]]\nid = 2\nname = \"config\"\nexpression = \"@hourly\"\nhandler = \"noop\"\n",
+            task_config
+        ),
+    )?;
+    park_timeout(Duration::from_secs_f32(0.5));
+    assert_eq!(delay_timer.get_task_info(2).unwrap().name, None);
+
+    delay_timer.remove_task_with_ack(2)?;
+    park_timeout(Duration::from_secs_f32(0.5));
+    assert_eq!(
+        delay_timer.get_task_info(2).unwrap().name,
+        Some(String::from("config"))
+    );
+
+    // The task removed by the api is restored, though its config isn't changed.
+    delay_timer.remove_task_with_ack(1)?;
+    assert_eq!(delay_timer.reload_config()?.added, vec![1]);
+    assert!(delay_timer.get_task_info(1).is_some());
+
+    // The task that is rejected by the timer is reported, and it's retried by the next reload.
+    std::fs::write(
+        &path,
+        format!(
+            "{}\n[[tasks]]\nid = 3\nexpression = \"0 0 0 1 1 * 2000\"\nhandler = \"noop\"\n",
+            task_config
+        ),
+    )?;
+    for _ in 0..2 {
+        assert!(matches!(
+            delay_timer.reload_config(),
+            Err(ConfigError::Task(3, TaskError::NoFutureFireTime(3)))
+        ));
+    }
+
+    delay_timer.stop_delay_timer()?;
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
 #[test]
 fn test_maximun_parallel_runable_num() -> AnyResult<()> {
     let delay_timer = DelayTimer::new();